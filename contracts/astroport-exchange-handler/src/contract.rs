@@ -1,18 +1,25 @@
 use astroport::asset::{Asset, AssetInfo};
-use astroport::pair::ExecuteMsg as PairExecuteMsg;
-use astroport::router::ExecuteMsg as RouterExecuteMsg;
-use astroport::router::SwapOperation;
+use astroport::oracle::QueryMsg as OracleQueryMsg;
+use astroport::pair::{ExecuteMsg as PairExecuteMsg, QueryMsg as PairQueryMsg, SimulationResponse};
+use astroport::router::{
+    ExecuteMsg as RouterExecuteMsg, QueryMsg as RouterQueryMsg, SimulateSwapOperationsResponse,
+    SwapOperation,
+};
 use cosmwasm_std::{
-    attr, entry_point, to_json_binary, Attribute, CosmosMsg, Deps, Uint128, WasmMsg,
+    attr, coin, entry_point, to_json_binary, Attribute, BankMsg, Coin, CosmosMsg, Decimal, Deps,
+    Uint128, Uint256, WasmMsg,
 };
-use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Response, StdError, StdResult};
 use cw2::set_contract_version;
 use drop_helpers::answer::{attr_coin, response};
 use drop_staking_base::error::astroport_exchange_handler::{ContractError, ContractResult};
 use drop_staking_base::msg::astroport_exchange_handler::{
     ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
 };
-use drop_staking_base::state::astroport_exchange_handler::{Config, CONFIG, SWAP_OPERATIONS};
+use drop_staking_base::msg::reward_handler::SimulateExchangeResponse;
+use drop_staking_base::state::astroport_exchange_handler::{
+    Config, PriceOracle, CONFIG, LEGACY_CONFIG, PRICE_ORACLE, SWAP_OPERATIONS,
+};
 
 const CONTRACT_NAME: &str = concat!("crates.io:drop-staking__", env!("CARGO_PKG_NAME"));
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -23,7 +30,7 @@ pub fn instantiate(
     _env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> ContractResult<Response> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     let owner = deps.api.addr_validate(&msg.owner)?;
     cw_ownable::initialize_owner(deps.storage, deps.api, Some(owner.as_ref()))?;
@@ -32,6 +39,7 @@ pub fn instantiate(
     let core_contract = deps.api.addr_validate(&msg.core_contract)?;
     let pair_contract = deps.api.addr_validate(&msg.pair_contract)?;
     let router_contract = deps.api.addr_validate(&msg.router_contract)?;
+    validate_max_spread(msg.max_spread)?;
 
     let config = Config {
         owner: msg.owner.clone(),
@@ -41,10 +49,17 @@ pub fn instantiate(
         router_contract: router_contract.to_string(),
         from_denom: msg.from_denom.clone(),
         min_rewards: msg.min_rewards,
+        max_spread: msg.max_spread,
+        max_chunk_size: msg.max_chunk_size.filter(|size| !size.is_zero()),
     };
 
     CONFIG.save(deps.storage, &config)?;
 
+    let mut attrs: Vec<Attribute> = Vec::new();
+    if let Some(oracle) = msg.price_oracle {
+        attrs.push(attr("price_oracle", save_price_oracle(deps, oracle)?));
+    }
+
     Ok(response(
         "instantiate",
         CONTRACT_NAME,
@@ -56,14 +71,23 @@ pub fn instantiate(
             attr("router_contract", msg.router_contract),
             attr("from_denom", msg.from_denom),
             attr("min_rewards", msg.min_rewards),
+            attr("max_spread", msg.max_spread.to_string()),
+            attr(
+                "max_chunk_size",
+                config
+                    .max_chunk_size
+                    .map_or("none".to_string(), |size| size.to_string()),
+            ),
         ],
-    ))
+    )
+    .add_attributes(attrs))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => query_config(deps, env),
+        QueryMsg::SimulateExchange { amount } => query_simulate_exchange(deps, amount),
     }
 }
 
@@ -71,6 +95,7 @@ fn query_config(deps: Deps, _env: Env) -> StdResult<Binary> {
     let config = CONFIG.load(deps.storage)?;
 
     let swap_operations = SWAP_OPERATIONS.may_load(deps.storage)?;
+    let price_oracle = PRICE_ORACLE.may_load(deps.storage)?;
 
     to_json_binary(&ConfigResponse {
         owner: config.owner,
@@ -80,14 +105,59 @@ fn query_config(deps: Deps, _env: Env) -> StdResult<Binary> {
         router_contract: config.router_contract,
         from_denom: config.from_denom,
         min_rewards: config.min_rewards,
+        max_spread: config.max_spread,
+        max_chunk_size: config.max_chunk_size,
         swap_operations,
+        price_oracle,
+    })
+}
+
+fn query_simulate_exchange(deps: Deps, amount: Coin) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    if amount.denom != config.from_denom {
+        return Err(StdError::generic_err(format!(
+            "Invalid denom {}, expected {}",
+            amount.denom, config.from_denom
+        )));
+    }
+
+    let return_amount = match SWAP_OPERATIONS.may_load(deps.storage)? {
+        Some(operations) => {
+            let simulation: SimulateSwapOperationsResponse = deps.querier.query_wasm_smart(
+                &config.router_contract,
+                &RouterQueryMsg::SimulateSwapOperations {
+                    offer_amount: amount.amount,
+                    operations,
+                },
+            )?;
+            simulation.amount
+        }
+        None => {
+            let simulation: SimulationResponse = deps.querier.query_wasm_smart(
+                &config.pair_contract,
+                &PairQueryMsg::Simulation {
+                    offer_asset: Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: amount.denom,
+                        },
+                        amount: amount.amount,
+                    },
+                    ask_asset_info: None,
+                },
+            )?;
+            simulation.return_amount
+        }
+    };
+
+    to_json_binary(&SimulateExchangeResponse {
+        amount: return_amount,
     })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    env: Env,
+    _env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> ContractResult<Response> {
@@ -100,6 +170,8 @@ pub fn execute(
             pair_contract,
             from_denom,
             min_rewards,
+            max_spread,
+            max_chunk_size,
         } => exec_update_config(
             deps,
             info,
@@ -110,11 +182,14 @@ pub fn execute(
             pair_contract,
             from_denom,
             min_rewards,
+            max_spread,
+            max_chunk_size,
         ),
-        ExecuteMsg::Exchange {} => exec_exchange(deps, env),
+        ExecuteMsg::Exchange {} => exec_exchange(deps, info),
         ExecuteMsg::UpdateSwapOperations { operations } => {
             exec_update_swap_operations(deps, info, operations)
         }
+        ExecuteMsg::UpdatePriceOracle { oracle } => exec_update_price_oracle(deps, info, oracle),
     }
 }
 
@@ -129,6 +204,8 @@ fn exec_update_config(
     pair_contract: Option<String>,
     from_denom: Option<String>,
     min_rewards: Option<Uint128>,
+    max_spread: Option<Decimal>,
+    max_chunk_size: Option<Uint128>,
 ) -> ContractResult<Response> {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
@@ -176,19 +253,37 @@ fn exec_update_config(
         attrs.push(attr("min_rewards", min_rewards))
     }
 
+    if let Some(max_spread) = max_spread {
+        validate_max_spread(max_spread)?;
+        config.max_spread = max_spread;
+        attrs.push(attr("max_spread", max_spread.to_string()))
+    }
+
+    if let Some(max_chunk_size) = max_chunk_size {
+        config.max_chunk_size = Some(max_chunk_size).filter(|size| !size.is_zero());
+        attrs.push(attr("max_chunk_size", max_chunk_size))
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     Ok(response("config_update", CONTRACT_NAME, attrs))
 }
 
-fn exec_exchange(deps: DepsMut, env: Env) -> ContractResult<Response> {
+fn exec_exchange(deps: DepsMut, info: MessageInfo) -> ContractResult<Response> {
     let swap_operations = SWAP_OPERATIONS.may_load(deps.storage)?;
     let config = CONFIG.load(deps.storage)?;
 
     let from_denom = config.from_denom;
-    let balance = deps
-        .querier
-        .query_balance(env.contract.address, from_denom.clone())?;
+    // only the attached funds are swapped, the proceeds go back to the caller
+    let balance = coin(
+        info.funds
+            .iter()
+            .filter(|fund| fund.denom == from_denom)
+            .map(|fund| fund.amount)
+            .sum::<Uint128>()
+            .u128(),
+        from_denom.clone(),
+    );
 
     if balance.amount < config.min_rewards {
         return Err(ContractError::LowBalance {
@@ -198,62 +293,189 @@ fn exec_exchange(deps: DepsMut, env: Env) -> ContractResult<Response> {
         });
     }
 
+    // Large balances are swapped in chunks over several ticks to limit the
+    // price impact of a single swap. The remainder is returned to the caller.
+    let swap_amount = match config.max_chunk_size {
+        Some(max_chunk_size) => balance.amount.min(max_chunk_size),
+        None => balance.amount,
+    };
+    let swap_coin = coin(swap_amount.u128(), from_denom.clone());
+    let remainder = balance.amount - swap_amount;
+
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: from_denom.clone(),
+        },
+        amount: swap_amount,
+    };
+
     let mut msgs: Vec<CosmosMsg> = Vec::new();
     let mut attrs: Vec<Attribute> = Vec::new();
 
     if let Some(swap_operations) = swap_operations {
         let router_contract_address = config.router_contract;
 
+        let simulation: SimulateSwapOperationsResponse = deps.querier.query_wasm_smart(
+            &router_contract_address,
+            &RouterQueryMsg::SimulateSwapOperations {
+                offer_amount: swap_amount,
+                operations: swap_operations.clone(),
+            },
+        )?;
+        let route_asset = swap_operations.last().map(|operation| match operation {
+            SwapOperation::NativeSwap { ask_denom, .. } => AssetInfo::NativeToken {
+                denom: ask_denom.to_string(),
+            },
+            SwapOperation::AstroSwap { ask_asset_info, .. } => ask_asset_info.clone(),
+        });
+        check_simulated_return(
+            deps.as_ref(),
+            &swap_coin,
+            simulation.amount,
+            route_asset.as_ref(),
+        )?;
+        let minimum_receive = simulation.amount * (Decimal::one() - config.max_spread);
+
         let exchange_rewards_msg = CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: router_contract_address.clone(),
             msg: to_json_binary(&RouterExecuteMsg::ExecuteSwapOperations {
                 operations: swap_operations,
-                minimum_receive: None,
-                to: Some(config.core_contract),
-                max_spread: None,
+                minimum_receive: Some(minimum_receive),
+                to: Some(info.sender.to_string()),
+                max_spread: Some(config.max_spread),
             })?,
-            funds: vec![balance.clone()],
+            funds: vec![swap_coin.clone()],
         });
 
         msgs.push(exchange_rewards_msg);
-        attrs.push(attr("router_contract", router_contract_address))
+        attrs.push(attr("router_contract", router_contract_address));
+        attrs.push(attr("minimum_receive", minimum_receive));
     } else {
         let pair_contract = config.pair_contract;
 
+        let simulation: SimulationResponse = deps.querier.query_wasm_smart(
+            &pair_contract,
+            &PairQueryMsg::Simulation {
+                offer_asset: offer_asset.clone(),
+                ask_asset_info: None,
+            },
+        )?;
+        check_simulated_return(deps.as_ref(), &swap_coin, simulation.return_amount, None)?;
+        let belief_price = Decimal::from_ratio(
+            swap_amount,
+            simulation.return_amount + simulation.spread_amount + simulation.commission_amount,
+        );
+
         let exchange_rewards_msg = CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: pair_contract.to_string(),
             msg: to_json_binary(&PairExecuteMsg::Swap {
-                offer_asset: Asset {
-                    info: AssetInfo::NativeToken {
-                        denom: from_denom.clone(),
-                    },
-                    amount: balance.amount,
-                },
+                offer_asset,
                 ask_asset_info: None,
-                belief_price: None,
-                max_spread: None,
-                to: Some(config.core_contract),
+                belief_price: Some(belief_price),
+                max_spread: Some(config.max_spread),
+                to: Some(info.sender.to_string()),
             })?,
-            funds: vec![balance.clone()],
+            funds: vec![swap_coin.clone()],
         });
 
         msgs.push(exchange_rewards_msg);
-        attrs.push(attr("pair_contract", pair_contract))
+        attrs.push(attr("pair_contract", pair_contract));
+        attrs.push(attr("belief_price", belief_price.to_string()));
+    }
+
+    if !remainder.is_zero() {
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![coin(remainder.u128(), from_denom.clone())],
+        }));
+        attrs.push(attr_coin("remainder", remainder, from_denom));
     }
 
     Ok(response(
         "exchange",
         CONTRACT_NAME,
-        [attr_coin(
-            "swap_amount",
-            balance.amount,
-            balance.denom.clone(),
-        )],
+        [attr_coin("swap_amount", swap_coin.amount, swap_coin.denom)],
     )
     .add_messages(msgs)
     .add_attributes(attrs))
 }
 
+/// Rejects the swap when the simulated return is empty or, if a price oracle
+/// is configured, falls below the TWAP by more than the allowed deviation.
+/// A route is only compared with an oracle quoting the asset it ends in.
+fn check_simulated_return(
+    deps: Deps,
+    offer: &Coin,
+    simulated_return: Uint128,
+    route_asset: Option<&AssetInfo>,
+) -> ContractResult<()> {
+    if simulated_return.is_zero() {
+        return Err(ContractError::ZeroSimulatedReturn {
+            amount: offer.amount,
+            denom: offer.denom.clone(),
+        });
+    }
+
+    if let Some(oracle) = PRICE_ORACLE.may_load(deps.storage)? {
+        let twap: Vec<(AssetInfo, Uint256)> = deps.querier.query_wasm_smart(
+            &oracle.contract,
+            &OracleQueryMsg::Consult {
+                token: AssetInfo::NativeToken {
+                    denom: offer.denom.clone(),
+                },
+                amount: offer.amount,
+            },
+        )?;
+        let (oracle_asset, twap_return) =
+            twap.first()
+                .ok_or_else(|| ContractError::EmptyOracleConsult {
+                    denom: offer.denom.clone(),
+                })?;
+        if let Some(route_asset) = route_asset {
+            if oracle_asset != route_asset {
+                return Err(ContractError::OracleAssetMismatch {
+                    oracle_asset: oracle_asset.to_string(),
+                    route_asset: route_asset.to_string(),
+                });
+            }
+        }
+        let twap_return = Uint128::try_from(*twap_return).map_err(StdError::from)?;
+        let min_return = twap_return * (Decimal::one() - oracle.max_deviation);
+        if simulated_return < min_return {
+            return Err(ContractError::PriceDeviation {
+                min_return,
+                simulated_return,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_max_spread(max_spread: Decimal) -> ContractResult<()> {
+    if max_spread >= Decimal::one() {
+        return Err(ContractError::InvalidMaxSpread { max_spread });
+    }
+    Ok(())
+}
+
+fn save_price_oracle(deps: DepsMut, oracle: PriceOracle) -> ContractResult<String> {
+    if oracle.max_deviation >= Decimal::one() {
+        return Err(ContractError::InvalidMaxDeviation {
+            max_deviation: oracle.max_deviation,
+        });
+    }
+    let contract = deps.api.addr_validate(&oracle.contract)?;
+    PRICE_ORACLE.save(
+        deps.storage,
+        &PriceOracle {
+            contract: contract.to_string(),
+            max_deviation: oracle.max_deviation,
+        },
+    )?;
+    Ok(contract.to_string())
+}
+
 fn exec_update_swap_operations(
     deps: DepsMut,
     info: MessageInfo,
@@ -271,8 +493,53 @@ fn exec_update_swap_operations(
     Ok(response("update_swap_operations", CONTRACT_NAME, attrs))
 }
 
+fn exec_update_price_oracle(
+    deps: DepsMut,
+    info: MessageInfo,
+    oracle: Option<PriceOracle>,
+) -> ContractResult<Response> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let mut attrs = vec![];
+    if let Some(oracle) = oracle {
+        let max_deviation = oracle.max_deviation;
+        attrs.push(attr("price_oracle", save_price_oracle(deps, oracle)?));
+        attrs.push(attr("max_deviation", max_deviation.to_string()));
+    } else {
+        PRICE_ORACLE.remove(deps.storage);
+        attrs.push(attr("clear_price_oracle", "1".to_string()));
+    }
+    Ok(response("update_price_oracle", CONTRACT_NAME, attrs))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> ContractResult<Response> {
     deps.api.debug("WASMDEBUG: migrate");
-    Ok(Response::default())
+    validate_max_spread(msg.max_spread)?;
+    let config = match CONFIG.may_load(deps.storage) {
+        Ok(Some(config)) => Config {
+            max_spread: msg.max_spread,
+            ..config
+        },
+        // configs saved before the slippage guard have no max_spread
+        _ => {
+            let legacy_config = LEGACY_CONFIG.load(deps.storage)?;
+            Config {
+                owner: legacy_config.owner,
+                core_contract: legacy_config.core_contract,
+                cron_address: legacy_config.cron_address,
+                router_contract: legacy_config.router_contract,
+                pair_contract: legacy_config.pair_contract,
+                from_denom: legacy_config.from_denom,
+                min_rewards: legacy_config.min_rewards,
+                max_spread: msg.max_spread,
+                max_chunk_size: None,
+            }
+        }
+    };
+    CONFIG.save(deps.storage, &config)?;
+    Ok(response(
+        "migrate",
+        CONTRACT_NAME,
+        [attr("max_spread", msg.max_spread.to_string())],
+    ))
 }
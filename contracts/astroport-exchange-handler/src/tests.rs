@@ -1,19 +1,26 @@
-use crate::contract::instantiate;
+use crate::contract::{instantiate, migrate};
 
 use astroport::asset::AssetInfo;
-use astroport::pair::ExecuteMsg as PairExecuteMsg;
-use astroport::router::{ExecuteMsg as RouterExecuteMsg, SwapOperation};
+use astroport::oracle::QueryMsg as OracleQueryMsg;
+use astroport::pair::{ExecuteMsg as PairExecuteMsg, QueryMsg as PairQueryMsg, SimulationResponse};
+use astroport::router::{
+    ExecuteMsg as RouterExecuteMsg, QueryMsg as RouterQueryMsg, SimulateSwapOperationsResponse,
+    SwapOperation,
+};
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 use cosmwasm_std::{
-    attr, coins, to_json_binary, Addr, Attribute, Coin, Empty, Event, Response, StdError,
-    StdResult, Uint128,
+    attr, coins, to_json_binary, Addr, Attribute, Coin, Decimal, Empty, Event, Response, StdError,
+    StdResult, Uint128, Uint256,
 };
-use cw_multi_test::{custom_app, App, Contract, ContractWrapper, Executor};
+use cw_multi_test::{custom_app, App, AppResponse, Contract, ContractWrapper, Executor};
 use drop_helpers::answer::response;
 use drop_staking_base::msg::astroport_exchange_handler::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg,
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
 };
 use drop_staking_base::msg::rewards_manager::QueryMsg;
+use drop_staking_base::state::astroport_exchange_handler::{
+    LegacyConfig, PriceOracle, CONFIG, LEGACY_CONFIG,
+};
 
 const CORE_CONTRACT_ADDR: &str = "core_contract";
 const OWNER_CONTRACT_ADDR: &str = "owner_contract";
@@ -21,6 +28,15 @@ const CRON_ADDR: &str = "cron_address";
 
 const SENDER_ADDR: &str = "sender";
 
+fn find_event(res: &AppResponse, ty: &str) -> Vec<Attribute> {
+    res.events
+        .iter()
+        .find(|event| event.ty == ty)
+        .unwrap()
+        .attributes[1..]
+        .to_vec()
+}
+
 fn instantiate_contract(
     app: &mut App,
     contract: fn() -> Box<dyn Contract<Empty>>,
@@ -39,56 +55,69 @@ fn instantiate_contract(
 }
 
 fn pair_contract() -> Box<dyn Contract<Empty>> {
-    let contract: ContractWrapper<PairExecuteMsg, Empty, Empty, StdError, StdError, StdError> =
-        ContractWrapper::new(
-            |_, _, info, msg: PairExecuteMsg| {
-                match msg {
-                    PairExecuteMsg::Swap {
-                        offer_asset,
-                        ask_asset_info,
-                        belief_price,
-                        max_spread,
-                        to,
-                    } => {
-                        if !info.funds.is_empty() {
-                            let asset_denom = match offer_asset.info {
-                                AssetInfo::NativeToken { denom } => denom,
-                                _ => {
-                                    return Err(StdError::generic_err("Wrong token type"));
-                                }
-                            };
-
-                            return Ok(response(
-                                "pair_contract_execute",
-                                "pair_mock",
-                                [
-                                    attr("message", "PairExecuteMsg::Swap".to_string()),
-                                    attr("to", to.unwrap().to_string()),
-                                    attr("ask_asset_info", ask_asset_info.is_some().to_string()),
-                                    attr("belief_price", belief_price.is_some().to_string()),
-                                    attr("max_spread", max_spread.is_some().to_string()),
-                                    attr(
-                                        "offer_asset",
-                                        format!("{}{}", offer_asset.amount, asset_denom),
-                                    ),
-                                    attr(
-                                        "funds_received",
-                                        format!("{}{}", info.funds[0].amount, info.funds[0].denom),
-                                    ),
-                                ],
-                            ));
-                        }
-                    }
-                    _ => {
-                        return Err(StdError::generic_err("Wrong execution call"));
+    let contract: ContractWrapper<
+        PairExecuteMsg,
+        Empty,
+        PairQueryMsg,
+        StdError,
+        StdError,
+        StdError,
+    > = ContractWrapper::new(
+        |_, _, info, msg: PairExecuteMsg| {
+            match msg {
+                PairExecuteMsg::Swap {
+                    offer_asset,
+                    ask_asset_info,
+                    belief_price,
+                    max_spread,
+                    to,
+                } => {
+                    if !info.funds.is_empty() {
+                        let asset_denom = match offer_asset.info {
+                            AssetInfo::NativeToken { denom } => denom,
+                            _ => {
+                                return Err(StdError::generic_err("Wrong token type"));
+                            }
+                        };
+
+                        return Ok(response(
+                            "pair_contract_execute",
+                            "pair_mock",
+                            [
+                                attr("message", "PairExecuteMsg::Swap".to_string()),
+                                attr("to", to.unwrap().to_string()),
+                                attr("ask_asset_info", ask_asset_info.is_some().to_string()),
+                                attr("belief_price", belief_price.is_some().to_string()),
+                                attr("max_spread", max_spread.is_some().to_string()),
+                                attr(
+                                    "offer_asset",
+                                    format!("{}{}", offer_asset.amount, asset_denom),
+                                ),
+                                attr(
+                                    "funds_received",
+                                    format!("{}{}", info.funds[0].amount, info.funds[0].denom),
+                                ),
+                            ],
+                        ));
                     }
                 }
+                _ => {
+                    return Err(StdError::generic_err("Wrong execution call"));
+                }
+            }
 
-                Err(StdError::generic_err("Wrong execution call"))
-            },
-            |_, _, _, _: Empty| Ok(Response::new()),
-            |_, _, _: Empty| to_json_binary(&{}),
-        );
+            Err(StdError::generic_err("Wrong execution call"))
+        },
+        |_, _, _, _: Empty| Ok(Response::new()),
+        |_, _, msg: PairQueryMsg| match msg {
+            PairQueryMsg::Simulation { offer_asset, .. } => to_json_binary(&SimulationResponse {
+                return_amount: offer_asset.amount,
+                spread_amount: Uint128::zero(),
+                commission_amount: Uint128::zero(),
+            }),
+            _ => Err(StdError::generic_err("Wrong query call")),
+        },
+    );
     Box::new(contract)
 }
 
@@ -97,54 +126,67 @@ fn instantiate_pair_contract(app: &mut App) -> Addr {
 }
 
 fn router_contract() -> Box<dyn Contract<Empty>> {
-    let contract: ContractWrapper<RouterExecuteMsg, Empty, Empty, StdError, StdError, StdError> =
-        ContractWrapper::new(
-            |_, _, info, msg: RouterExecuteMsg| {
-                match msg {
-                    RouterExecuteMsg::ExecuteSwapOperations {
-                        operations,
-                        minimum_receive,
-                        max_spread,
-                        to,
-                    } => {
-                        if !info.funds.is_empty() {
-                            return Ok(response(
-                                "router_contract_execute",
-                                "router_mock",
-                                [
-                                    attr(
-                                        "message",
-                                        "RouterExecuteMsg::ExecuteSwapOperations".to_string(),
-                                    ),
-                                    attr("to", to.unwrap().to_string()),
-                                    attr("minimum_receive", minimum_receive.is_some().to_string()),
-                                    attr("max_spread", max_spread.is_some().to_string()),
-                                    attr(
-                                        "funds_received",
-                                        format!("{}{}", info.funds[0].amount, info.funds[0].denom),
-                                    ),
-                                    attr(
-                                        "operation1",
-                                        get_swap_operation(operations[0].clone()).unwrap(),
-                                    ),
-                                    attr(
-                                        "operation2",
-                                        get_swap_operation(operations[1].clone()).unwrap(),
-                                    ),
-                                ],
-                            ));
-                        }
-                    }
-                    _ => {
-                        return Err(StdError::generic_err("Wrong execution call"));
+    let contract: ContractWrapper<
+        RouterExecuteMsg,
+        Empty,
+        RouterQueryMsg,
+        StdError,
+        StdError,
+        StdError,
+    > = ContractWrapper::new(
+        |_, _, info, msg: RouterExecuteMsg| {
+            match msg {
+                RouterExecuteMsg::ExecuteSwapOperations {
+                    operations,
+                    minimum_receive,
+                    max_spread,
+                    to,
+                } => {
+                    if !info.funds.is_empty() {
+                        return Ok(response(
+                            "router_contract_execute",
+                            "router_mock",
+                            [
+                                attr(
+                                    "message",
+                                    "RouterExecuteMsg::ExecuteSwapOperations".to_string(),
+                                ),
+                                attr("to", to.unwrap().to_string()),
+                                attr("minimum_receive", minimum_receive.is_some().to_string()),
+                                attr("max_spread", max_spread.is_some().to_string()),
+                                attr(
+                                    "funds_received",
+                                    format!("{}{}", info.funds[0].amount, info.funds[0].denom),
+                                ),
+                                attr(
+                                    "operation1",
+                                    get_swap_operation(operations[0].clone()).unwrap(),
+                                ),
+                                attr(
+                                    "operation2",
+                                    get_swap_operation(operations[1].clone()).unwrap(),
+                                ),
+                            ],
+                        ));
                     }
                 }
+                _ => {
+                    return Err(StdError::generic_err("Wrong execution call"));
+                }
+            }
 
-                Err(StdError::generic_err("Wrong execution call"))
-            },
-            |_, _, _, _: Empty| Ok(Response::new()),
-            |_, _, _: Empty| to_json_binary(&{}),
-        );
+            Err(StdError::generic_err("Wrong execution call"))
+        },
+        |_, _, _, _: Empty| Ok(Response::new()),
+        |_, _, msg: RouterQueryMsg| match msg {
+            RouterQueryMsg::SimulateSwapOperations { offer_amount, .. } => {
+                to_json_binary(&SimulateSwapOperationsResponse {
+                    amount: offer_amount,
+                })
+            }
+            _ => Err(StdError::generic_err("Wrong query call")),
+        },
+    );
     Box::new(contract)
 }
 
@@ -237,6 +279,8 @@ fn test_initialization() {
             Attribute::new("router_contract".to_string(), "router_contract".to_string()),
             Attribute::new("from_denom".to_string(), "ueth".to_string()),
             Attribute::new("min_rewards".to_string(), Uint128::one()),
+            Attribute::new("max_spread".to_string(), "0.01".to_string()),
+            Attribute::new("max_chunk_size".to_string(), "none".to_string()),
         ])]
     );
 }
@@ -258,6 +302,9 @@ fn test_config_query() {
             router_contract: "router_contract".to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: None,
         },
     );
 
@@ -276,7 +323,10 @@ fn test_config_query() {
             router_contract: "router_contract".to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
             swap_operations: None,
+            price_oracle: None,
         }
     );
 }
@@ -285,8 +335,6 @@ fn test_config_query() {
 fn test_exchange_through_pair_call() {
     let mut app = mock_app();
 
-    let sender_address = Addr::unchecked(SENDER_ADDR);
-
     let pair_contract = instantiate_pair_contract(&mut app);
     let router_contract = instantiate_router_contract(&mut app);
 
@@ -303,37 +351,33 @@ fn test_exchange_through_pair_call() {
             router_contract: router_contract.to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: None,
         },
     );
 
     let amount = coins(100, "ueth");
-    let _ = app
-        .send_tokens(sender_address, astroport_handler_contract.clone(), &amount)
-        .unwrap();
 
     let res = app
         .execute_contract(
-            Addr::unchecked(CORE_CONTRACT_ADDR),
+            Addr::unchecked(SENDER_ADDR),
             astroport_handler_contract.clone(),
             &ExecuteMsg::Exchange {},
-            &[],
+            &amount,
         )
         .unwrap();
 
-    let ty = res.events[4].ty.clone();
-
-    assert_eq!(ty, "wasm-pair_mock-pair_contract_execute".to_string());
-
-    let attrs = res.events[4].attributes[1..].to_vec();
+    let attrs = find_event(&res, "wasm-pair_mock-pair_contract_execute");
 
     assert_eq!(
         attrs,
         vec![
             Attribute::new("message".to_string(), "PairExecuteMsg::Swap".to_string()),
-            Attribute::new("to".to_string(), CORE_CONTRACT_ADDR.to_string()),
+            Attribute::new("to".to_string(), SENDER_ADDR.to_string()),
             Attribute::new("ask_asset_info".to_string(), "false".to_string()),
-            Attribute::new("belief_price".to_string(), "false".to_string()),
-            Attribute::new("max_spread".to_string(), "false".to_string()),
+            Attribute::new("belief_price".to_string(), "true".to_string()),
+            Attribute::new("max_spread".to_string(), "true".to_string()),
             Attribute::new("offer_asset".to_string(), "100ueth".to_string()),
             Attribute::new("funds_received".to_string(), "100ueth".to_string()),
         ]
@@ -344,8 +388,6 @@ fn test_exchange_through_pair_call() {
 fn test_exchange_through_router_call() {
     let mut app = mock_app();
 
-    let sender_address = Addr::unchecked(SENDER_ADDR);
-
     let pair_contract = instantiate_pair_contract(&mut app);
     let router_contract = instantiate_router_contract(&mut app);
 
@@ -362,13 +404,13 @@ fn test_exchange_through_router_call() {
             router_contract: router_contract.to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: None,
         },
     );
 
     let amount = coins(100, "ueth");
-    let _ = app
-        .send_tokens(sender_address, astroport_handler_contract.clone(), &amount)
-        .unwrap();
 
     let _res = app
         .execute_contract(
@@ -392,18 +434,14 @@ fn test_exchange_through_router_call() {
 
     let res = app
         .execute_contract(
-            Addr::unchecked(CORE_CONTRACT_ADDR),
+            Addr::unchecked(SENDER_ADDR),
             astroport_handler_contract.clone(),
             &ExecuteMsg::Exchange {},
-            &[],
+            &amount,
         )
         .unwrap();
 
-    let ty = res.events[4].ty.clone();
-
-    assert_eq!(ty, "wasm-router_mock-router_contract_execute".to_string());
-
-    let attrs = res.events[4].attributes[1..].to_vec();
+    let attrs = find_event(&res, "wasm-router_mock-router_contract_execute");
 
     assert_eq!(
         attrs,
@@ -412,9 +450,9 @@ fn test_exchange_through_router_call() {
                 "message".to_string(),
                 "RouterExecuteMsg::ExecuteSwapOperations".to_string()
             ),
-            Attribute::new("to".to_string(), CORE_CONTRACT_ADDR.to_string()),
-            Attribute::new("minimum_receive".to_string(), "false".to_string()),
-            Attribute::new("max_spread".to_string(), "false".to_string()),
+            Attribute::new("to".to_string(), SENDER_ADDR.to_string()),
+            Attribute::new("minimum_receive".to_string(), "true".to_string()),
+            Attribute::new("max_spread".to_string(), "true".to_string()),
             Attribute::new("funds_received".to_string(), "100ueth".to_string()),
             Attribute::new("operation1".to_string(), "ueth/untrn".to_string()),
             Attribute::new("operation2".to_string(), "untrn/ueth".to_string()),
@@ -426,8 +464,6 @@ fn test_exchange_through_router_call() {
 fn test_not_enough_balance_error() {
     let mut app = mock_app();
 
-    let sender_address = Addr::unchecked(SENDER_ADDR);
-
     let astroport_exchange_handler_code_id = app.store_code(astroport_handler_contract());
 
     let astroport_handler_contract = instantiate_astroport_handler_contract(
@@ -441,19 +477,19 @@ fn test_not_enough_balance_error() {
             router_contract: "router_contract".to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::from(200u128),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: None,
         },
     );
 
     let amount = coins(100, "ueth");
-    let _ = app
-        .send_tokens(sender_address, astroport_handler_contract.clone(), &amount)
-        .unwrap();
 
     let res = app.execute_contract(
-        Addr::unchecked(CORE_CONTRACT_ADDR),
+        Addr::unchecked(SENDER_ADDR),
         astroport_handler_contract.clone(),
         &ExecuteMsg::Exchange {},
-        &[],
+        &amount,
     );
 
     let unwrapped_err = res.unwrap_err();
@@ -483,6 +519,9 @@ fn test_unauthorized_router_call() {
             router_contract: "router_contract".to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: None,
         },
     );
 
@@ -533,6 +572,9 @@ fn test_unauthorized_config_update() {
             router_contract: "router_contract".to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: None,
         },
     );
 
@@ -547,6 +589,8 @@ fn test_unauthorized_config_update() {
             router_contract: Some("router_contract".to_string()),
             from_denom: Some("ueth".to_string()),
             min_rewards: Some(Uint128::one()),
+            max_spread: None,
+            max_chunk_size: None,
         },
         &[],
     );
@@ -578,6 +622,9 @@ fn test_config_update() {
             router_contract: "router_contract".to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: None,
         },
     );
 
@@ -593,6 +640,8 @@ fn test_config_update() {
                 router_contract: Some("router_contract_1".to_string()),
                 from_denom: Some("untrn".to_string()),
                 min_rewards: Some(Uint128::zero()),
+                max_spread: None,
+                max_chunk_size: None,
             },
             &[],
         )
@@ -638,7 +687,10 @@ fn test_config_update() {
             router_contract: "router_contract_1".to_string(),
             from_denom: "untrn".to_string(),
             min_rewards: Uint128::zero(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
             swap_operations: None,
+            price_oracle: None,
         }
     );
 }
@@ -662,6 +714,9 @@ fn test_swap_operations_update() {
             router_contract: "router_contract".to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: None,
         },
     );
 
@@ -706,7 +761,10 @@ fn test_swap_operations_update() {
             router_contract: "router_contract".to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
             swap_operations: Some(operations),
+            price_oracle: None,
         }
     );
 
@@ -734,7 +792,312 @@ fn test_swap_operations_update() {
             router_contract: "router_contract".to_string(),
             from_denom: "ueth".to_string(),
             min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
             swap_operations: None,
+            price_oracle: None,
         }
     );
 }
+
+fn oracle_contract() -> Box<dyn Contract<Empty>> {
+    let contract: ContractWrapper<Empty, Empty, OracleQueryMsg, StdError, StdError, StdError> =
+        ContractWrapper::new(
+            |_, _, _, _: Empty| Ok(Response::new()),
+            |_, _, _, _: Empty| Ok(Response::new()),
+            |_, _, msg: OracleQueryMsg| match msg {
+                OracleQueryMsg::Consult { amount, .. } => to_json_binary(&vec![(
+                    AssetInfo::NativeToken {
+                        denom: "untrn".to_string(),
+                    },
+                    Uint256::from(amount) * Uint256::from(2u128),
+                )]),
+            },
+        );
+    Box::new(contract)
+}
+
+#[test]
+fn test_exchange_in_chunks() {
+    let mut app = mock_app();
+
+    let pair_contract = instantiate_pair_contract(&mut app);
+    let router_contract = instantiate_router_contract(&mut app);
+
+    let astroport_exchange_handler_code_id = app.store_code(astroport_handler_contract());
+
+    let astroport_handler_contract = instantiate_astroport_handler_contract(
+        &mut app,
+        astroport_exchange_handler_code_id,
+        InstantiateMsg {
+            owner: OWNER_CONTRACT_ADDR.to_string(),
+            core_contract: CORE_CONTRACT_ADDR.to_string(),
+            cron_address: CRON_ADDR.to_string(),
+            pair_contract: pair_contract.to_string(),
+            router_contract: router_contract.to_string(),
+            from_denom: "ueth".to_string(),
+            min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: Some(Uint128::from(60u128)),
+            price_oracle: None,
+        },
+    );
+
+    let amount = coins(100, "ueth");
+
+    let res = app
+        .execute_contract(
+            Addr::unchecked(SENDER_ADDR),
+            astroport_handler_contract.clone(),
+            &ExecuteMsg::Exchange {},
+            &amount,
+        )
+        .unwrap();
+
+    let attrs = find_event(&res, "wasm-pair_mock-pair_contract_execute");
+    assert_eq!(
+        attrs[5],
+        Attribute::new("offer_asset".to_string(), "60ueth".to_string())
+    );
+
+    // the part above the chunk size is refunded to the caller
+    let balance = app
+        .wrap()
+        .query_balance(astroport_handler_contract.clone(), "ueth")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::zero());
+    let handler_event = find_event(
+        &res,
+        "wasm-crates.io:drop-staking__drop-astroport-exchange-handler-exchange",
+    );
+    assert!(handler_event.contains(&Attribute::new("remainder", "40ueth")));
+}
+
+#[test]
+fn test_exchange_price_deviation() {
+    let mut app = mock_app();
+
+    let pair_contract = instantiate_pair_contract(&mut app);
+    let router_contract = instantiate_router_contract(&mut app);
+    let oracle_contract = instantiate_contract(
+        &mut app,
+        oracle_contract,
+        "astroport oracle contract".to_string(),
+    );
+
+    let astroport_exchange_handler_code_id = app.store_code(astroport_handler_contract());
+
+    let astroport_handler_contract = instantiate_astroport_handler_contract(
+        &mut app,
+        astroport_exchange_handler_code_id,
+        InstantiateMsg {
+            owner: OWNER_CONTRACT_ADDR.to_string(),
+            core_contract: CORE_CONTRACT_ADDR.to_string(),
+            cron_address: CRON_ADDR.to_string(),
+            pair_contract: pair_contract.to_string(),
+            router_contract: router_contract.to_string(),
+            from_denom: "ueth".to_string(),
+            min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: Some(PriceOracle {
+                contract: oracle_contract.to_string(),
+                max_deviation: Decimal::percent(10),
+            }),
+        },
+    );
+
+    let amount = coins(100, "ueth");
+
+    let res = app.execute_contract(
+        Addr::unchecked(SENDER_ADDR),
+        astroport_handler_contract.clone(),
+        &ExecuteMsg::Exchange {},
+        &amount,
+    );
+
+    let unwrapped_err = res.unwrap_err();
+    let chain: Vec<_> = unwrapped_err.chain().collect();
+    assert_eq!(
+        chain[1].to_string(),
+        "Price deviates from oracle TWAP: expected at least 180, simulated 100",
+    );
+}
+
+fn empty_oracle_contract() -> Box<dyn Contract<Empty>> {
+    let contract: ContractWrapper<Empty, Empty, OracleQueryMsg, StdError, StdError, StdError> =
+        ContractWrapper::new(
+            |_, _, _, _: Empty| Ok(Response::new()),
+            |_, _, _, _: Empty| Ok(Response::new()),
+            |_, _, msg: OracleQueryMsg| match msg {
+                OracleQueryMsg::Consult { .. } => {
+                    to_json_binary(&Vec::<(AssetInfo, Uint256)>::new())
+                }
+            },
+        );
+    Box::new(contract)
+}
+
+#[test]
+fn test_exchange_empty_oracle_consult() {
+    let mut app = mock_app();
+
+    let pair_contract = instantiate_pair_contract(&mut app);
+    let router_contract = instantiate_router_contract(&mut app);
+    let oracle_contract = instantiate_contract(
+        &mut app,
+        empty_oracle_contract,
+        "astroport oracle contract".to_string(),
+    );
+
+    let astroport_exchange_handler_code_id = app.store_code(astroport_handler_contract());
+
+    let astroport_handler_contract = instantiate_astroport_handler_contract(
+        &mut app,
+        astroport_exchange_handler_code_id,
+        InstantiateMsg {
+            owner: OWNER_CONTRACT_ADDR.to_string(),
+            core_contract: CORE_CONTRACT_ADDR.to_string(),
+            cron_address: CRON_ADDR.to_string(),
+            pair_contract: pair_contract.to_string(),
+            router_contract: router_contract.to_string(),
+            from_denom: "ueth".to_string(),
+            min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: Some(PriceOracle {
+                contract: oracle_contract.to_string(),
+                max_deviation: Decimal::percent(10),
+            }),
+        },
+    );
+
+    let res = app.execute_contract(
+        Addr::unchecked(SENDER_ADDR),
+        astroport_handler_contract.clone(),
+        &ExecuteMsg::Exchange {},
+        &coins(100, "ueth"),
+    );
+
+    let unwrapped_err = res.unwrap_err();
+    let chain: Vec<_> = unwrapped_err.chain().collect();
+    assert_eq!(
+        chain[1].to_string(),
+        "Price oracle returns nothing for ueth",
+    );
+}
+
+#[test]
+fn test_exchange_route_oracle_asset_mismatch() {
+    let mut app = mock_app();
+
+    let pair_contract = instantiate_pair_contract(&mut app);
+    let router_contract = instantiate_router_contract(&mut app);
+    let oracle_contract = instantiate_contract(
+        &mut app,
+        oracle_contract,
+        "astroport oracle contract".to_string(),
+    );
+
+    let astroport_exchange_handler_code_id = app.store_code(astroport_handler_contract());
+
+    let astroport_handler_contract = instantiate_astroport_handler_contract(
+        &mut app,
+        astroport_exchange_handler_code_id,
+        InstantiateMsg {
+            owner: OWNER_CONTRACT_ADDR.to_string(),
+            core_contract: CORE_CONTRACT_ADDR.to_string(),
+            cron_address: CRON_ADDR.to_string(),
+            pair_contract: pair_contract.to_string(),
+            router_contract: router_contract.to_string(),
+            from_denom: "ueth".to_string(),
+            min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(1),
+            max_chunk_size: None,
+            price_oracle: Some(PriceOracle {
+                contract: oracle_contract.to_string(),
+                max_deviation: Decimal::percent(10),
+            }),
+        },
+    );
+
+    // the oracle quotes untrn while the route goes back to ueth
+    app.execute_contract(
+        Addr::unchecked(OWNER_CONTRACT_ADDR),
+        astroport_handler_contract.clone(),
+        &ExecuteMsg::UpdateSwapOperations {
+            operations: Some(vec![
+                SwapOperation::NativeSwap {
+                    offer_denom: "ueth".to_string(),
+                    ask_denom: "untrn".to_string(),
+                },
+                SwapOperation::NativeSwap {
+                    offer_denom: "untrn".to_string(),
+                    ask_denom: "ueth".to_string(),
+                },
+            ]),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let res = app.execute_contract(
+        Addr::unchecked(SENDER_ADDR),
+        astroport_handler_contract.clone(),
+        &ExecuteMsg::Exchange {},
+        &coins(100, "ueth"),
+    );
+
+    let unwrapped_err = res.unwrap_err();
+    let chain: Vec<_> = unwrapped_err.chain().collect();
+    assert_eq!(
+        chain[1].to_string(),
+        "Price oracle quotes untrn, the swap route ends in ueth",
+    );
+}
+
+#[test]
+fn migrate_sets_max_spread_of_legacy_config() {
+    let mut deps = mock_dependencies();
+    LEGACY_CONFIG
+        .save(
+            deps.as_mut().storage,
+            &LegacyConfig {
+                owner: OWNER_CONTRACT_ADDR.to_string(),
+                core_contract: CORE_CONTRACT_ADDR.to_string(),
+                cron_address: CRON_ADDR.to_string(),
+                router_contract: "router_contract".to_string(),
+                pair_contract: "pair_contract".to_string(),
+                from_denom: "ueth".to_string(),
+                min_rewards: Uint128::one(),
+            },
+        )
+        .unwrap();
+
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            max_spread: Decimal::percent(2),
+        },
+    )
+    .unwrap();
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.max_spread, Decimal::percent(2));
+    assert_eq!(config.pair_contract, "pair_contract");
+
+    // configs already holding the field get it updated
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            max_spread: Decimal::percent(3),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        CONFIG.load(deps.as_ref().storage).unwrap().max_spread,
+        Decimal::percent(3)
+    );
+}
@@ -7,22 +7,28 @@ use crate::{
     store::{
         bondings_map,
         reply::{CoreUnbond, CORE_UNBOND},
-        BondingRecord, CORE_ADDRESS, LD_TOKEN, WITHDRAWAL_MANAGER_ADDRESS,
+        BondingRecord, CORE_ADDRESS, KEEPER_TIP, LD_TOKEN, WITHDRAWAL_MANAGER_ADDRESS,
         WITHDRAWAL_VOUCHER_ADDRESS,
     },
 };
 use cosmwasm_std::{
-    attr, ensure, ensure_eq, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut,
-    Env, Event, MessageInfo, Order, Reply, Response, SubMsg, WasmMsg,
+    attr, ensure, ensure_eq, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, Event, MessageInfo, Order, Reply, Response, StdError, StdResult, SubMsg, Uint128,
+    WasmMsg,
 };
 use cw_storage_plus::Bound;
 use drop_helpers::answer::response;
+use drop_staking_base::msg::withdrawal_voucher::{QueryExtMsg, VoucherInfoResponse};
 use neutron_sdk::bindings::{msg::NeutronMsg, query::NeutronQuery};
+use std::collections::BTreeMap;
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const CORE_UNBOND_REPLY_ID: u64 = 1;
 pub const PAGINATION_DEFAULT_LIMIT: usize = 100;
+pub const WITHDRAW_READY_DEFAULT_LIMIT: usize = 10;
+// every scanned bonding costs a voucher query
+pub const MAX_SCANNED_BONDINGS: usize = 100;
 
 #[cfg_attr(not(feature = "library"), cosmwasm_std::entry_point)]
 pub fn instantiate(
@@ -43,6 +49,12 @@ pub fn instantiate(
         &deps.api.addr_validate(&msg.withdrawal_manager_address)?,
     )?;
     LD_TOKEN.save(deps.storage, &msg.ld_token)?;
+    let keeper_tip = msg.keeper_tip.unwrap_or_default();
+    ensure!(
+        keeper_tip <= Decimal::one(),
+        ContractError::InvalidKeeperTip {}
+    );
+    KEEPER_TIP.save(deps.storage, &keeper_tip)?;
 
     Ok(response(
         "instantiate",
@@ -52,6 +64,7 @@ pub fn instantiate(
             attr("withdrawal_voucher", msg.withdrawal_voucher_address),
             attr("withdrawal_manager", msg.withdrawal_manager_address),
             attr("ld_token", msg.ld_token),
+            attr("keeper_tip", keeper_tip.to_string()),
         ],
     ))
 }
@@ -68,8 +81,13 @@ pub fn execute(
             BondMsg::WithLdAssets {} => execute_bond_with_ld_assets(deps, info),
             BondMsg::WithNFT { token_id } => execute_bond_with_nft(deps, env, info, token_id),
         },
-        ExecuteMsg::Unbond { token_id } => execute_unbond(deps, info, token_id),
-        ExecuteMsg::Withdraw { token_id } => execute_withdraw(deps, info, token_id),
+        ExecuteMsg::Unbond { token_id } | ExecuteMsg::Cancel { token_id } => {
+            execute_cancel(deps, info, token_id)
+        }
+        ExecuteMsg::Withdraw { token_id } => execute_withdraw(deps, env, info, token_id),
+        ExecuteMsg::WithdrawReady { limit, page_key } => {
+            execute_withdraw_ready(deps, env, info, limit, page_key)
+        }
     }
 }
 
@@ -86,7 +104,7 @@ fn execute_bond_with_ld_assets(
             .ok_or(ContractError::LdTokenExpected {})?,
     );
     let deposit = info.funds;
-    ensure!(!deposit.is_empty(), ContractError::DepositExpected {});
+    ensure_deposit(deps.as_ref(), &deposit)?;
 
     CORE_UNBOND.save(
         deps.storage,
@@ -113,7 +131,7 @@ fn execute_bond_with_nft(
     token_id: String,
 ) -> ContractResult<Response<NeutronMsg>> {
     let deposit = info.funds;
-    ensure!(!deposit.is_empty(), ContractError::DepositExpected {});
+    ensure_deposit(deps.as_ref(), &deposit)?;
 
     // XXX: this code allows user to pass ld_token as a deposit. This sounds strange, but it might actually make
     //      sense to do so. Should we introduce a check that forbids it?
@@ -143,7 +161,7 @@ fn execute_bond_with_nft(
     Ok(Response::new().add_message(msg))
 }
 
-fn execute_unbond(
+fn execute_cancel(
     deps: DepsMut<NeutronQuery>,
     info: MessageInfo,
     token_id: String,
@@ -159,59 +177,214 @@ fn execute_unbond(
         msg: to_json_binary(
             &drop_staking_base::msg::withdrawal_voucher::ExecuteMsg::TransferNft {
                 recipient: info.sender.to_string(),
-                token_id,
+                token_id: token_id.to_string(),
             },
         )?,
         funds: vec![],
     }
     .into();
 
-    let deposit_msg = BankMsg::Send {
-        to_address: info.sender.into_string(),
-        amount: bonding.deposit,
+    let mut messages = vec![nft_msg];
+    // deposit may be empty when the keeper tip is set
+    if !bonding.deposit.is_empty() {
+        messages.push(
+            BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: bonding.deposit,
+            }
+            .into(),
+        );
     }
-    .into();
 
-    // TODO: attributes
-    Ok(Response::new().add_messages([nft_msg, deposit_msg]))
+    Ok(response(
+        "execute-cancel",
+        CONTRACT_NAME,
+        [attr("token_id", token_id), attr("bonder", info.sender)],
+    )
+    .add_messages(messages))
+}
+
+// deposit is the only keeper reward unless the keeper tip is set
+fn ensure_deposit(deps: Deps<NeutronQuery>, deposit: &[Coin]) -> ContractResult<()> {
+    let keeper_tip = KEEPER_TIP.may_load(deps.storage)?.unwrap_or_default();
+    ensure!(
+        !deposit.is_empty() || !keeper_tip.is_zero(),
+        ContractError::DepositExpected {}
+    );
+    Ok(())
 }
 
 fn execute_withdraw(
     deps: DepsMut<NeutronQuery>,
+    env: Env,
     info: MessageInfo,
     token_id: String,
 ) -> ContractResult<Response<NeutronMsg>> {
     let bonding = bondings_map().load(deps.storage, &token_id)?;
-    bondings_map().remove(deps.storage, &token_id)?;
+    let voucher_info = query_voucher_info(deps.as_ref(), &token_id)?;
+    ensure!(
+        voucher_info.claimable,
+        ContractError::VoucherIsNotClaimable { token_id }
+    );
+    withdraw(deps, env, info, vec![(token_id, bonding, voucher_info)])
+}
+
+fn execute_withdraw_ready(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<usize>,
+    page_key: Option<String>,
+) -> ContractResult<Response<NeutronMsg>> {
+    let (ready, _) = ready_bondings(
+        deps.as_ref(),
+        page_key,
+        limit.unwrap_or(WITHDRAW_READY_DEFAULT_LIMIT),
+    )?;
+    ensure!(!ready.is_empty(), ContractError::NoReadyBondings {});
+    withdraw(deps, env, info, ready)
+}
 
+fn withdraw(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    bondings: Vec<BondingWithVoucher>,
+) -> ContractResult<Response<NeutronMsg>> {
     let withdrawal_voucher = WITHDRAWAL_VOUCHER_ADDRESS.load(deps.storage)?;
     let withdrawal_manager = WITHDRAWAL_MANAGER_ADDRESS.load(deps.storage)?;
+    let keeper_tip = KEEPER_TIP.may_load(deps.storage)?.unwrap_or_default();
+    let base_denom = if keeper_tip.is_zero() {
+        None
+    } else {
+        let core_config: drop_staking_base::state::core::Config = deps.querier.query_wasm_smart(
+            CORE_ADDRESS.load(deps.storage)?,
+            &drop_staking_base::msg::core::QueryMsg::Config {},
+        )?;
+        Some(core_config.base_denom)
+    };
 
-    let withdraw_msg: CosmosMsg<NeutronMsg> = WasmMsg::Execute {
-        contract_addr: withdrawal_voucher.into_string(),
-        msg: to_json_binary(
-            &drop_staking_base::msg::withdrawal_voucher::ExecuteMsg::SendNft {
-                contract: withdrawal_manager.into_string(),
-                token_id,
+    let mut messages: Vec<CosmosMsg<NeutronMsg>> = vec![];
+    let mut keeper_reward: BTreeMap<String, Uint128> = BTreeMap::new();
+    let mut attrs = vec![attr("keeper", &info.sender)];
+    for (token_id, bonding, voucher_info) in bondings {
+        bondings_map().remove(deps.storage, &token_id)?;
+        let payout = voucher_info.payout.unwrap_or_default();
+        let tip = keeper_tip * payout;
+        let tip_denom = base_denom.as_ref().filter(|_| !tip.is_zero());
+        // payout goes through the contract when the keeper takes a part of it
+        let receiver = match tip_denom {
+            Some(_) => env.contract.address.to_string(),
+            None => bonding.bonder.to_string(),
+        };
+        messages.push(
+            WasmMsg::Execute {
+                contract_addr: withdrawal_voucher.to_string(),
                 msg: to_json_binary(
-                    &drop_staking_base::msg::withdrawal_manager::ReceiveNftMsg::Withdraw {
-                        receiver: Some(bonding.bonder.into_string()),
+                    &drop_staking_base::msg::withdrawal_voucher::ExecuteMsg::SendNft {
+                        contract: withdrawal_manager.to_string(),
+                        token_id: token_id.to_string(),
+                        msg: to_json_binary(
+                            &drop_staking_base::msg::withdrawal_manager::ReceiveNftMsg::Withdraw {
+                                receiver: Some(receiver),
+                            },
+                        )?,
                     },
                 )?,
-            },
-        )?,
-        funds: vec![],
+                funds: vec![],
+            }
+            .into(),
+        );
+        if let Some(base_denom) = tip_denom {
+            messages.push(
+                BankMsg::Send {
+                    to_address: bonding.bonder.to_string(),
+                    amount: vec![Coin::new((payout - tip).u128(), base_denom)],
+                }
+                .into(),
+            );
+            *keeper_reward.entry(base_denom.to_string()).or_default() += tip;
+        }
+        for coin in bonding.deposit {
+            *keeper_reward.entry(coin.denom).or_default() += coin.amount;
+        }
+        attrs.push(attr("token_id", token_id));
+        attrs.push(attr("keeper_tip", tip));
     }
-    .into();
+    if !keeper_reward.is_empty() {
+        messages.push(
+            BankMsg::Send {
+                to_address: info.sender.into_string(),
+                amount: keeper_reward
+                    .into_iter()
+                    .map(|(denom, amount)| Coin { denom, amount })
+                    .collect(),
+            }
+            .into(),
+        );
+    }
+
+    Ok(response("execute-withdraw", CONTRACT_NAME, attrs).add_messages(messages))
+}
+
+fn query_voucher_info(
+    deps: Deps<NeutronQuery>,
+    token_id: &str,
+) -> ContractResult<VoucherInfoResponse> {
+    Ok(deps.querier.query_wasm_smart(
+        WITHDRAWAL_VOUCHER_ADDRESS.load(deps.storage)?,
+        &drop_staking_base::msg::withdrawal_voucher::QueryMsg::Extension {
+            msg: QueryExtMsg::VoucherInfo {
+                token_id: token_id.to_string(),
+            },
+        },
+    )?)
+}
+
+fn voucher_batch_id(voucher_info: &VoucherInfoResponse) -> ContractResult<u128> {
+    Ok(voucher_info
+        .metadata
+        .batch_id
+        .parse::<u128>()
+        .map_err(|_| StdError::generic_err("invalid batch_id"))?)
+}
 
-    let deposit_msg = BankMsg::Send {
-        to_address: info.sender.into_string(),
-        amount: bonding.deposit,
+type BondingWithVoucher = (String, BondingRecord, VoucherInfoResponse);
+
+// returns up to `limit` bondings matching the filter along with the next page key,
+// the page ends early once MAX_SCANNED_BONDINGS rows are scanned
+fn scan_bondings<'a>(
+    deps: Deps<NeutronQuery>,
+    iter: Box<dyn Iterator<Item = StdResult<(String, BondingRecord)>> + 'a>,
+    limit: usize,
+    filter: impl Fn(&VoucherInfoResponse) -> ContractResult<bool>,
+) -> ContractResult<(Vec<BondingWithVoucher>, Option<String>)> {
+    let mut bondings = vec![];
+    for (scanned, item) in iter.enumerate() {
+        let (token_id, bonding) = item?;
+        if bondings.len() == limit || scanned == MAX_SCANNED_BONDINGS {
+            return Ok((bondings, Some(token_id)));
+        }
+        let voucher_info = query_voucher_info(deps, &token_id)?;
+        if filter(&voucher_info)? {
+            bondings.push((token_id, bonding, voucher_info));
+        }
     }
-    .into();
+    Ok((bondings, None))
+}
 
-    // TODO: attributes
-    Ok(Response::new().add_messages([withdraw_msg, deposit_msg]))
+fn ready_bondings(
+    deps: Deps<NeutronQuery>,
+    page_key: Option<String>,
+    limit: usize,
+) -> ContractResult<(Vec<BondingWithVoucher>, Option<String>)> {
+    let page_key = page_key.as_deref().map(Bound::inclusive);
+    scan_bondings(
+        deps,
+        bondings_map().range(deps.storage, page_key, None, Order::Ascending),
+        limit,
+        |voucher_info| Ok(voucher_info.claimable),
+    )
 }
 
 #[cfg_attr(not(feature = "library"), cosmwasm_std::entry_point)]
@@ -264,9 +437,11 @@ pub fn query(deps: Deps<NeutronQuery>, _env: Env, msg: QueryMsg) -> ContractResu
     match msg {
         QueryMsg::Bondings {
             user,
+            batch_id,
             limit,
             page_key,
-        } => query_all_bondings(deps, user, limit, page_key),
+        } => query_all_bondings(deps, user, batch_id, limit, page_key),
+        QueryMsg::ReadyBondings { limit, page_key } => query_ready_bondings(deps, limit, page_key),
         QueryMsg::Config {} => query_config(deps),
     }
 }
@@ -274,13 +449,14 @@ pub fn query(deps: Deps<NeutronQuery>, _env: Env, msg: QueryMsg) -> ContractResu
 fn query_all_bondings(
     deps: Deps<NeutronQuery>,
     user: Option<String>,
+    batch_id: Option<u128>,
     limit: Option<usize>,
     page_key: Option<String>,
 ) -> ContractResult<Binary> {
     let user = user.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
     let limit = limit.unwrap_or(PAGINATION_DEFAULT_LIMIT);
     let page_key = page_key.as_deref().map(Bound::inclusive);
-    let mut iter = match user {
+    let iter = match user {
         None => bondings_map().range(deps.storage, page_key, None, Order::Ascending),
         Some(addr) => bondings_map().idx.bonder.prefix(addr).range(
             deps.storage,
@@ -290,23 +466,50 @@ fn query_all_bondings(
         ),
     };
 
-    let mut bondings = vec![];
-    for i in (&mut iter).take(limit) {
-        let (token_id, bonding) = i?;
-        bondings.push(BondingResponse {
-            token_id,
-            bonder: bonding.bonder.into_string(),
-            deposit: bonding.deposit,
+    let (bondings, next_page_key) = scan_bondings(deps, iter, limit, |voucher_info| {
+        Ok(match batch_id {
+            Some(batch_id) => voucher_batch_id(voucher_info)? == batch_id,
+            None => true,
         })
-    }
+    })?;
 
-    let next_page_key = iter
-        .next()
-        .transpose()?
-        .map(|(token_id, _bonding)| token_id);
+    Ok(to_json_binary(&BondingsResponse {
+        bondings: bondings
+            .into_iter()
+            .map(bonding_response)
+            .collect::<ContractResult<_>>()?,
+        next_page_key,
+    })?)
+}
+
+fn bonding_response(
+    (token_id, bonding, voucher_info): BondingWithVoucher,
+) -> ContractResult<BondingResponse> {
+    Ok(BondingResponse {
+        token_id,
+        bonder: bonding.bonder.into_string(),
+        deposit: bonding.deposit,
+        batch_id: voucher_batch_id(&voucher_info)?,
+        batch_status: voucher_info.batch_status,
+        expected_payout: voucher_info
+            .payout
+            .unwrap_or(voucher_info.metadata.expected_amount),
+        claimable: voucher_info.claimable,
+    })
+}
 
+fn query_ready_bondings(
+    deps: Deps<NeutronQuery>,
+    limit: Option<usize>,
+    page_key: Option<String>,
+) -> ContractResult<Binary> {
+    let (ready, next_page_key) =
+        ready_bondings(deps, page_key, limit.unwrap_or(PAGINATION_DEFAULT_LIMIT))?;
     Ok(to_json_binary(&BondingsResponse {
-        bondings,
+        bondings: ready
+            .into_iter()
+            .map(bonding_response)
+            .collect::<ContractResult<_>>()?,
         next_page_key,
     })?)
 }
@@ -317,6 +520,7 @@ fn query_config(deps: Deps<NeutronQuery>) -> ContractResult<Binary> {
         withdrawal_voucher_address: WITHDRAWAL_VOUCHER_ADDRESS.load(deps.storage)?.into_string(),
         withdrawal_manager_address: WITHDRAWAL_MANAGER_ADDRESS.load(deps.storage)?.into_string(),
         ld_token: LD_TOKEN.load(deps.storage)?,
+        keeper_tip: Some(KEEPER_TIP.may_load(deps.storage)?.unwrap_or_default()),
     })?)
 }
 
@@ -11,6 +11,15 @@ pub enum ContractError {
 
     #[error("no deposit was provided")]
     DepositExpected {},
+
+    #[error("keeper tip must be less than or equal to 1")]
+    InvalidKeeperTip {},
+
+    #[error("voucher {token_id} is not claimable yet")]
+    VoucherIsNotClaimable { token_id: String },
+
+    #[error("no bondings are ready to be withdrawn")]
+    NoReadyBondings {},
 }
 
 pub type ContractResult<T> = Result<T, ContractError>;
@@ -1,5 +1,6 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Coin;
+use cosmwasm_std::{Coin, Decimal, Uint128};
+use drop_staking_base::state::core::UnbondBatchStatus;
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -7,13 +8,31 @@ pub struct InstantiateMsg {
     pub withdrawal_voucher_address: String,
     pub withdrawal_manager_address: String,
     pub ld_token: String,
+    /// Share of the payout the keeper gets on withdrawal. Default is zero
+    pub keeper_tip: Option<Decimal>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
     Bond(BondMsg),
-    Unbond { token_id: String },
-    Withdraw { token_id: String },
+    /// Same as `Cancel`, kept for compatibility
+    Unbond {
+        token_id: String,
+    },
+    /// Return the voucher and the deposit to the bonder
+    Cancel {
+        token_id: String,
+    },
+    Withdraw {
+        token_id: String,
+    },
+    /// Withdraw bondings with withdrawn batches
+    WithdrawReady {
+        /// Maximum amount of bondings to withdraw. Default is 10
+        limit: Option<usize>,
+        /// Key to start scanning from, `next_page_key` of the `ReadyBondings` query
+        page_key: Option<String>,
+    },
 }
 
 #[cw_serde]
@@ -30,6 +49,16 @@ pub enum QueryMsg {
     Bondings {
         /// Optionally filter bondings by user address
         user: Option<String>,
+        /// Optionally filter bondings by unbond batch id
+        batch_id: Option<u128>,
+        /// Pagination limit. Default is 100
+        limit: Option<usize>,
+        /// Pagination offset
+        page_key: Option<String>,
+    },
+    /// List bondings that can be withdrawn right now
+    #[returns(BondingsResponse)]
+    ReadyBondings {
         /// Pagination limit. Default is 100
         limit: Option<usize>,
         /// Pagination offset
@@ -42,6 +71,7 @@ pub enum QueryMsg {
 #[cw_serde]
 pub struct BondingsResponse {
     pub bondings: Vec<BondingResponse>,
+    /// Set when not all the bondings are scanned, pages may hold less than `limit` of them
     pub next_page_key: Option<String>,
 }
 
@@ -50,6 +80,11 @@ pub struct BondingResponse {
     pub token_id: String,
     pub bonder: String,
     pub deposit: Vec<Coin>,
+    pub batch_id: u128,
+    pub batch_status: UnbondBatchStatus,
+    /// Actual payout once the batch is withdrawn, voucher expected amount before that
+    pub expected_payout: Uint128,
+    pub claimable: bool,
 }
 
 #[cw_serde]
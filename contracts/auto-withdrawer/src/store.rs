@@ -1,11 +1,13 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{Addr, Coin, Decimal};
 use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
 
 pub const CORE_ADDRESS: Item<Addr> = Item::new("core");
 pub const WITHDRAWAL_VOUCHER_ADDRESS: Item<Addr> = Item::new("withdrawal_voucher");
 pub const WITHDRAWAL_MANAGER_ADDRESS: Item<Addr> = Item::new("withdrawal_manager");
 pub const LD_TOKEN: Item<String> = Item::new("ld_token");
+// is not set for contracts instantiated before keeper tips were introduced
+pub const KEEPER_TIP: Item<Decimal> = Item::new("keeper_tip");
 
 pub use bondings::{map as bondings_map, BondingRecord};
 mod bondings {
@@ -33,6 +33,7 @@ fn instantiate() {
             withdrawal_voucher_address: "withdrawal_voucher".to_string(),
             withdrawal_manager_address: "withdrawal_manager".to_string(),
             ld_token: "ld_token".to_string(),
+            keeper_tip: None,
         },
     )
     .unwrap();
@@ -58,7 +59,8 @@ fn instantiate() {
                 attr("core_address", "core"),
                 attr("withdrawal_voucher", "withdrawal_voucher"),
                 attr("withdrawal_manager", "withdrawal_manager"),
-                attr("ld_token", "ld_token")
+                attr("ld_token", "ld_token"),
+                attr("keeper_tip", "0")
             ])
         ]
     );
@@ -118,3 +120,313 @@ mod bond_missing_deposit {
         assert_eq!(err, ContractError::DepositExpected {});
     }
 }
+
+mod withdraw {
+    use super::*;
+    use crate::{
+        msg::{BondingResponse, BondingsResponse, QueryMsg},
+        store::{bondings_map, BondingRecord, KEEPER_TIP},
+    };
+    use cosmwasm_std::{
+        from_json, to_json_binary, Addr, BankMsg, ContractResult, CosmosMsg, Decimal, SystemResult,
+        Uint128, WasmMsg, WasmQuery,
+    };
+    use drop_staking_base::{
+        msg::withdrawal_voucher::{QueryExtMsg, VoucherInfoResponse},
+        state::{
+            core::{Config as CoreConfig, UnbondBatchStatus},
+            withdrawal_voucher::Metadata,
+        },
+    };
+
+    fn setup(claimable: bool) -> OwnedDeps<MockStorage, MockApi, MockQuerier, NeutronQuery> {
+        let mut deps = mock_dependencies::<MockQuerier>();
+        deps.querier.update_wasm(move |request| {
+            let WasmQuery::Smart { contract_addr, msg } = request else {
+                unimplemented!()
+            };
+            let reply = match contract_addr.as_str() {
+                "withdrawal_voucher" => {
+                    match from_json::<drop_staking_base::msg::withdrawal_voucher::QueryMsg>(msg)
+                        .unwrap()
+                    {
+                        drop_staking_base::msg::withdrawal_voucher::QueryMsg::Extension {
+                            msg: QueryExtMsg::VoucherInfo { token_id },
+                        } => to_json_binary(&VoucherInfoResponse {
+                            token_id,
+                            owner: "cosmos2contract".to_string(),
+                            metadata: Metadata {
+                                batch_id: "1".to_string(),
+                                expected_amount: Uint128::new(1000),
+                                ..Default::default()
+                            },
+                            batch_status: if claimable {
+                                UnbondBatchStatus::Withdrawn
+                            } else {
+                                UnbondBatchStatus::Unbonding
+                            },
+                            expected_release: 0,
+                            slashing_effect: Some(Decimal::one()),
+                            payout: Some(Uint128::new(1000)),
+                            claimable,
+                        }),
+                        _ => unimplemented!(),
+                    }
+                }
+                "core" => to_json_binary(&CoreConfig {
+                    base_denom: "base_denom".to_string(),
+                    ..Default::default()
+                }),
+                _ => unimplemented!(),
+            };
+            SystemResult::Ok(ContractResult::from(reply))
+        });
+        CORE_ADDRESS
+            .save(deps.as_mut().storage, &Addr::unchecked("core"))
+            .unwrap();
+        WITHDRAWAL_VOUCHER_ADDRESS
+            .save(
+                deps.as_mut().storage,
+                &Addr::unchecked("withdrawal_voucher"),
+            )
+            .unwrap();
+        WITHDRAWAL_MANAGER_ADDRESS
+            .save(
+                deps.as_mut().storage,
+                &Addr::unchecked("withdrawal_manager"),
+            )
+            .unwrap();
+        KEEPER_TIP
+            .save(deps.as_mut().storage, &Decimal::percent(1))
+            .unwrap();
+        bondings_map()
+            .save(
+                deps.as_mut().storage,
+                "token_id",
+                &BondingRecord {
+                    bonder: Addr::unchecked("bonder"),
+                    deposit: vec![coin(10, "untrn")],
+                },
+            )
+            .unwrap();
+        deps
+    }
+
+    #[test]
+    fn with_keeper_tip() {
+        let mut deps = setup(true);
+        let response = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::WithdrawReady {
+                limit: None,
+                page_key: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response
+                .messages
+                .into_iter()
+                .map(|m| m.msg)
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "withdrawal_voucher".to_string(),
+                    msg: to_json_binary(
+                        &drop_staking_base::msg::withdrawal_voucher::ExecuteMsg::SendNft {
+                            contract: "withdrawal_manager".to_string(),
+                            token_id: "token_id".to_string(),
+                            msg: to_json_binary(
+                                &drop_staking_base::msg::withdrawal_manager::ReceiveNftMsg::Withdraw {
+                                    receiver: Some("cosmos2contract".to_string()),
+                                },
+                            )
+                            .unwrap(),
+                        }
+                    )
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "bonder".to_string(),
+                    amount: vec![coin(990, "base_denom")],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "keeper".to_string(),
+                    amount: vec![coin(10, "base_denom"), coin(10, "untrn")],
+                }),
+            ]
+        );
+        assert!(bondings_map()
+            .may_load(deps.as_ref().storage, "token_id")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn not_claimable() {
+        let mut deps = setup(false);
+        let err = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Withdraw {
+                token_id: "token_id".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::VoucherIsNotClaimable {
+                token_id: "token_id".to_string()
+            }
+        );
+
+        let err = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::WithdrawReady {
+                limit: None,
+                page_key: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoReadyBondings {});
+    }
+
+    #[test]
+    fn cancel() {
+        let mut deps = setup(false);
+        let err = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::Cancel {
+                token_id: "token_id".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let response = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bonder", &[]),
+            ExecuteMsg::Cancel {
+                token_id: "token_id".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            response
+                .messages
+                .into_iter()
+                .map(|m| m.msg)
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "withdrawal_voucher".to_string(),
+                    msg: to_json_binary(
+                        &drop_staking_base::msg::withdrawal_voucher::ExecuteMsg::TransferNft {
+                            recipient: "bonder".to_string(),
+                            token_id: "token_id".to_string(),
+                        }
+                    )
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "bonder".to_string(),
+                    amount: vec![coin(10, "untrn")],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn bondings_by_batch() {
+        let deps = setup(true);
+        let bondings = |batch_id: u128| -> BondingsResponse {
+            from_json(
+                contract::query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::Bondings {
+                        user: None,
+                        batch_id: Some(batch_id),
+                        limit: None,
+                        page_key: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            bondings(1).bondings,
+            vec![BondingResponse {
+                token_id: "token_id".to_string(),
+                bonder: "bonder".to_string(),
+                deposit: vec![coin(10, "untrn")],
+                batch_id: 1,
+                batch_status: UnbondBatchStatus::Withdrawn,
+                expected_payout: Uint128::new(1000),
+                claimable: true,
+            }]
+        );
+        assert!(bondings(2).bondings.is_empty());
+    }
+
+    #[test]
+    fn ready_bondings_scan_is_bounded() {
+        let mut deps = setup(false);
+        for i in 0..100 {
+            bondings_map()
+                .save(
+                    deps.as_mut().storage,
+                    &format!("token_id_{i:03}"),
+                    &BondingRecord {
+                        bonder: Addr::unchecked("bonder"),
+                        deposit: vec![],
+                    },
+                )
+                .unwrap();
+        }
+        let ready_bondings = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier, NeutronQuery>,
+                              page_key: Option<String>|
+         -> BondingsResponse {
+            from_json(
+                contract::query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::ReadyBondings {
+                        limit: None,
+                        page_key,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap()
+        };
+
+        // nothing is claimable, the page ends after 100 scanned rows anyway
+        assert_eq!(
+            ready_bondings(&deps, None),
+            BondingsResponse {
+                bondings: vec![],
+                next_page_key: Some("token_id_099".to_string()),
+            }
+        );
+        assert_eq!(
+            ready_bondings(&deps, Some("token_id_099".to_string())),
+            BondingsResponse {
+                bondings: vec![],
+                next_page_key: None,
+            }
+        );
+    }
+}
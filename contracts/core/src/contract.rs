@@ -1,27 +1,48 @@
 use crate::error::{ContractError, ContractResult};
+use astroport::router::{
+    ExecuteMsg as RouterExecuteMsg, QueryMsg as RouterQueryMsg, SimulateSwapOperationsResponse,
+    SwapOperation,
+};
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    attr, ensure, ensure_eq, entry_point, to_json_binary, Addr, Attribute, BankQuery, Binary, Coin,
-    CosmosMsg, CustomQuery, Decimal, Deps, DepsMut, Env, MessageInfo, Order, QueryRequest,
-    Response, StdError, StdResult, Timestamp, Uint128, WasmMsg,
+    attr, ensure, ensure_eq, entry_point, to_json_binary, Addr, Attribute, BankMsg, BankQuery,
+    Binary, Coin, CosmosMsg, CustomQuery, Decimal, Deps, DepsMut, Env, MessageInfo, Order,
+    QueryRequest, Reply, Response, StdError, StdResult, SubMsg, Timestamp, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
-use drop_helpers::answer::response;
+use cw_storage_plus::{Item, Map};
+use drop_helpers::answer::{attr_coin, response};
 use drop_helpers::pause::{assert_paused, is_paused, set_pause, unpause, PauseInfoResponse};
-use drop_puppeteer_base::msg::{IBCTransferReason, TransferReadyBatchesMsg};
+use drop_puppeteer_base::msg::{IBCTransferItem, IBCTransferReason, TransferReadyBatchesMsg};
 use drop_puppeteer_base::state::RedeemShareItem;
 use drop_staking_base::state::core::{
-    unbond_batches_map, Config, ConfigOptional, ContractState, FeeItem, NonNativeRewardsItem,
-    UnbondBatch, UnbondBatchStatus, UnbondItem, BONDED_AMOUNT, COLLECTED_FEES, CONFIG,
-    EXCHANGE_RATE, FAILED_BATCH_ID, FSM, LAST_ICA_BALANCE_CHANGE_HEIGHT, LAST_LSM_REDEEM,
-    LAST_PUPPETEER_RESPONSE, LSM_SHARES_TO_REDEEM, NON_NATIVE_REWARDS_CONFIG, PENDING_LSM_SHARES,
-    PENDING_TRANSFER, PRE_UNBONDING_BALANCE, TOTAL_LSM_SHARES, UNBOND_BATCH_ID,
+    referrers_map, unbond_batches_map, BondWithSwapState, Config, ConfigOptional, ContractState,
+    FeeHistoryItem, FeeItem, FeeRecipient, NonNativeRewardsDestination, NonNativeRewardsItem,
+    NonNativeRewardsRoute, ReferrerInfo, Subscriber, UnbondBatch, UnbondBatchStatus, UnbondItem,
+    BONDED_AMOUNT, BOND_WITH_SWAP_STATE, COLLECTED_FEES, COMPOUNDED_REWARDS, CONFIG, EXCHANGE_RATE,
+    EXCHANGE_RATE_SUBSCRIBERS, FAILED_BATCH_ID, FAILED_PUMP_TRANSFERS, FEES_TO_CLAIM, FEE_HISTORY,
+    FEE_RECIPIENTS, FSM, INSURANCE_FEES, LAST_ICA_BALANCE_CHANGE_HEIGHT, LAST_LSM_REDEEM,
+    LAST_PUPPETEER_RESPONSE, LEGACY_COLLECTED_FEES, LIFECYCLE_HOOKS, LSM_SHARES_TO_REDEEM,
+    LSM_SHARE_PATHS, NON_NATIVE_REWARDS_CONFIG, NON_NATIVE_REWARDS_HELD, NOTIFIED_EXCHANGE_RATE,
+    PENDING_FEE_TRANSFER, PENDING_HOOK_CALLS, PENDING_INSURANCE_FEE, PENDING_LSM_SHARES,
+    PENDING_LSM_SHARE_PATHS, PENDING_NON_NATIVE_REWARDS_HELD, PENDING_NOTIFICATIONS,
+    PENDING_TRANSFER, PRE_UNBONDING_BALANCE, REFERRAL_FEE_PER_VOLUME, TOTAL_LSM_SHARES,
+    TOTAL_REFERRAL_FEE_CREDIT, TOTAL_REFERRED_AMOUNT, UNBOND_BATCH_ID,
 };
 use drop_staking_base::state::validatorset::ValidatorInfo;
 use drop_staking_base::state::withdrawal_voucher::{Metadata, Trait};
 use drop_staking_base::{
     msg::{
-        core::{ExecuteMsg, InstantiateMsg, QueryMsg},
+        core::{
+            CollectedFeesResponse, ExchangeRateSubscriberMsg, ExecuteMsg, InstantiateMsg,
+            LifecycleHookMsg, MigrateMsg, NonNativeRewardsConsistencyResponse,
+            NonNativeRewardsRouteAmount, NonNativeRewardsRoutingResponseItem, QueryMsg,
+            ReferrerResponse, SimulateBondWithSwapResponse, SupportedBondDenomsResponse,
+        },
+        insurance_fund::{
+            ExecuteMsg as InsuranceFundExecuteMsg, QueryMsg as InsuranceFundQueryMsg,
+        },
+        pump::PumpTransferHookMsg,
         token::ExecuteMsg as TokenExecuteMsg,
         withdrawal_voucher::ExecuteMsg as VoucherExecuteMsg,
     },
@@ -35,6 +56,14 @@ pub type MessageWithFeeResponse<T> = (CosmosMsg<T>, Option<CosmosMsg<T>>);
 const CONTRACT_NAME: &str = concat!("crates.io:drop-staking__", env!("CARGO_PKG_NAME"));
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+pub const BOND_WITH_SWAP_REPLY_ID: u64 = 1;
+// reply ids starting from this one belong to the exchange rate notifications
+pub const EXCHANGE_RATE_NOTIFICATION_REPLY_ID: u64 = 1000;
+pub const LIFECYCLE_HOOK_REPLY_ID: u64 = 2000;
+// per kind of subscribers
+pub const MAX_SUBSCRIBERS: u64 = 10;
+pub const TOP_REFERRERS_DEFAULT_LIMIT: usize = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -50,6 +79,15 @@ pub fn instantiate(
         attr("base_denom", &msg.base_denom),
         attr("owner", &msg.owner),
     ];
+    ensure!(
+        msg.referral_fee_share.unwrap_or_default() <= Decimal::one(),
+        ContractError::InvalidReferralFeeShare {}
+    );
+    ensure!(
+        msg.referral_fee_share.unwrap_or_default() + msg.insurance_fee_share.unwrap_or_default()
+            <= Decimal::one(),
+        ContractError::InvalidInsuranceFeeShare {}
+    );
     cw_ownable::initialize_owner(deps.storage, deps.api, Some(msg.owner.as_ref()))?;
     CONFIG.save(deps.storage, &msg.into())?;
     //an empty unbonding batch added as it's ready to be used on unbond action
@@ -77,11 +115,58 @@ pub fn query(deps: Deps<NeutronQuery>, env: Env, msg: QueryMsg) -> ContractResul
         QueryMsg::PendingLSMShares {} => query_pending_lsm_shares(deps)?,
         QueryMsg::LSMSharesToRedeem {} => query_lsm_shares_to_redeem(deps)?,
         QueryMsg::TotalBonded {} => to_json_binary(&BONDED_AMOUNT.load(deps.storage)?)?,
+        QueryMsg::LSMSharePaths {} => query_lsm_share_paths(deps)?,
+        QueryMsg::SupportedBondDenoms {} => query_supported_bond_denoms(deps)?,
+        QueryMsg::FeeRecipients {} => {
+            to_json_binary(&FEE_RECIPIENTS.may_load(deps.storage)?.unwrap_or_default())?
+        }
+        QueryMsg::CollectedFees {} => query_collected_fees(deps)?,
+        QueryMsg::FeeHistory {} => to_json_binary(
+            &FEE_HISTORY
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| item.map(|(_, history_item)| history_item))
+                .collect::<StdResult<Vec<_>>>()?,
+        )?,
+        QueryMsg::Referrer { address } => query_referrer(deps, address)?,
+        QueryMsg::TopReferrers { limit } => query_top_referrers(deps, limit)?,
+        QueryMsg::ExchangeRateSubscribers {} => to_json_binary(
+            &EXCHANGE_RATE_SUBSCRIBERS
+                .range(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?,
+        )?,
+        QueryMsg::LifecycleHooks {} => to_json_binary(
+            &LIFECYCLE_HOOKS
+                .range(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?,
+        )?,
+        QueryMsg::SimulateBondWithSwap {
+            offer,
+            swap_operations,
+        } => query_simulate_bond_with_swap(deps, env, offer, swap_operations)?,
         QueryMsg::ExchangeRate {} => to_json_binary(&query_exchange_rate(deps, env, None)?)?,
+        QueryMsg::CompoundedRewards {} => to_json_binary(
+            &COMPOUNDED_REWARDS
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        )?,
+        QueryMsg::FailedPumpTransfers {} => to_json_binary(
+            &FAILED_PUMP_TRANSFERS
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        )?,
         QueryMsg::UnbondBatch { batch_id } => query_unbond_batch(deps, batch_id)?,
         QueryMsg::NonNativeRewardsReceivers {} => {
             to_json_binary(&NON_NATIVE_REWARDS_CONFIG.load(deps.storage)?)?
         }
+        QueryMsg::NonNativeRewardsConsistency {} => {
+            to_json_binary(&query_non_native_rewards_consistency(deps)?)?
+        }
+        QueryMsg::NonNativeRewardsRouting {} => to_json_binary(
+            &get_non_native_rewards_routing(deps)?
+                .into_iter()
+                .map(|(_, routing)| routing)
+                .collect::<Vec<_>>(),
+        )?,
         QueryMsg::ContractState {} => to_json_binary(&FSM.get_current_state(deps.storage)?)?,
         QueryMsg::LastPuppeteerResponse {} => {
             to_json_binary(&LAST_PUPPETEER_RESPONSE.load(deps.storage)?)?
@@ -112,6 +197,107 @@ fn query_lsm_shares_to_redeem(deps: Deps<NeutronQuery>) -> ContractResult<Binary
     to_json_binary(&shares).map_err(From::from)
 }
 
+fn query_lsm_share_paths(deps: Deps<NeutronQuery>) -> ContractResult<Binary> {
+    let paths = LSM_SHARE_PATHS.may_load(deps.storage)?.unwrap_or_default();
+    to_json_binary(&paths).map_err(From::from)
+}
+
+fn query_supported_bond_denoms(deps: Deps<NeutronQuery>) -> ContractResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut lsm_share_paths = vec![("transfer".to_string(), config.channel.to_string())];
+    for path in LSM_SHARE_PATHS.may_load(deps.storage)?.unwrap_or_default() {
+        if !lsm_share_paths.contains(&path) {
+            lsm_share_paths.push(path);
+        }
+    }
+    let validators: Vec<ValidatorInfo> = deps.querier.query_wasm_smart(
+        config.validators_set_contract.to_string(),
+        &drop_staking_base::msg::validatorset::QueryMsg::Validators {},
+    )?;
+    to_json_binary(&SupportedBondDenomsResponse {
+        base_denom: config.base_denom,
+        lsm_share_paths,
+        lsm_share_validators: validators.into_iter().map(|v| v.valoper_address).collect(),
+    })
+    .map_err(From::from)
+}
+
+fn query_collected_fees(deps: Deps<NeutronQuery>) -> ContractResult<Binary> {
+    let collected = COLLECTED_FEES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, fee_item)| fee_item))
+        .collect::<StdResult<Vec<_>>>()?;
+    let to_claim = FEES_TO_CLAIM
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, fee_item)| fee_item))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&CollectedFeesResponse {
+        collected,
+        to_claim,
+    })
+    .map_err(From::from)
+}
+
+fn referrer_response(referrer_info: ReferrerInfo, fee_per_volume: Decimal) -> ReferrerResponse {
+    ReferrerResponse {
+        fee_credit: referrer_fee_credit(&referrer_info, fee_per_volume),
+        address: referrer_info.address,
+        bonded_amount: referrer_info.bonded_amount,
+        bonds_count: referrer_info.bonds_count,
+    }
+}
+
+fn query_referrer(deps: Deps<NeutronQuery>, address: String) -> ContractResult<Binary> {
+    let fee_per_volume = REFERRAL_FEE_PER_VOLUME
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let referrer = referrers_map()
+        .may_load(deps.storage, address)?
+        .map(|referrer_info| referrer_response(referrer_info, fee_per_volume));
+    to_json_binary(&referrer).map_err(From::from)
+}
+
+fn query_top_referrers(deps: Deps<NeutronQuery>, limit: Option<usize>) -> ContractResult<Binary> {
+    let fee_per_volume = REFERRAL_FEE_PER_VOLUME
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let referrers = referrers_map()
+        .idx
+        .bonded_amount
+        .range(deps.storage, None, None, Order::Descending)
+        .take(limit.unwrap_or(TOP_REFERRERS_DEFAULT_LIMIT))
+        .map(|item| item.map(|(_, referrer_info)| referrer_response(referrer_info, fee_per_volume)))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&referrers).map_err(From::from)
+}
+
+fn query_simulate_bond_with_swap(
+    deps: Deps<NeutronQuery>,
+    env: Env,
+    offer: Coin,
+    swap_operations: Vec<SwapOperation>,
+) -> ContractResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let router_contract = config
+        .router_contract
+        .ok_or(ContractError::RouterContractIsNotSet {})?;
+    validate_swap_operations(&swap_operations, &offer.denom, &config.base_denom)?;
+    let simulation: SimulateSwapOperationsResponse = deps.querier.query_wasm_smart(
+        router_contract,
+        &RouterQueryMsg::SimulateSwapOperations {
+            offer_amount: offer.amount,
+            operations: swap_operations,
+        },
+    )?;
+    let exchange_rate = query_exchange_rate(deps, env, None)?;
+    to_json_binary(&SimulateBondWithSwapResponse {
+        base_denom_amount: simulation.amount,
+        ld_amount: simulation.amount * (Decimal::one() / exchange_rate),
+        exchange_rate,
+    })
+    .map_err(From::from)
+}
+
 fn query_exchange_rate(
     deps: Deps<NeutronQuery>,
     env: Env,
@@ -179,14 +365,82 @@ fn query_exchange_rate(
     Ok(exchange_rate) // arithmetic operations order is important here as we don't want to overflow
 }
 
+// returns notifications for the subscribers if the rate moved enough since the last one
 fn cache_exchange_rate(
     deps: DepsMut<NeutronQuery>,
     env: Env,
     current_stake: Option<Uint128>,
-) -> ContractResult<()> {
+) -> ContractResult<Vec<SubMsg<NeutronMsg>>> {
     let exchange_rate = query_exchange_rate(deps.as_ref(), env.clone(), current_stake)?;
     EXCHANGE_RATE.save(deps.storage, &(exchange_rate, env.block.height))?;
-    Ok(())
+
+    let subscribers = EXCHANGE_RATE_SUBSCRIBERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if subscribers.is_empty() {
+        return Ok(vec![]);
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let delta = config.exchange_rate_notify_delta.unwrap_or_default();
+    let moved = match NOTIFIED_EXCHANGE_RATE.may_load(deps.storage)? {
+        None => true,
+        Some((notified_rate, _)) if notified_rate.is_zero() => exchange_rate != notified_rate,
+        Some((notified_rate, _)) => {
+            let change = if exchange_rate > notified_rate {
+                exchange_rate - notified_rate
+            } else {
+                notified_rate - exchange_rate
+            };
+            !change.is_zero() && change / notified_rate >= delta
+        }
+    };
+    if !moved {
+        return Ok(vec![]);
+    }
+    NOTIFIED_EXCHANGE_RATE.save(deps.storage, &(exchange_rate, env.block.height))?;
+    let msg = to_json_binary(&ExchangeRateSubscriberMsg::ExchangeRateUpdated {
+        rate: exchange_rate,
+        height: env.block.height,
+    })?;
+    Ok(get_subscriber_msgs(
+        deps.storage,
+        &PENDING_NOTIFICATIONS,
+        subscribers,
+        msg,
+        EXCHANGE_RATE_NOTIFICATION_REPLY_ID,
+    )?)
+}
+
+// failing subscribers must not break the caller, replies are matched by the subscriber index
+fn get_subscriber_msgs(
+    storage: &mut dyn cosmwasm_std::Storage,
+    pending: &Item<Vec<String>>,
+    subscribers: Vec<(String, Subscriber)>,
+    msg: Binary,
+    reply_id: u64,
+) -> StdResult<Vec<SubMsg<NeutronMsg>>> {
+    pending.save(
+        storage,
+        &subscribers
+            .iter()
+            .map(|(address, _)| address.to_string())
+            .collect(),
+    )?;
+    Ok(subscribers
+        .into_iter()
+        .enumerate()
+        .map(|(i, (address, subscriber))| {
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: address,
+                    msg: msg.clone(),
+                    funds: vec![],
+                },
+                reply_id + i as u64,
+            )
+            .with_gas_limit(subscriber.gas_limit)
+        })
+        .collect())
 }
 
 fn query_unbond_batch(deps: Deps<NeutronQuery>, batch_id: Uint128) -> StdResult<Binary> {
@@ -201,8 +455,27 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> ContractResult<Response<NeutronMsg>> {
     match msg {
-        ExecuteMsg::Bond { receiver } => execute_bond(deps, env, info, receiver),
+        ExecuteMsg::Bond {
+            receiver,
+            r#ref,
+            mint_cw20,
+        } => execute_bond(deps, env, info, receiver, r#ref, mint_cw20),
+        ExecuteMsg::BondWithSwap {
+            swap_operations,
+            min_ld_out,
+            receiver,
+            r#ref,
+        } => execute_bond_with_swap(
+            deps,
+            env,
+            info,
+            swap_operations,
+            min_ld_out,
+            receiver,
+            r#ref,
+        ),
         ExecuteMsg::Unbond {} => execute_unbond(deps, env, info),
+        ExecuteMsg::CompoundRewards {} => execute_compound_rewards(deps, env, info),
         ExecuteMsg::UpdateConfig { new_config } => execute_update_config(deps, info, *new_config),
         ExecuteMsg::UpdateOwnership(action) => {
             cw_ownable::update_ownership(deps.into_empty(), &env.block, &info.sender, action)?;
@@ -213,16 +486,200 @@ pub fn execute(
             ))
         }
         ExecuteMsg::ResetBondedAmount {} => execute_reset_bonded_amount(deps, env, info),
+        ExecuteMsg::ReconcileWithdrawingBatches { batch_ids } => {
+            execute_reconcile_withdrawing_batches(deps, info, batch_ids)
+        }
+        ExecuteMsg::ClearFailedPumpTransfers { amount } => {
+            execute_clear_failed_pump_transfers(deps, info, amount)
+        }
         ExecuteMsg::UpdateNonNativeRewardsReceivers { items } => {
             execute_set_non_native_rewards_receivers(deps, env, info, items)
         }
+        ExecuteMsg::UpdateLSMSharePaths { paths } => {
+            execute_update_lsm_share_paths(deps, info, paths)
+        }
+        ExecuteMsg::UpdateFeeRecipients { recipients } => {
+            execute_update_fee_recipients(deps, info, recipients)
+        }
+        ExecuteMsg::ClaimFees {} => execute_claim_fees(deps),
+        ExecuteMsg::ClaimReferralFee { receiver } => {
+            execute_claim_referral_fee(deps, info, receiver)
+        }
+        ExecuteMsg::AddExchangeRateSubscriber { address, gas_limit } => Ok(response(
+            "execute-add_exchange_rate_subscriber",
+            CONTRACT_NAME,
+            execute_add_subscriber(deps, info, &EXCHANGE_RATE_SUBSCRIBERS, address, gas_limit)?,
+        )),
+        ExecuteMsg::RemoveExchangeRateSubscriber { address } => Ok(response(
+            "execute-remove_exchange_rate_subscriber",
+            CONTRACT_NAME,
+            execute_remove_subscriber(deps, info, &EXCHANGE_RATE_SUBSCRIBERS, address)?,
+        )),
+        ExecuteMsg::AddLifecycleHook { address, gas_limit } => Ok(response(
+            "execute-add_lifecycle_hook",
+            CONTRACT_NAME,
+            execute_add_subscriber(deps, info, &LIFECYCLE_HOOKS, address, gas_limit)?,
+        )),
+        ExecuteMsg::RemoveLifecycleHook { address } => Ok(response(
+            "execute-remove_lifecycle_hook",
+            CONTRACT_NAME,
+            execute_remove_subscriber(deps, info, &LIFECYCLE_HOOKS, address)?,
+        )),
         ExecuteMsg::Tick {} => execute_tick(deps, env, info),
         ExecuteMsg::PuppeteerHook(msg) => execute_puppeteer_hook(deps, env, info, *msg),
+        ExecuteMsg::PumpHook(msg) => execute_pump_hook(deps, info, msg),
         ExecuteMsg::Pause {} => exec_pause(deps, info),
         ExecuteMsg::Unpause {} => exec_unpause(deps, info),
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut<NeutronQuery>,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> ContractResult<Response<NeutronMsg>> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    // legacy collected fees are running totals which were already transferred, nothing is owed
+    let legacy_fees = LEGACY_COLLECTED_FEES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut attrs = vec![attr("action", "migrate")];
+    for (denom, fee_item) in legacy_fees {
+        LEGACY_COLLECTED_FEES.remove(deps.storage, denom);
+        attrs.push(attr_coin(
+            format!("dropped_legacy_fee_{}", fee_item.address),
+            fee_item.amount,
+            fee_item.denom,
+        ));
+    }
+    Ok(response("migrate", CONTRACT_NAME, attrs))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    msg: Reply,
+) -> ContractResult<Response<NeutronMsg>> {
+    match msg.id {
+        BOND_WITH_SWAP_REPLY_ID => bond_with_swap_reply(deps, env),
+        id if (EXCHANGE_RATE_NOTIFICATION_REPLY_ID
+            ..EXCHANGE_RATE_NOTIFICATION_REPLY_ID + MAX_SUBSCRIBERS)
+            .contains(&id) =>
+        {
+            subscriber_failure_reply(
+                deps,
+                &EXCHANGE_RATE_SUBSCRIBERS,
+                &PENDING_NOTIFICATIONS,
+                id - EXCHANGE_RATE_NOTIFICATION_REPLY_ID,
+                msg.result.unwrap_err(),
+            )
+        }
+        id if (LIFECYCLE_HOOK_REPLY_ID..LIFECYCLE_HOOK_REPLY_ID + MAX_SUBSCRIBERS)
+            .contains(&id) =>
+        {
+            subscriber_failure_reply(
+                deps,
+                &LIFECYCLE_HOOKS,
+                &PENDING_HOOK_CALLS,
+                id - LIFECYCLE_HOOK_REPLY_ID,
+                msg.result.unwrap_err(),
+            )
+        }
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+fn subscriber_failure_reply(
+    deps: DepsMut<NeutronQuery>,
+    subscribers: &Map<String, Subscriber>,
+    pending: &Item<Vec<String>>,
+    index: u64,
+    error: String,
+) -> ContractResult<Response<NeutronMsg>> {
+    let address = pending
+        .load(deps.storage)?
+        .get(index as usize)
+        .cloned()
+        .ok_or_else(|| StdError::generic_err("unknown subscriber"))?;
+    // the subscriber could have been removed in the meantime
+    if let Some(mut subscriber) = subscribers.may_load(deps.storage, address.to_string())? {
+        subscriber.failures += 1;
+        subscribers.save(deps.storage, address.to_string(), &subscriber)?;
+    }
+    Ok(response(
+        "reply-subscriber_failure",
+        CONTRACT_NAME,
+        [attr("subscriber", address), attr("error", error)],
+    ))
+}
+
+fn execute_add_subscriber(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    subscribers: &Map<String, Subscriber>,
+    address: String,
+    gas_limit: u64,
+) -> ContractResult<Vec<Attribute>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let address = deps.api.addr_validate(&address)?.to_string();
+    if !subscribers.has(deps.storage, address.to_string()) {
+        let subscribers_count = subscribers
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count() as u64;
+        ensure!(
+            subscribers_count < MAX_SUBSCRIBERS,
+            ContractError::TooManySubscribers {
+                max: MAX_SUBSCRIBERS
+            }
+        );
+    }
+    subscribers.save(
+        deps.storage,
+        address.to_string(),
+        &Subscriber {
+            gas_limit,
+            failures: 0,
+        },
+    )?;
+    Ok(vec![
+        attr("address", address),
+        attr("gas_limit", gas_limit.to_string()),
+    ])
+}
+
+fn execute_remove_subscriber(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    subscribers: &Map<String, Subscriber>,
+    address: String,
+) -> ContractResult<Vec<Attribute>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    subscribers.remove(deps.storage, address.to_string());
+    Ok(vec![attr("address", address)])
+}
+
+// partners are notified about bonds, unbonds and withdrawals
+fn get_lifecycle_hook_msgs(
+    storage: &mut dyn cosmwasm_std::Storage,
+    msg: &LifecycleHookMsg,
+) -> ContractResult<Vec<SubMsg<NeutronMsg>>> {
+    let hooks = LIFECYCLE_HOOKS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if hooks.is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(get_subscriber_msgs(
+        storage,
+        &PENDING_HOOK_CALLS,
+        hooks,
+        to_json_binary(msg)?,
+        LIFECYCLE_HOOK_REPLY_ID,
+    )?)
+}
+
 fn exec_pause(
     deps: DepsMut<NeutronQuery>,
     info: MessageInfo,
@@ -267,6 +724,60 @@ fn execute_reset_bonded_amount(
     ))
 }
 
+fn execute_reconcile_withdrawing_batches(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    batch_ids: Vec<u128>,
+) -> ContractResult<Response<NeutronMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    // a failed transfer may have carried any of the withdrawing batches
+    let failed_amount = FAILED_PUMP_TRANSFERS
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let mut attrs = vec![attr("action", "reconcile_withdrawing_batches")];
+    for id in batch_ids {
+        let mut batch = unbond_batches_map().load(deps.storage, id)?;
+        batch.status = match batch.status {
+            UnbondBatchStatus::Withdrawing => {
+                ensure!(
+                    failed_amount.is_zero(),
+                    ContractError::FailedPumpTransfersPending {
+                        amount: failed_amount
+                    }
+                );
+                UnbondBatchStatus::Withdrawn
+            }
+            UnbondBatchStatus::WithdrawingEmergency => UnbondBatchStatus::WithdrawnEmergency,
+            _ => return Err(ContractError::BatchIsNotWithdrawing { batch_id: id }),
+        };
+        unbond_batches_map().save(deps.storage, id, &batch)?;
+        attrs.push(attr("withdrawn_batch_id", id.to_string()));
+    }
+    Ok(response(
+        "execute-reconcile_withdrawing_batches",
+        CONTRACT_NAME,
+        attrs,
+    ))
+}
+
+fn execute_clear_failed_pump_transfers(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    amount: Uint128,
+) -> ContractResult<Response<NeutronMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let failed_amount = FAILED_PUMP_TRANSFERS
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .checked_sub(amount)?;
+    FAILED_PUMP_TRANSFERS.save(deps.storage, &failed_amount)?;
+    Ok(response(
+        "execute-clear_failed_pump_transfers",
+        CONTRACT_NAME,
+        [attr("amount", amount), attr("failed_amount", failed_amount)],
+    ))
+}
+
 fn execute_set_non_native_rewards_receivers(
     deps: DepsMut<NeutronQuery>,
     _env: Env,
@@ -274,14 +785,260 @@ fn execute_set_non_native_rewards_receivers(
     items: Vec<NonNativeRewardsItem>,
 ) -> ContractResult<Response<NeutronMsg>> {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    for item in &items {
+        validate_non_native_rewards_routes(item)?;
+    }
     NON_NATIVE_REWARDS_CONFIG.save(deps.storage, &items)?;
+    // held shares are indexed by route, so they go back to the unallocated
+    // balance and get split by the new rules
+    NON_NATIVE_REWARDS_HELD.clear(deps.storage);
+    PENDING_NON_NATIVE_REWARDS_HELD.remove(deps.storage);
+
+    let mut attrs = vec![attr("action", "set_non_native_rewards_receivers")];
+    let mut messages = vec![];
+    let consistency = query_non_native_rewards_consistency(deps.as_ref())?;
+    if !consistency.missing_in_puppeteer.is_empty() || !consistency.missing_in_core.is_empty() {
+        let config = CONFIG.load(deps.storage)?;
+        let denoms = items
+            .iter()
+            .map(|item| item.denom.to_string())
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        attrs.push(attr("non_native_rewards_denoms", denoms.join(",")));
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.puppeteer_contract,
+            msg: to_json_binary(
+                &drop_staking_base::msg::puppeteer::ExecuteMsg::RegisterNonNativeRewardsBalancesQuery {
+                    denoms,
+                },
+            )?,
+            funds: info.funds,
+        }));
+    } else if !info.funds.is_empty() {
+        // the query registration fee is only needed when the denoms change
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: info.funds,
+        }));
+    }
     Ok(response(
         "execute-set_non_native_rewards_receivers",
         CONTRACT_NAME,
-        vec![attr("action", "set_non_native_rewards_receivers")],
+        attrs,
+    )
+    .add_messages(messages))
+}
+
+fn query_non_native_rewards_consistency(
+    deps: Deps<NeutronQuery>,
+) -> ContractResult<NonNativeRewardsConsistencyResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let core_denoms = NON_NATIVE_REWARDS_CONFIG
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|item| item.denom)
+        .collect::<std::collections::BTreeSet<_>>();
+    let puppeteer_denoms: Vec<String> = deps.querier.query_wasm_smart(
+        config.puppeteer_contract.to_string(),
+        &drop_puppeteer_base::msg::QueryMsg::Extention {
+            msg: drop_staking_base::msg::puppeteer::QueryExtMsg::NonNativeRewardsDenoms {},
+        },
+    )?;
+    let puppeteer_denoms = puppeteer_denoms
+        .into_iter()
+        .collect::<std::collections::BTreeSet<_>>();
+    Ok(NonNativeRewardsConsistencyResponse {
+        missing_in_puppeteer: core_denoms.difference(&puppeteer_denoms).cloned().collect(),
+        missing_in_core: puppeteer_denoms.difference(&core_denoms).cloned().collect(),
+    })
+}
+
+fn validate_non_native_rewards_routes(item: &NonNativeRewardsItem) -> ContractResult<()> {
+    if item.routes.is_empty() {
+        return Ok(());
+    }
+    let total_weight = item
+        .routes
+        .iter()
+        .try_fold(Decimal::zero(), |total, route| {
+            total.checked_add(route.weight)
+        })?;
+    ensure_eq!(
+        total_weight,
+        Decimal::one(),
+        ContractError::InvalidNonNativeRewardsRoutes {
+            denom: item.denom.to_string(),
+            reason: "weights should sum up to 1".to_string(),
+        }
+    );
+    ensure!(
+        item.routes.iter().all(|route| !route.weight.is_zero()),
+        ContractError::InvalidNonNativeRewardsRoutes {
+            denom: item.denom.to_string(),
+            reason: "weight should be greater than zero".to_string(),
+        }
+    );
+    Ok(())
+}
+
+fn execute_update_fee_recipients(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    recipients: Vec<FeeRecipient>,
+) -> ContractResult<Response<NeutronMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    if !recipients.is_empty() {
+        let total_share = recipients
+            .iter()
+            .try_fold(Decimal::zero(), |total, recipient| {
+                total.checked_add(recipient.share)
+            })?;
+        ensure_eq!(
+            total_share,
+            Decimal::one(),
+            ContractError::InvalidFeeRecipients {
+                reason: "shares should sum up to 1".to_string(),
+            }
+        );
+        ensure!(
+            recipients
+                .iter()
+                .all(|recipient| !recipient.share.is_zero()),
+            ContractError::InvalidFeeRecipients {
+                reason: "share should be greater than zero".to_string(),
+            }
+        );
+    }
+    let attrs = recipients
+        .iter()
+        .map(|recipient| attr(&recipient.address, recipient.share.to_string()))
+        .collect::<Vec<_>>();
+    FEE_RECIPIENTS.save(deps.storage, &recipients)?;
+    Ok(response(
+        "execute-update_fee_recipients",
+        CONTRACT_NAME,
+        attrs,
+    ))
+}
+
+fn execute_compound_rewards(
+    mut deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    let amount = cw_utils::must_pay(&info, &config.base_denom)?;
+    let total = COMPOUNDED_REWARDS
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + amount;
+    COMPOUNDED_REWARDS.save(deps.storage, &total)?;
+    let mut attrs = vec![
+        attr("action", "compound_rewards"),
+        attr("sender", info.sender),
+        attr_coin("amount", amount, config.base_denom),
+        attr("total_compounded", total),
+    ];
+    // the proceeds are already part of the core balance, so the idle rate reflects them
+    let mut notification_msgs = vec![];
+    if FSM.get_current_state(deps.storage)? == ContractState::Idle {
+        notification_msgs = cache_exchange_rate(deps.branch(), env, None)?;
+        attrs.push(attr(
+            "exchange_rate",
+            EXCHANGE_RATE.load(deps.storage)?.0.to_string(),
+        ));
+    }
+    Ok(response("execute-compound_rewards", CONTRACT_NAME, attrs)
+        .add_submessages(notification_msgs))
+}
+
+// fees can only be sent to the configured recipients, so anyone can trigger the claim
+fn execute_claim_fees(deps: DepsMut<NeutronQuery>) -> ContractResult<Response<NeutronMsg>> {
+    let collected_fees = COLLECTED_FEES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    ensure!(!collected_fees.is_empty(), ContractError::NoFeesToClaim {});
+    let mut attrs = vec![attr("action", "claim_fees")];
+    for (key, fee_item) in collected_fees {
+        attrs.push(attr_coin(
+            &fee_item.address,
+            fee_item.amount,
+            &fee_item.denom,
+        ));
+        COLLECTED_FEES.remove(deps.storage, key.clone());
+        FEES_TO_CLAIM.update(deps.storage, key, |to_claim| {
+            let mut to_claim = to_claim.unwrap_or(FeeItem {
+                amount: Uint128::zero(),
+                ..fee_item.clone()
+            });
+            to_claim.amount += fee_item.amount;
+            StdResult::Ok(to_claim)
+        })?;
+    }
+    Ok(response("execute-claim_fees", CONTRACT_NAME, attrs))
+}
+
+fn execute_update_lsm_share_paths(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    paths: Vec<(String, String)>,
+) -> ContractResult<Response<NeutronMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let attrs = vec![
+        attr("action", "update_lsm_share_paths"),
+        attr(
+            "paths",
+            paths
+                .iter()
+                .map(|(port, channel)| format!("{port}/{channel}"))
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+    ];
+    if paths.is_empty() {
+        LSM_SHARE_PATHS.remove(deps.storage);
+    } else {
+        LSM_SHARE_PATHS.save(deps.storage, &paths)?;
+    }
+    Ok(response(
+        "execute-update_lsm_share_paths",
+        CONTRACT_NAME,
+        attrs,
     ))
 }
 
+fn execute_pump_hook(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    msg: PumpTransferHookMsg,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_eq!(
+        config.pump_contract,
+        Some(info.sender.to_string()),
+        ContractError::Unauthorized {}
+    );
+    let attrs = vec![
+        attr("action", "pump_hook"),
+        attr_coin("amount", msg.amount.amount, &msg.amount.denom),
+        attr("receiver", &msg.receiver),
+        attr("succeeded", msg.succeeded.to_string()),
+    ];
+    // the withdrawal manager funds the batches from its own balance once the transfer lands
+    if !msg.succeeded {
+        FAILED_PUMP_TRANSFERS.save(
+            deps.storage,
+            &(FAILED_PUMP_TRANSFERS
+                .may_load(deps.storage)?
+                .unwrap_or_default()
+                + msg.amount.amount),
+        )?;
+    }
+    Ok(response("execute-pump_hook", CONTRACT_NAME, attrs))
+}
+
 fn execute_puppeteer_hook(
     deps: DepsMut<NeutronQuery>,
     env: Env,
@@ -322,6 +1079,7 @@ fn execute_puppeteer_hook(
                                 )?;
                                 if current_amount == sent_amount {
                                     PENDING_LSM_SHARES.remove(deps.storage, denom.to_string());
+                                    PENDING_LSM_SHARE_PATHS.remove(deps.storage, denom.to_string());
                                 } else {
                                     PENDING_LSM_SHARES.update(
                                         deps.storage,
@@ -339,6 +1097,10 @@ fn execute_puppeteer_hook(
                             }
                         }
                     }
+                    drop_puppeteer_base::msg::Transaction::Transfer { items, .. } => {
+                        settle_claimed_fees(deps.storage)?;
+                        settle_non_native_rewards_held(deps.storage)?;
+                    }
                     drop_puppeteer_base::msg::Transaction::RedeemShares { items, .. } => {
                         let mut sum = 0u128;
                         for item in items {
@@ -406,11 +1168,11 @@ fn execute_tick_idle(
     let mut attrs = vec![attr("action", "tick_idle")];
     let last_idle_call = LAST_IDLE_CALL.load(deps.storage)?;
     let mut messages = vec![];
-    cache_exchange_rate(deps.branch(), env.clone(), None)?;
+    let exchange_rate_notifications = cache_exchange_rate(deps.branch(), env.clone(), None)?;
     if env.block.time.seconds() - last_idle_call < config.idle_min_interval {
         //process non-native rewards
         if let Some(transfer_msg) =
-            get_non_native_rewards_and_fee_transfer_msg(deps.as_ref(), info.clone(), &env)?
+            get_non_native_rewards_and_fee_transfer_msg(deps.branch(), info.clone(), &env)?
         {
             messages.push(transfer_msg);
         } else if let Some(lsm_msg) =
@@ -451,6 +1213,9 @@ fn execute_tick_idle(
             &config.remote_denom,
             true,
         )?;
+        // unpaid protocol fees are no unbond proceeds
+        let ica_balance =
+            ica_balance.saturating_sub(get_reserved_ica_balance(deps.storage, config)?);
 
         let unbonded_batches = if !unbonding_batches.is_empty() {
             unbonding_batches
@@ -474,14 +1239,34 @@ fn execute_tick_idle(
                     .next()
                     .unwrap();
 
-                let (unbonded_amount, slashing_effect) =
+                let (transfer_amount, unbonded_amount, slashing_effect) =
                     if ica_balance < unbonding_batch.expected_amount {
+                        // the insurance fund pays its part straight to the withdrawal manager
+                        let covered_amount = match get_insurance_cover_msg(
+                            deps.as_ref(),
+                            config,
+                            id,
+                            unbonding_batch.expected_amount - ica_balance,
+                        )? {
+                            Some((cover_msg, covered_amount)) => {
+                                messages.push(cover_msg);
+                                attrs.push(attr("insurance_covered_amount", covered_amount));
+                                covered_amount
+                            }
+                            None => Uint128::zero(),
+                        };
+                        let unbonded_amount = ica_balance + covered_amount;
                         (
                             ica_balance,
-                            Decimal::from_ratio(ica_balance, unbonding_batch.expected_amount),
+                            unbonded_amount,
+                            Decimal::from_ratio(unbonded_amount, unbonding_batch.expected_amount),
                         )
                     } else {
-                        (unbonding_batch.expected_amount, Decimal::one())
+                        (
+                            unbonding_batch.expected_amount,
+                            unbonding_batch.expected_amount,
+                            Decimal::one(),
+                        )
                     };
                 unbonding_batch.unbonded_amount = Some(unbonded_amount);
                 unbonding_batch.slashing_effect = Some(slashing_effect);
@@ -490,7 +1275,7 @@ fn execute_tick_idle(
                 Some(TransferReadyBatchesMsg {
                     batch_ids: vec![id],
                     emergency: false,
-                    amount: unbonded_amount,
+                    amount: transfer_amount,
                     recipient: pump_address,
                 })
             }
@@ -499,17 +1284,38 @@ fn execute_tick_idle(
                     .iter()
                     .map(|(_id, batch)| batch.expected_amount)
                     .sum();
-                let (emergency, recipient, amount) = if ica_balance < total_expected_amount {
-                    (
+                let cover_msgs = if ica_balance < total_expected_amount {
+                    get_batches_insurance_cover_msgs(
+                        deps.as_ref(),
+                        config,
+                        &unbonded_batches,
+                        ica_balance,
+                        total_expected_amount,
+                    )?
+                } else {
+                    Some(vec![])
+                };
+                let (emergency, recipient, amount) = match cover_msgs {
+                    Some(cover_msgs) => {
+                        // the insurance fund pays its part straight to the withdrawal manager
+                        let mut covered_amount = Uint128::zero();
+                        for (cover_msg, amount) in cover_msgs {
+                            messages.push(cover_msg);
+                            covered_amount += amount;
+                        }
+                        if !covered_amount.is_zero() {
+                            attrs.push(attr("insurance_covered_amount", covered_amount));
+                        }
+                        (false, pump_address, ica_balance.min(total_expected_amount))
+                    }
+                    None => (
                         true,
                         config
                             .emergency_address
                             .clone()
                             .ok_or(ContractError::EmergencyAddressIsNotSet {})?,
                         ica_balance,
-                    )
-                } else {
-                    (false, pump_address, total_expected_amount)
+                    ),
                 };
                 let mut batch_ids = vec![];
                 for (id, mut batch) in unbonded_batches {
@@ -597,7 +1403,9 @@ fn execute_tick_idle(
         attrs.push(attr("state", "claiming"));
         LAST_IDLE_CALL.save(deps.storage, &env.block.time.seconds())?;
     }
-    Ok(response("execute-tick_idle", CONTRACT_NAME, attrs).add_messages(messages))
+    Ok(response("execute-tick_idle", CONTRACT_NAME, attrs)
+        .add_messages(messages)
+        .add_submessages(exchange_rate_notifications))
 }
 
 fn execute_tick_claiming(
@@ -750,22 +1558,95 @@ fn execute_tick_unbonding(
 }
 
 fn execute_bond(
-    deps: DepsMut<NeutronQuery>,
+    mut deps: DepsMut<NeutronQuery>,
     env: Env,
     info: MessageInfo,
     receiver: Option<String>,
+    r#ref: Option<String>,
+    mint_cw20: bool,
 ) -> ContractResult<Response<NeutronMsg>> {
     let config = CONFIG.load(deps.storage)?;
     let Coin { amount, denom } = cw_utils::one_coin(&info)?;
-    if let Some(bond_limit) = config.bond_limit {
-        if BONDED_AMOUNT.load(deps.storage)? + amount > bond_limit {
+    let receiver = receiver.map_or(Ok::<String, ContractError>(info.sender.to_string()), |a| {
+        deps.api.addr_validate(&a)?;
+        Ok(a)
+    })?;
+    let referrer = validate_referrer(deps.as_ref(), r#ref, &receiver)?;
+    // core receives the minted ld denom and wraps it for the receiver
+    let cw20_wrap = if mint_cw20 {
+        Some((
+            config
+                .cw20_wrapper_contract
+                .clone()
+                .ok_or(ContractError::Cw20WrapperContractIsNotSet {})?,
+            config
+                .ld_denom
+                .clone()
+                .ok_or(ContractError::LDDenomIsNotSet {})?,
+        ))
+    } else {
+        None
+    };
+    let mint_receiver = match cw20_wrap {
+        Some(_) => env.contract.address.to_string(),
+        None => receiver.to_string(),
+    };
+    let (ld_minted, mut attrs, mut msgs) = bond(
+        deps.branch(),
+        env,
+        &config,
+        amount,
+        denom.to_string(),
+        mint_receiver,
+        referrer,
+    )?;
+    // the minted tokens pass through core when they are wrapped
+    attrs.push(attr("receiver", &receiver));
+    if let Some((cw20_wrapper_contract, ld_denom)) = cw20_wrap {
+        attrs.push(attr("cw20_wrapper_contract", &cw20_wrapper_contract));
+        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cw20_wrapper_contract,
+            msg: to_json_binary(&drop_staking_base::msg::cw20_wrapper::ExecuteMsg::Wrap {
+                recipient: Some(receiver.to_string()),
+            })?,
+            funds: vec![Coin {
+                denom: ld_denom,
+                amount: ld_minted,
+            }],
+        }));
+    }
+    let hook_msgs = get_lifecycle_hook_msgs(
+        deps.storage,
+        &LifecycleHookMsg::OnBond {
+            sender: info.sender.to_string(),
+            receiver,
+            amount: Coin { denom, amount },
+            ld_minted,
+        },
+    )?;
+    Ok(response("execute-bond", CONTRACT_NAME, attrs)
+        .add_messages(msgs)
+        .add_submessages(hook_msgs))
+}
+
+fn bond(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    config: &Config,
+    amount: Uint128,
+    denom: String,
+    receiver: String,
+    referrer: Option<String>,
+) -> ContractResult<(Uint128, Vec<Attribute>, Vec<CosmosMsg<NeutronMsg>>)> {
+    if let Some(bond_limit) = config.bond_limit {
+        if BONDED_AMOUNT.load(deps.storage)? + amount > bond_limit {
             return Err(ContractError::BondLimitExceeded {});
         }
     }
     BONDED_AMOUNT.update(deps.storage, |total| StdResult::Ok(total + amount))?;
-    let denom_type = check_denom::check_denom(&deps, &denom, &config)?;
+    let denom_type = check_denom::check_denom(&deps, &denom, amount, config)?;
 
-    if let check_denom::DenomType::LsmShare(remote_denom) = denom_type {
+    if let check_denom::DenomType::LsmShare { remote_denom, path } = denom_type {
         if amount < config.lsm_min_bond_amount {
             return Err(ContractError::LSMBondAmountIsBelowMinimum {
                 min_stake_amount: config.lsm_min_bond_amount,
@@ -773,6 +1654,8 @@ fn execute_bond(
             });
         }
         TOTAL_LSM_SHARES.update(deps.storage, |total| StdResult::Ok(total + amount.u128()))?;
+        // the denom hash includes the path, so it is the same for all shares of the denom
+        PENDING_LSM_SHARE_PATHS.save(deps.storage, denom.clone(), &path)?;
         PENDING_LSM_SHARES.update(deps.storage, denom, |one| {
             let mut new = one.unwrap_or((remote_denom, Uint128::zero()));
             new.1 += amount;
@@ -788,21 +1671,274 @@ fn execute_bond(
     let issue_amount = amount * (Decimal::one() / exchange_rate);
     attrs.push(attr("issue_amount", issue_amount.to_string()));
 
-    let receiver = receiver.map_or(Ok::<String, ContractError>(info.sender.to_string()), |a| {
-        deps.api.addr_validate(&a)?;
-        Ok(a)
-    })?;
-    attrs.push(attr("receiver", receiver.clone()));
+    if let Some(referrer) = referrer {
+        record_referral(deps.storage, &referrer, amount)?;
+        attrs.push(attr("ref", referrer));
+    }
 
     let msgs = vec![CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: config.token_contract,
+        contract_addr: config.token_contract.to_string(),
         msg: to_json_binary(&TokenExecuteMsg::Mint {
             amount: issue_amount,
             receiver,
         })?,
         funds: vec![],
     })];
-    Ok(response("execute-bond", CONTRACT_NAME, attrs).add_messages(msgs))
+    Ok((issue_amount, attrs, msgs))
+}
+
+fn validate_referrer(
+    deps: Deps<NeutronQuery>,
+    r#ref: Option<String>,
+    receiver: &str,
+) -> ContractResult<Option<String>> {
+    r#ref
+        .map(|referrer| -> ContractResult<String> {
+            let referrer = deps.api.addr_validate(&referrer)?.to_string();
+            ensure!(
+                referrer != receiver,
+                ContractError::SelfReferralIsNotAllowed {}
+            );
+            Ok(referrer)
+        })
+        .transpose()
+}
+
+pub fn record_referral(
+    storage: &mut dyn cosmwasm_std::Storage,
+    referrer: &str,
+    amount: Uint128,
+) -> ContractResult<()> {
+    let fee_per_volume = REFERRAL_FEE_PER_VOLUME
+        .may_load(storage)?
+        .unwrap_or_default();
+    let mut referrer_info = referrers_map()
+        .may_load(storage, referrer.to_string())?
+        .unwrap_or(ReferrerInfo {
+            address: referrer.to_string(),
+            bonded_amount: Uint128::zero(),
+            bonds_count: 0,
+            fee_credit: Uint128::zero(),
+            fee_per_volume_checkpoint: fee_per_volume,
+        });
+    // settle the fee credit accrued on the previous volume before increasing it
+    referrer_info.fee_credit = referrer_fee_credit(&referrer_info, fee_per_volume);
+    referrer_info.fee_per_volume_checkpoint = fee_per_volume;
+    referrer_info.bonded_amount += amount;
+    referrer_info.bonds_count += 1;
+    referrers_map().save(storage, referrer.to_string(), &referrer_info)?;
+    TOTAL_REFERRED_AMOUNT.save(
+        storage,
+        &(TOTAL_REFERRED_AMOUNT.may_load(storage)?.unwrap_or_default() + amount),
+    )?;
+    Ok(())
+}
+
+fn referrer_fee_credit(referrer_info: &ReferrerInfo, fee_per_volume: Decimal) -> Uint128 {
+    referrer_info.fee_credit
+        + referrer_info.bonded_amount * (fee_per_volume - referrer_info.fee_per_volume_checkpoint)
+}
+
+fn execute_claim_referral_fee(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    receiver: String,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    let fee_per_volume = REFERRAL_FEE_PER_VOLUME
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let mut referrer_info = referrers_map()
+        .may_load(deps.storage, info.sender.to_string())?
+        .ok_or(ContractError::NoFeesToClaim {})?;
+    let fee_credit = referrer_fee_credit(&referrer_info, fee_per_volume);
+    ensure!(!fee_credit.is_zero(), ContractError::NoFeesToClaim {});
+    referrer_info.fee_credit = Uint128::zero();
+    referrer_info.fee_per_volume_checkpoint = fee_per_volume;
+    referrers_map().save(deps.storage, info.sender.to_string(), &referrer_info)?;
+    // the credits are rounded down, so they never add up to more than the accrued total
+    TOTAL_REFERRAL_FEE_CREDIT.save(
+        deps.storage,
+        &TOTAL_REFERRAL_FEE_CREDIT
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            .saturating_sub(fee_credit),
+    )?;
+    // the credit is paid from the ICA on the remote chain along with the other claimed fees
+    collect_fee(
+        deps.storage,
+        &[FeeRecipient {
+            address: receiver.clone(),
+            share: Decimal::one(),
+        }],
+        &config.remote_denom,
+        fee_credit,
+    )?;
+    Ok(response(
+        "execute-claim_referral_fee",
+        CONTRACT_NAME,
+        vec![
+            attr("action", "claim_referral_fee"),
+            attr("referrer", info.sender),
+            attr("receiver", receiver),
+            attr_coin("amount", fee_credit, config.remote_denom),
+        ],
+    ))
+}
+
+fn execute_bond_with_swap(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    swap_operations: Vec<SwapOperation>,
+    min_ld_out: Uint128,
+    receiver: Option<String>,
+    r#ref: Option<String>,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    let router_contract = config
+        .router_contract
+        .clone()
+        .ok_or(ContractError::RouterContractIsNotSet {})?;
+    let offer = cw_utils::one_coin(&info)?;
+    validate_swap_operations(&swap_operations, &offer.denom, &config.base_denom)?;
+    let receiver = receiver.map_or(Ok::<String, ContractError>(info.sender.to_string()), |a| {
+        deps.api.addr_validate(&a)?;
+        Ok(a)
+    })?;
+    let referrer = validate_referrer(deps.as_ref(), r#ref, &receiver)?;
+    // the router must return at least enough base denom to mint min_ld_out at the current rate
+    let exchange_rate = query_exchange_rate(deps.as_ref(), env.clone(), None)?;
+    let minimum_receive = min_ld_out * exchange_rate;
+    let base_denom_balance = deps
+        .querier
+        .query_balance(env.contract.address, config.base_denom)?
+        .amount;
+    BOND_WITH_SWAP_STATE.save(
+        deps.storage,
+        &BondWithSwapState {
+            sender: info.sender.to_string(),
+            receiver: receiver.to_string(),
+            referrer,
+            min_ld_out,
+            base_denom_balance,
+        },
+    )?;
+    let attrs = vec![
+        attr("action", "bond_with_swap"),
+        attr_coin("offer", offer.amount, &offer.denom),
+        attr("min_ld_out", min_ld_out),
+        attr("minimum_receive", minimum_receive),
+        attr("receiver", receiver),
+    ];
+    let swap_msg = SubMsg::reply_on_success(
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: router_contract,
+            msg: to_json_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                operations: swap_operations,
+                minimum_receive: Some(minimum_receive),
+                to: None,
+                max_spread: None,
+            })?,
+            funds: vec![offer],
+        }),
+        BOND_WITH_SWAP_REPLY_ID,
+    );
+    Ok(response("execute-bond_with_swap", CONTRACT_NAME, attrs).add_submessage(swap_msg))
+}
+
+fn validate_swap_operations(
+    swap_operations: &[SwapOperation],
+    offer_denom: &str,
+    base_denom: &str,
+) -> ContractResult<()> {
+    ensure!(
+        offer_denom != base_denom,
+        ContractError::InvalidSwapOperations {
+            reason: format!("{base_denom} should be bonded without swap"),
+        }
+    );
+    let (first, last) = match (swap_operations.first(), swap_operations.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            return Err(ContractError::InvalidSwapOperations {
+                reason: "no swap operations provided".to_string(),
+            })
+        }
+    };
+    let offer = match first {
+        SwapOperation::NativeSwap { offer_denom, .. } => offer_denom.to_string(),
+        SwapOperation::AstroSwap {
+            offer_asset_info, ..
+        } => offer_asset_info.to_string(),
+    };
+    ensure_eq!(
+        offer,
+        offer_denom,
+        ContractError::InvalidSwapOperations {
+            reason: format!("first operation should offer {offer_denom}"),
+        }
+    );
+    let ask = match last {
+        SwapOperation::NativeSwap { ask_denom, .. } => ask_denom.to_string(),
+        SwapOperation::AstroSwap { ask_asset_info, .. } => ask_asset_info.to_string(),
+    };
+    ensure_eq!(
+        ask,
+        base_denom,
+        ContractError::InvalidSwapOperations {
+            reason: format!("last operation should ask for {base_denom}"),
+        }
+    );
+    Ok(())
+}
+
+fn bond_with_swap_reply(
+    mut deps: DepsMut<NeutronQuery>,
+    env: Env,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    let state = BOND_WITH_SWAP_STATE.load(deps.storage)?;
+    BOND_WITH_SWAP_STATE.remove(deps.storage);
+    let base_denom_balance = deps
+        .querier
+        .query_balance(
+            env.contract.address.to_string(),
+            config.base_denom.to_string(),
+        )?
+        .amount;
+    let amount = base_denom_balance.checked_sub(state.base_denom_balance)?;
+    let denom = config.base_denom.to_string();
+    let (issue_amount, mut attrs, msgs) = bond(
+        deps.branch(),
+        env,
+        &config,
+        amount,
+        denom.to_string(),
+        state.receiver.to_string(),
+        state.referrer,
+    )?;
+    attrs.push(attr("receiver", &state.receiver));
+    ensure!(
+        issue_amount >= state.min_ld_out,
+        ContractError::BondWithSwapSlippageExceeded {
+            min_ld_out: state.min_ld_out,
+            ld_out: issue_amount,
+        }
+    );
+    attrs.push(attr("swapped_amount", amount));
+    let hook_msgs = get_lifecycle_hook_msgs(
+        deps.storage,
+        &LifecycleHookMsg::OnBond {
+            sender: state.sender,
+            receiver: state.receiver,
+            amount: Coin { denom, amount },
+            ld_minted: issue_amount,
+        },
+    )?;
+    Ok(response("reply-bond_with_swap", CONTRACT_NAME, attrs)
+        .add_messages(msgs)
+        .add_submessages(hook_msgs))
 }
 
 fn execute_update_config(
@@ -928,10 +2064,56 @@ fn execute_update_config(
         attrs.push(attr("fee_address", &fee_address));
         config.fee_address = Some(fee_address);
     }
+    if let Some(referral_fee_share) = new_config.referral_fee_share {
+        ensure!(
+            referral_fee_share <= Decimal::one(),
+            ContractError::InvalidReferralFeeShare {}
+        );
+        attrs.push(attr("referral_fee_share", referral_fee_share.to_string()));
+        config.referral_fee_share = Some(referral_fee_share);
+    }
     if let Some(emergency_address) = new_config.emergency_address {
         attrs.push(attr("emergency_address", &emergency_address));
         config.emergency_address = Some(emergency_address);
     }
+    if let Some(router_contract) = new_config.router_contract {
+        attrs.push(attr("router_contract", &router_contract));
+        config.router_contract = Some(deps.api.addr_validate(&router_contract)?.to_string());
+    }
+    if let Some(insurance_contract) = new_config.insurance_contract {
+        attrs.push(attr("insurance_contract", &insurance_contract));
+        config.insurance_contract = Some(deps.api.addr_validate(&insurance_contract)?.to_string());
+    }
+    if let Some(insurance_fee_share) = new_config.insurance_fee_share {
+        attrs.push(attr("insurance_fee_share", insurance_fee_share.to_string()));
+        config.insurance_fee_share = Some(insurance_fee_share);
+    }
+    if let Some(insurance_fee_channel) = new_config.insurance_fee_channel {
+        attrs.push(attr("insurance_fee_channel", &insurance_fee_channel));
+        config.insurance_fee_channel = Some(insurance_fee_channel);
+    }
+    ensure!(
+        config.referral_fee_share.unwrap_or_default()
+            + config.insurance_fee_share.unwrap_or_default()
+            <= Decimal::one(),
+        ContractError::InvalidInsuranceFeeShare {}
+    );
+    if let Some(exchange_rate_notify_delta) = new_config.exchange_rate_notify_delta {
+        attrs.push(attr(
+            "exchange_rate_notify_delta",
+            exchange_rate_notify_delta.to_string(),
+        ));
+        config.exchange_rate_notify_delta = Some(exchange_rate_notify_delta);
+    }
+    if let Some(pump_contract) = new_config.pump_contract {
+        attrs.push(attr("pump_contract", &pump_contract));
+        config.pump_contract = Some(deps.api.addr_validate(&pump_contract)?.to_string());
+    }
+    if let Some(cw20_wrapper_contract) = new_config.cw20_wrapper_contract {
+        attrs.push(attr("cw20_wrapper_contract", &cw20_wrapper_contract));
+        config.cw20_wrapper_contract =
+            Some(deps.api.addr_validate(&cw20_wrapper_contract)?.to_string());
+    }
     if let Some(min_stake_amount) = new_config.min_stake_amount {
         attrs.push(attr("min_stake_amount", min_stake_amount));
         config.min_stake_amount = min_stake_amount;
@@ -995,16 +2177,27 @@ fn execute_unbond(
             },
         ]),
     });
+    let token_id = unbond_batch_id.to_string()
+        + "_"
+        + info.sender.to_string().as_str()
+        + "_"
+        + &unbond_batch.unbond_items.len().to_string();
+    let hook_msgs = get_lifecycle_hook_msgs(
+        deps.storage,
+        &LifecycleHookMsg::OnUnbond {
+            sender: info.sender.to_string(),
+            batch_id: unbond_batch_id,
+            token_id: token_id.to_string(),
+            ld_amount: amount,
+            expected_amount,
+        },
+    )?;
     let msgs = vec![
         CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: config.withdrawal_voucher_contract,
             msg: to_json_binary(&VoucherExecuteMsg::Mint {
                 owner: info.sender.to_string(),
-                token_id: unbond_batch_id.to_string()
-                    + "_"
-                    + info.sender.to_string().as_str()
-                    + "_"
-                    + &unbond_batch.unbond_items.len().to_string(),
+                token_id,
                 token_uri: None,
                 extension,
             })?,
@@ -1019,7 +2212,9 @@ fn execute_unbond(
             }],
         }),
     ];
-    Ok(response("execute-unbond", CONTRACT_NAME, attrs).add_messages(msgs))
+    Ok(response("execute-unbond", CONTRACT_NAME, attrs)
+        .add_messages(msgs)
+        .add_submessages(hook_msgs))
 }
 
 fn get_transfer_pending_balance_msg<T>(
@@ -1052,6 +2247,7 @@ fn get_transfer_pending_balance_msg<T>(
                     reason: IBCTransferReason::Stake,
                     timeout: config.puppeteer_timeout,
                     reply_to: env.contract.address.to_string(),
+                    source_path: None,
                 },
             )?,
             funds: all_funds,
@@ -1073,6 +2269,7 @@ pub fn get_stake_msg<T>(
         &config.remote_denom,
         true,
     )?;
+    let balance = balance.saturating_sub(get_reserved_ica_balance(deps.storage, config)?);
 
     if balance < config.min_stake_amount {
         return Ok(None);
@@ -1097,19 +2294,50 @@ pub fn get_stake_msg<T>(
             },
         )?;
 
-    if fee > Uint128::zero() && config.fee_address.is_some() {
-        COLLECTED_FEES.update(deps.storage, config.remote_denom.to_string(), |fee_item| {
-            if let Some(mut fee_item) = fee_item {
-                fee_item.amount += fee;
-                Ok::<FeeItem, StdError>(fee_item)
-            } else {
-                Ok(FeeItem {
-                    address: config.fee_address.clone().unwrap(),
-                    denom: config.remote_denom.to_string(),
-                    amount: fee,
-                })
+    let fee_recipients = get_fee_recipients(deps.storage, config)?;
+    if fee > Uint128::zero() && !fee_recipients.is_empty() {
+        let mut referral_fee = Uint128::zero();
+        if let Some(referral_fee_share) = config.referral_fee_share {
+            let total_referred_amount = TOTAL_REFERRED_AMOUNT
+                .may_load(deps.storage)?
+                .unwrap_or_default();
+            if !total_referred_amount.is_zero() {
+                referral_fee = fee * referral_fee_share;
+                let fee_per_volume = REFERRAL_FEE_PER_VOLUME
+                    .may_load(deps.storage)?
+                    .unwrap_or_default();
+                REFERRAL_FEE_PER_VOLUME.save(
+                    deps.storage,
+                    &(fee_per_volume + Decimal::from_ratio(referral_fee, total_referred_amount)),
+                )?;
+                TOTAL_REFERRAL_FEE_CREDIT.save(
+                    deps.storage,
+                    &(TOTAL_REFERRAL_FEE_CREDIT
+                        .may_load(deps.storage)?
+                        .unwrap_or_default()
+                        + referral_fee),
+                )?;
             }
-        })?;
+        }
+        let mut insurance_fee = Uint128::zero();
+        if let (Some(insurance_fee_share), Some(_), Some(_)) = (
+            config.insurance_fee_share,
+            &config.insurance_contract,
+            &config.insurance_fee_channel,
+        ) {
+            insurance_fee = fee * insurance_fee_share;
+            INSURANCE_FEES.save(
+                deps.storage,
+                &(INSURANCE_FEES.may_load(deps.storage)?.unwrap_or_default() + insurance_fee),
+            )?;
+        }
+        // the referral share stays on the ICA until the referrers claim it
+        collect_fee(
+            deps.storage,
+            &fee_recipients,
+            &config.remote_denom,
+            fee.checked_sub(referral_fee)?.checked_sub(insurance_fee)?,
+        )?;
     };
 
     Ok(Some(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -1249,14 +2477,238 @@ fn new_unbond(now: u64) -> UnbondBatch {
     }
 }
 
-pub fn get_non_native_rewards_and_fee_transfer_msg<T>(
+fn get_insurance_cover_msg<T>(
     deps: Deps<NeutronQuery>,
-    info: MessageInfo,
-    env: &Env,
-) -> ContractResult<Option<CosmosMsg<T>>> {
+    config: &Config,
+    batch_id: u128,
+    shortfall: Uint128,
+) -> ContractResult<Option<(CosmosMsg<T>, Uint128)>> {
+    let insurance_contract = match &config.insurance_contract {
+        Some(insurance_contract) => insurance_contract,
+        None => return Ok(None),
+    };
+    let covered_amount: Uint128 = deps.querier.query_wasm_smart(
+        insurance_contract,
+        &InsuranceFundQueryMsg::AvailableCoverage { shortfall },
+    )?;
+    if covered_amount.is_zero() {
+        return Ok(None);
+    }
+    Ok(Some((
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: insurance_contract.to_string(),
+            msg: to_json_binary(&InsuranceFundExecuteMsg::CoverShortfall {
+                batch_id,
+                amount: covered_amount,
+            })?,
+            funds: vec![],
+        }),
+        covered_amount,
+    )))
+}
+
+/// Splits the ICA balance across the batches pro rata to their expected amounts and asks
+/// the insurance fund to cover each batch's shortfall. Returns `None` unless the whole
+/// shortfall is covered, the batches then go to the emergency settlement.
+pub fn get_batches_insurance_cover_msgs<T>(
+    deps: Deps<NeutronQuery>,
+    config: &Config,
+    batches: &[(u128, UnbondBatch)],
+    ica_balance: Uint128,
+    total_expected_amount: Uint128,
+) -> ContractResult<Option<Vec<(CosmosMsg<T>, Uint128)>>> {
+    let insurance_contract = match &config.insurance_contract {
+        Some(insurance_contract) => insurance_contract,
+        None => return Ok(None),
+    };
+    let mut cover_msgs = vec![];
+    let mut total_covered = Uint128::zero();
+    let mut remaining_balance = ica_balance;
+    for (i, (id, batch)) in batches.iter().enumerate() {
+        let allocated = if i == batches.len() - 1 {
+            remaining_balance
+        } else {
+            ica_balance.multiply_ratio(batch.expected_amount, total_expected_amount)
+        };
+        remaining_balance -= allocated;
+        let shortfall = batch.expected_amount.saturating_sub(allocated);
+        if shortfall.is_zero() {
+            continue;
+        }
+        match get_insurance_cover_msg(deps, config, *id, shortfall)? {
+            Some((cover_msg, covered)) if covered == shortfall => {
+                total_covered += covered;
+                cover_msgs.push((cover_msg, covered));
+            }
+            _ => return Ok(None),
+        }
+    }
+    // every cover is checked against the whole fund, the payouts must fit it together
+    let insurance_balance = deps
+        .querier
+        .query_balance(insurance_contract, &config.base_denom)?
+        .amount;
+    if total_covered > insurance_balance {
+        return Ok(None);
+    }
+    Ok(Some(cover_msgs))
+}
+
+fn get_fee_recipients(
+    storage: &dyn cosmwasm_std::Storage,
+    config: &Config,
+) -> ContractResult<Vec<FeeRecipient>> {
+    let fee_recipients = FEE_RECIPIENTS.may_load(storage)?.unwrap_or_default();
+    if !fee_recipients.is_empty() {
+        return Ok(fee_recipients);
+    }
+    // fall back to the single fee address when no split is configured
+    Ok(config
+        .fee_address
+        .iter()
+        .map(|address| FeeRecipient {
+            address: address.to_string(),
+            share: Decimal::one(),
+        })
+        .collect())
+}
+
+fn collect_fee(
+    storage: &mut dyn cosmwasm_std::Storage,
+    fee_recipients: &[FeeRecipient],
+    denom: &str,
+    fee: Uint128,
+) -> ContractResult<()> {
+    let mut remaining = fee;
+    for (i, recipient) in fee_recipients.iter().enumerate() {
+        // the last recipient gets the rounding leftovers
+        let amount = if i == fee_recipients.len() - 1 {
+            remaining
+        } else {
+            fee * recipient.share
+        };
+        remaining = remaining.checked_sub(amount)?;
+        if amount.is_zero() {
+            continue;
+        }
+        let key = (recipient.address.to_string(), denom.to_string());
+        COLLECTED_FEES.update(storage, key.clone(), |fee_item| {
+            let mut fee_item = fee_item.unwrap_or(FeeItem {
+                address: recipient.address.to_string(),
+                denom: denom.to_string(),
+                amount: Uint128::zero(),
+            });
+            fee_item.amount += amount;
+            StdResult::Ok(fee_item)
+        })?;
+        FEE_HISTORY.update(storage, key, |history_item| {
+            let mut history_item = history_item.unwrap_or(FeeHistoryItem {
+                address: recipient.address.to_string(),
+                denom: denom.to_string(),
+                collected: Uint128::zero(),
+                claimed: Uint128::zero(),
+            });
+            history_item.collected += amount;
+            StdResult::Ok(history_item)
+        })?;
+    }
+    Ok(())
+}
+
+// protocol fees accrued on the ICA which are neither staked nor unbond proceeds
+fn get_reserved_ica_balance(
+    storage: &dyn cosmwasm_std::Storage,
+    config: &Config,
+) -> ContractResult<Uint128> {
+    let mut reserved = Uint128::zero();
+    for fee_item in COLLECTED_FEES
+        .range(storage, None, None, Order::Ascending)
+        .chain(FEES_TO_CLAIM.range(storage, None, None, Order::Ascending))
+    {
+        let (_, fee_item) = fee_item?;
+        if fee_item.denom == config.remote_denom {
+            reserved += fee_item.amount;
+        }
+    }
+    reserved += TOTAL_REFERRAL_FEE_CREDIT
+        .may_load(storage)?
+        .unwrap_or_default();
+    reserved += INSURANCE_FEES.may_load(storage)?.unwrap_or_default();
+    Ok(reserved)
+}
+
+pub fn settle_claimed_fees(storage: &mut dyn cosmwasm_std::Storage) -> ContractResult<()> {
+    let pending_fees = PENDING_FEE_TRANSFER.may_load(storage)?.unwrap_or_default();
+    for fee_item in pending_fees {
+        let key = (fee_item.address.to_string(), fee_item.denom.to_string());
+        // fees claimed while the transfer was in flight stay for the next one
+        let to_claim = FEES_TO_CLAIM.load(storage, key.clone())?;
+        let remaining = to_claim.amount.checked_sub(fee_item.amount)?;
+        if remaining.is_zero() {
+            FEES_TO_CLAIM.remove(storage, key.clone());
+        } else {
+            FEES_TO_CLAIM.save(
+                storage,
+                key.clone(),
+                &FeeItem {
+                    amount: remaining,
+                    ..to_claim
+                },
+            )?;
+        }
+        FEE_HISTORY.update(storage, key, |history_item| match history_item {
+            Some(mut history_item) => {
+                history_item.claimed += fee_item.amount;
+                StdResult::Ok(history_item)
+            }
+            None => Err(StdError::generic_err("fee history item is not found")),
+        })?;
+    }
+    PENDING_FEE_TRANSFER.remove(storage);
+    let insurance_fee = PENDING_INSURANCE_FEE.may_load(storage)?.unwrap_or_default();
+    if !insurance_fee.is_zero() {
+        INSURANCE_FEES.update(storage, |fees| {
+            StdResult::Ok(fees.checked_sub(insurance_fee)?)
+        })?;
+    }
+    PENDING_INSURANCE_FEE.remove(storage);
+    Ok(())
+}
+
+fn settle_non_native_rewards_held(storage: &mut dyn cosmwasm_std::Storage) -> ContractResult<()> {
+    let pending_held = PENDING_NON_NATIVE_REWARDS_HELD
+        .may_load(storage)?
+        .unwrap_or_default();
+    for (key, held) in pending_held {
+        if held.is_zero() {
+            NON_NATIVE_REWARDS_HELD.remove(storage, key);
+        } else {
+            NON_NATIVE_REWARDS_HELD.save(storage, key, &held)?;
+        }
+    }
+    PENDING_NON_NATIVE_REWARDS_HELD.remove(storage);
+    Ok(())
+}
+
+fn get_non_native_rewards_routes(item: &NonNativeRewardsItem) -> Vec<NonNativeRewardsRoute> {
+    if item.routes.is_empty() {
+        vec![NonNativeRewardsRoute {
+            destination: NonNativeRewardsDestination::Remote {
+                address: item.address.to_string(),
+            },
+            weight: Decimal::one(),
+            min_amount: Uint128::zero(),
+        }]
+    } else {
+        item.routes.clone()
+    }
+}
+
+fn get_non_native_rewards_routing(
+    deps: Deps<NeutronQuery>,
+) -> ContractResult<Vec<(NonNativeRewardsItem, NonNativeRewardsRoutingResponseItem)>> {
     let config = CONFIG.load(deps.storage)?;
     let non_native_rewards_receivers = NON_NATIVE_REWARDS_CONFIG.load(deps.storage)?;
-    let mut items = vec![];
     let rewards: drop_staking_base::msg::puppeteer::BalancesResponse =
         deps.querier.query_wasm_smart(
             config.puppeteer_contract.to_string(),
@@ -1271,56 +2723,159 @@ pub fn get_non_native_rewards_and_fee_transfer_msg<T>(
         .iter()
         .map(|c| (c.denom.clone(), c.amount))
         .collect::<std::collections::HashMap<_, _>>();
-    let default_amount = Uint128::zero();
 
+    let mut routing = vec![];
     for item in non_native_rewards_receivers {
-        let amount = rewards_map.get(&item.denom).unwrap_or(&default_amount);
-        if amount > &item.min_amount {
-            let fee = item.fee * *amount;
-            let amount = *amount - fee;
+        let balance = rewards_map.get(&item.denom).copied().unwrap_or_default();
+        let routes = get_non_native_rewards_routes(&item);
+        let held = (0..routes.len() as u32)
+            .map(|index| {
+                NON_NATIVE_REWARDS_HELD
+                    .may_load(deps.storage, (item.denom.to_string(), index))
+                    .map(|held| held.unwrap_or_default())
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut fee = Uint128::zero();
+        let mut route_amounts = vec![];
+        if balance > item.min_amount {
+            // held shares have been charged with the fee already
+            let unallocated = balance.saturating_sub(held.iter().sum());
+            fee = item.fee * unallocated;
+            let to_split = unallocated - fee;
+            let mut left = to_split;
+            let last_index = routes.len() - 1;
+            for (index, (route, held)) in routes.into_iter().zip(held).enumerate() {
+                // the last route takes the rounding remainder
+                let share = if index == last_index {
+                    left
+                } else {
+                    route.weight * to_split
+                };
+                left -= share;
+                let amount = held + share;
+                let (transfer, held) = if !amount.is_zero() && amount >= route.min_amount {
+                    (amount, Uint128::zero())
+                } else {
+                    (Uint128::zero(), amount)
+                };
+                route_amounts.push(NonNativeRewardsRouteAmount {
+                    destination: route.destination,
+                    transfer,
+                    held,
+                });
+            }
+        } else {
+            for (route, held) in routes.into_iter().zip(held) {
+                route_amounts.push(NonNativeRewardsRouteAmount {
+                    destination: route.destination,
+                    transfer: Uint128::zero(),
+                    held,
+                });
+            }
+        }
+
+        routing.push((
+            item.clone(),
+            NonNativeRewardsRoutingResponseItem {
+                denom: item.denom,
+                balance,
+                fee,
+                routes: route_amounts,
+            },
+        ));
+    }
+    Ok(routing)
+}
+
+pub fn get_non_native_rewards_and_fee_transfer_msg<T>(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    env: &Env,
+) -> ContractResult<Option<CosmosMsg<T>>> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut items = vec![];
+    let mut ibc_items = vec![];
+    let mut pending_held = vec![];
+
+    for (item, routing) in get_non_native_rewards_routing(deps.as_ref())? {
+        for (index, route) in routing.routes.into_iter().enumerate() {
+            pending_held.push(((item.denom.to_string(), index as u32), route.held));
+            if route.transfer.is_zero() {
+                continue;
+            }
+            let amount = cosmwasm_std::Coin {
+                denom: item.denom.to_string(),
+                amount: route.transfer,
+            };
+            match route.destination {
+                NonNativeRewardsDestination::Remote { address } => items.push((address, amount)),
+                NonNativeRewardsDestination::Neutron { channel, address } => {
+                    ibc_items.push(IBCTransferItem {
+                        channel,
+                        receiver: address,
+                        amount,
+                    })
+                }
+            }
+        }
+
+        if (item.fee > Decimal::zero()) && (routing.fee > Uint128::zero()) {
             items.push((
-                item.address,
+                item.fee_address,
                 cosmwasm_std::Coin {
-                    denom: item.denom.clone(),
-                    amount,
+                    denom: item.denom,
+                    amount: routing.fee,
                 },
             ));
-
-            if (item.fee > Decimal::zero()) && (fee > Uint128::zero()) {
-                items.push((
-                    item.fee_address,
-                    cosmwasm_std::Coin {
-                        denom: item.denom,
-                        amount: fee,
-                    },
-                ));
-            }
         }
     }
 
-    let collected_fees = COLLECTED_FEES
+    let fees_to_claim = FEES_TO_CLAIM
         .range_raw(deps.storage, None, None, Order::Ascending)
         .map(|item| item.map(|(_key, value)| value))
         .collect::<StdResult<Vec<FeeItem>>>()?;
 
-    for fee_item in collected_fees {
+    for fee_item in &fees_to_claim {
         items.push((
-            fee_item.address,
+            fee_item.address.to_string(),
             cosmwasm_std::Coin {
-                denom: fee_item.denom,
+                denom: fee_item.denom.to_string(),
                 amount: fee_item.amount,
             },
         ));
     }
 
-    if items.is_empty() {
+    let mut insurance_fee = Uint128::zero();
+    if let (Some(insurance_contract), Some(channel)) = (
+        config.insurance_contract.as_ref(),
+        config.insurance_fee_channel.as_ref(),
+    ) {
+        insurance_fee = INSURANCE_FEES.may_load(deps.storage)?.unwrap_or_default();
+        if !insurance_fee.is_zero() {
+            ibc_items.push(IBCTransferItem {
+                channel: channel.to_string(),
+                receiver: insurance_contract.to_string(),
+                amount: cosmwasm_std::Coin {
+                    denom: config.remote_denom.to_string(),
+                    amount: insurance_fee,
+                },
+            });
+        }
+    }
+
+    if items.is_empty() && ibc_items.is_empty() {
         return Ok(None);
     }
+    PENDING_NON_NATIVE_REWARDS_HELD.save(deps.storage, &pending_held)?;
+    PENDING_FEE_TRANSFER.save(deps.storage, &fees_to_claim)?;
+    PENDING_INSURANCE_FEE.save(deps.storage, &insurance_fee)?;
 
     Ok(Some(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: config.puppeteer_contract,
         msg: to_json_binary(&drop_staking_base::msg::puppeteer::ExecuteMsg::Transfer {
             items,
+            ibc_items,
             timeout: Some(config.puppeteer_timeout),
             reply_to: env.contract.address.to_string(),
         })?,
@@ -1369,7 +2924,7 @@ fn get_pending_redeem_msg<T>(
     })))
 }
 
-fn get_pending_lsm_share_msg<T, X: CustomQuery>(
+pub fn get_pending_lsm_share_msg<T, X: CustomQuery>(
     deps: DepsMut<X>,
     config: &Config,
     env: &Env,
@@ -1384,6 +2939,8 @@ fn get_pending_lsm_share_msg<T, X: CustomQuery>(
                     reason: IBCTransferReason::LSMShare,
                     timeout: config.puppeteer_timeout,
                     reply_to: env.contract.address.to_string(),
+                    // shares bonded before the paths were tracked came through the default one
+                    source_path: PENDING_LSM_SHARE_PATHS.may_load(deps.storage, denom.clone())?,
                 },
             )?,
             funds: {
@@ -1396,13 +2953,18 @@ fn get_pending_lsm_share_msg<T, X: CustomQuery>(
     }
 }
 
-mod check_denom {
+pub mod check_denom {
     use super::*;
+    use drop_staking_base::msg::puppeteer::{LiquidStakingCaps, LiquidStakingCapsResponse};
 
     #[derive(PartialEq)]
     pub enum DenomType {
         Base,
-        LsmShare(String),
+        LsmShare {
+            remote_denom: String,
+            // (port, channel) the share arrived through
+            path: (String, String),
+        },
     }
 
     // XXX: cosmos_sdk_proto defines these structures for me,
@@ -1441,10 +3003,85 @@ mod check_denom {
             })
     }
 
+    // LSM shares we receive stay liquid after redemption on the ICA, so accepting
+    // them must not push the remote chain over any of the liquid staking caps
+    pub fn check_lsm_caps(
+        caps: &LiquidStakingCaps,
+        validator: &str,
+        amount: Uint128,
+    ) -> ContractResult<()> {
+        if !caps.total_bonded_tokens.is_zero() {
+            let global_liquid_share = Decimal::from_ratio(
+                caps.total_liquid_staked_tokens + amount,
+                caps.total_bonded_tokens,
+            );
+            ensure!(
+                global_liquid_share <= caps.global_liquid_staking_cap,
+                ContractError::GlobalLiquidStakingCapExceeded {}
+            );
+        }
+        let validator_info = caps
+            .validators
+            .iter()
+            .find(|v| v.valoper_address == validator)
+            .ok_or_else(|| ContractError::LiquidStakingCapsAreNotTracked {
+                validator: validator.to_string(),
+            })?;
+        let shares = if validator_info.tokens.is_zero() {
+            Decimal::from_ratio(amount, 1u128)
+        } else {
+            validator_info
+                .delegator_shares
+                .checked_mul(Decimal::from_ratio(amount, validator_info.tokens))?
+        };
+        let liquid_shares = validator_info.total_liquid_shares.checked_add(shares)?;
+        if let Some(validator_bond_factor) = caps.validator_bond_factor {
+            ensure!(
+                liquid_shares
+                    <= validator_info
+                        .total_validator_bond_shares
+                        .checked_mul(validator_bond_factor)?,
+                ContractError::ValidatorBondCapExceeded {
+                    validator: validator.to_string(),
+                }
+            );
+        }
+        if !validator_info.delegator_shares.is_zero() {
+            ensure!(
+                liquid_shares / validator_info.delegator_shares
+                    <= caps.validator_liquid_staking_cap,
+                ContractError::ValidatorLiquidStakingCapExceeded {
+                    validator: validator.to_string(),
+                }
+            );
+        }
+        Ok(())
+    }
+
+    pub fn is_lsm_share_path_allowed(
+        storage: &dyn cosmwasm_std::Storage,
+        config: &Config,
+        port: &str,
+        channel: &str,
+    ) -> ContractResult<bool> {
+        if port == "transfer" && channel == config.channel {
+            return Ok(true);
+        }
+        // core stakes on a single remote chain, so the paths are not keyed by chain
+        Ok(LSM_SHARE_PATHS
+            .may_load(storage)?
+            .unwrap_or_default()
+            .iter()
+            .any(|(allowed_port, allowed_channel)| {
+                allowed_port == port && allowed_channel == channel
+            }))
+    }
+
     // TODO: extensive unit tests
     pub fn check_denom(
         deps: &DepsMut<NeutronQuery>,
         denom: &str,
+        amount: Uint128,
         config: &Config,
     ) -> ContractResult<DenomType> {
         if denom == config.base_denom {
@@ -1455,8 +3092,9 @@ mod check_denom {
             .path
             .split_once('/')
             .ok_or(ContractError::InvalidDenom {})?;
-        if port != "transfer" && channel != config.channel {
-            return Err(ContractError::InvalidDenom {});
+        // multihop traces never match since the channel part contains the rest of the path
+        if !is_lsm_share_path_allowed(deps.as_ref().storage, config, port, channel)? {
+            return Err(ContractError::LSMSharePathIsNotWhitelisted { path: trace.path });
         }
 
         let (validator, _unbonding_index) = trace
@@ -1473,8 +3111,24 @@ mod check_denom {
             )?
             .validator;
         if validator_info.is_none() {
-            return Err(ContractError::InvalidDenom {});
+            return Err(ContractError::LSMShareValidatorIsNotWhitelisted {
+                validator: validator.to_string(),
+            });
+        }
+        let caps = deps
+            .querier
+            .query_wasm_smart::<Option<LiquidStakingCapsResponse>>(
+                &config.puppeteer_contract,
+                &drop_puppeteer_base::msg::QueryMsg::Extention {
+                    msg: drop_staking_base::msg::puppeteer::QueryExtMsg::LiquidStakingCaps {},
+                },
+            )?;
+        if let Some((caps, _, _)) = caps {
+            check_lsm_caps(&caps, validator, amount)?;
         }
-        Ok(DenomType::LsmShare(trace.base_denom))
+        Ok(DenomType::LsmShare {
+            remote_denom: trace.base_denom,
+            path: (port.to_string(), channel.to_string()),
+        })
     }
 }
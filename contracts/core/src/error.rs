@@ -42,6 +42,12 @@ pub enum ContractError {
     #[error("Invalid denom")]
     InvalidDenom {},
 
+    #[error("LSM shares received through {path} are not accepted")]
+    LSMSharePathIsNotWhitelisted { path: String },
+
+    #[error("LSM shares of validator {validator} are not accepted, validator is not in the validators set")]
+    LSMShareValidatorIsNotWhitelisted { validator: String },
+
     #[error("Idle min interval is not reached")]
     IdleMinIntervalIsNotReached {},
 
@@ -86,6 +92,63 @@ pub enum ContractError {
     #[error("Previous staking was failed")]
     PreviousStakingWasFailed {},
 
+    #[error("Accepting LSM shares would exceed the global liquid staking cap")]
+    GlobalLiquidStakingCapExceeded {},
+
+    #[error("Accepting LSM shares would exceed the validator bond cap of {validator}")]
+    ValidatorBondCapExceeded { validator: String },
+
+    #[error("Accepting LSM shares would exceed the liquid staking cap of {validator}")]
+    ValidatorLiquidStakingCapExceeded { validator: String },
+
+    #[error("Liquid staking caps are not tracked for validator {validator}")]
+    LiquidStakingCapsAreNotTracked { validator: String },
+
+    #[error("Router contract is not set")]
+    RouterContractIsNotSet {},
+
+    #[error("CW20 wrapper contract is not set")]
+    Cw20WrapperContractIsNotSet {},
+
+    #[error("Invalid swap operations: {reason}")]
+    InvalidSwapOperations { reason: String },
+
+    #[error("Bond with swap slippage exceeded: expected at least {min_ld_out}, got {ld_out}")]
+    BondWithSwapSlippageExceeded {
+        min_ld_out: Uint128,
+        ld_out: Uint128,
+    },
+
+    #[error("Referrer can't be the bond receiver")]
+    SelfReferralIsNotAllowed {},
+
+    #[error("Referral fee share should be less than or equal to 1")]
+    InvalidReferralFeeShare {},
+
+    #[error("Referral and insurance fee shares should sum up to at most 1")]
+    InvalidInsuranceFeeShare {},
+
+    #[error("Invalid fee recipients: {reason}")]
+    InvalidFeeRecipients { reason: String },
+
+    #[error("Invalid non-native rewards routes for {denom}: {reason}")]
+    InvalidNonNativeRewardsRoutes { denom: String, reason: String },
+
+    #[error("No fees to claim")]
+    NoFeesToClaim {},
+
+    #[error("Too many subscribers, max is {max}")]
+    TooManySubscribers { max: u64 },
+
+    #[error("unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Batch {batch_id} is not withdrawing")]
+    BatchIsNotWithdrawing { batch_id: u128 },
+
+    #[error("Pump transfers of {amount} failed, clear them once recovered to reconcile batches")]
+    FailedPumpTransfersPending { amount: Uint128 },
+
     #[error(transparent)]
     PauseError(#[from] PauseError),
 }
@@ -1,31 +1,57 @@
 use std::marker::PhantomData;
 
 use cosmwasm_std::{
-    from_json,
+    attr, from_json,
     testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage},
-    to_json_binary, Addr, Coin, ContractResult, CosmosMsg, Decimal, Empty, MessageInfo, Order,
-    OwnedDeps, Querier, QuerierResult, QueryRequest, StdResult, SystemError, SystemResult,
-    Timestamp, Uint128, WasmMsg, WasmQuery,
+    to_json_binary, Addr, BankMsg, Coin, ContractResult, CosmosMsg, Decimal, Empty, MessageInfo,
+    Order, OwnedDeps, Querier, QuerierResult, QueryRequest, Reply, StdResult, SubMsgResult,
+    SystemError, SystemResult, Timestamp, Uint128, WasmMsg, WasmQuery,
 };
 
-use drop_puppeteer_base::msg::QueryMsg as PuppeteerBaseQueryMsg;
+use astroport::router::SwapOperation;
+use drop_puppeteer_base::msg::{
+    IBCTransferItem, IBCTransferReason, QueryMsg as PuppeteerBaseQueryMsg,
+};
 use drop_staking_base::{
-    msg::puppeteer::{MultiBalances, QueryExtMsg},
+    msg::core::{
+        CollectedFeesResponse, ExecuteMsg, MigrateMsg, NonNativeRewardsConsistencyResponse,
+        NonNativeRewardsRouteAmount, NonNativeRewardsRoutingResponseItem, QueryMsg,
+        ReferrerResponse,
+    },
+    msg::pump::PumpTransferHookMsg,
+    msg::puppeteer::{LiquidStakingCaps, MultiBalances, QueryExtMsg, ValidatorLiquidStaking},
     state::core::{
-        Config, FeeItem, NonNativeRewardsItem, COLLECTED_FEES, LAST_ICA_BALANCE_CHANGE_HEIGHT,
-        NON_NATIVE_REWARDS_CONFIG,
+        unbond_batches_map, Config, ContractState, FeeHistoryItem, FeeItem, FeeRecipient,
+        NonNativeRewardsDestination, NonNativeRewardsItem, NonNativeRewardsRoute, Subscriber,
+        UnbondBatch, UnbondBatchStatus, BONDED_AMOUNT, COLLECTED_FEES, FEE_RECIPIENTS, FSM,
+        INSURANCE_FEES, LAST_ICA_BALANCE_CHANGE_HEIGHT, LEGACY_COLLECTED_FEES, LSM_SHARE_PATHS,
+        NON_NATIVE_REWARDS_CONFIG, NON_NATIVE_REWARDS_HELD, PENDING_HOOK_CALLS, PENDING_LSM_SHARES,
+        PENDING_LSM_SHARE_PATHS, PENDING_NOTIFICATIONS, TOTAL_REFERRAL_FEE_CREDIT,
+    },
+};
+use drop_staking_base::{
+    msg::insurance_fund::{
+        ExecuteMsg as InsuranceFundExecuteMsg, QueryMsg as InsuranceFundQueryMsg,
     },
+    msg::strategy::QueryMsg as StategyQueryMsg,
+    state::core::CONFIG,
 };
-use drop_staking_base::{msg::strategy::QueryMsg as StategyQueryMsg, state::core::CONFIG};
 use neutron_sdk::{
     bindings::{msg::NeutronMsg, query::NeutronQuery},
     interchain_queries::v045::types::Balances,
 };
 
-use crate::contract::{get_non_native_rewards_and_fee_transfer_msg, get_stake_msg};
+use crate::contract::{
+    check_denom::{check_lsm_caps, is_lsm_share_path_allowed},
+    execute, get_batches_insurance_cover_msgs, get_non_native_rewards_and_fee_transfer_msg,
+    get_pending_lsm_share_msg, get_stake_msg, migrate, query, record_referral, reply,
+    settle_claimed_fees, EXCHANGE_RATE_NOTIFICATION_REPLY_ID, LIFECYCLE_HOOK_REPLY_ID,
+};
+use crate::error::ContractError;
 
 pub const MOCK_PUPPETEER_CONTRACT_ADDR: &str = "puppeteer_contract";
 pub const MOCK_STRATEGY_CONTRACT_ADDR: &str = "strategy_contract";
+pub const MOCK_INSURANCE_CONTRACT_ADDR: &str = "insurance_contract";
 
 fn mock_dependencies() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, NeutronQuery> {
     let custom_querier = WasmMockQuerier::new(MockQuerier::new(&[]));
@@ -78,6 +104,9 @@ impl WasmMockQuerier {
                                 );
                                 to_json_binary(&data)
                             }
+                            QueryExtMsg::NonNativeRewardsDenoms {} => {
+                                to_json_binary(&vec!["denom".to_string()])
+                            }
                             QueryExtMsg::Balances {} => {
                                 let data = (
                                     Balances {
@@ -113,6 +142,16 @@ impl WasmMockQuerier {
                     };
                     return SystemResult::Ok(ContractResult::from(reply));
                 }
+                if contract_addr == MOCK_INSURANCE_CONTRACT_ADDR {
+                    let q: InsuranceFundQueryMsg = from_json(msg).unwrap();
+                    let reply = match q {
+                        InsuranceFundQueryMsg::AvailableCoverage { shortfall } => {
+                            to_json_binary(&shortfall.min(Uint128::new(15)))
+                        }
+                        _ => todo!(),
+                    };
+                    return SystemResult::Ok(ContractResult::from(reply));
+                }
                 SystemResult::Err(SystemError::NoSuchContract {
                     addr: contract_addr.to_string(),
                 })
@@ -154,6 +193,14 @@ fn get_default_config(fee: Option<Decimal>) -> Config {
         bond_limit: None,
         emergency_address: None,
         min_stake_amount: Uint128::new(100),
+        router_contract: None,
+        referral_fee_share: None,
+        insurance_contract: None,
+        insurance_fee_share: None,
+        insurance_fee_channel: None,
+        exchange_rate_notify_delta: None,
+        pump_contract: None,
+        cw20_wrapper_contract: None,
     }
 }
 
@@ -181,6 +228,7 @@ fn get_non_native_rewards_and_fee_transfer_msg_success() {
                 min_amount: Uint128::new(100),
                 fee: Decimal::from_atomics(1u32, 1).unwrap(),
                 fee_address: "fee_address".to_string(),
+                routes: vec![],
             }],
         )
         .unwrap();
@@ -188,7 +236,7 @@ fn get_non_native_rewards_and_fee_transfer_msg_success() {
     let info = mock_info("addr0000", &[Coin::new(1000, "untrn")]);
 
     let result: CosmosMsg<NeutronMsg> =
-        get_non_native_rewards_and_fee_transfer_msg(deps.as_ref(), info, &mock_env())
+        get_non_native_rewards_and_fee_transfer_msg(deps.as_mut(), info, &mock_env())
             .unwrap()
             .unwrap();
 
@@ -213,6 +261,7 @@ fn get_non_native_rewards_and_fee_transfer_msg_success() {
                         }
                     )
                 ],
+                ibc_items: vec![],
                 timeout: Some(60),
                 reply_to: "cosmos2contract".to_string()
             })
@@ -237,6 +286,7 @@ fn get_non_native_rewards_and_fee_transfer_msg_zero_fee() {
                 min_amount: Uint128::new(100),
                 fee: Decimal::zero(),
                 fee_address: "fee_address".to_string(),
+                routes: vec![],
             }],
         )
         .unwrap();
@@ -244,7 +294,7 @@ fn get_non_native_rewards_and_fee_transfer_msg_zero_fee() {
     let info = mock_info("addr0000", &[Coin::new(1000, "untrn")]);
 
     let result: CosmosMsg<NeutronMsg> =
-        get_non_native_rewards_and_fee_transfer_msg(deps.as_ref(), info, &mock_env())
+        get_non_native_rewards_and_fee_transfer_msg(deps.as_mut(), info, &mock_env())
             .unwrap()
             .unwrap();
 
@@ -260,6 +310,7 @@ fn get_non_native_rewards_and_fee_transfer_msg_zero_fee() {
                         amount: Uint128::new(150)
                     }
                 )],
+                ibc_items: vec![],
                 timeout: Some(60),
                 reply_to: "cosmos2contract".to_string()
             })
@@ -322,7 +373,7 @@ fn get_stake_msg_success() {
 }
 
 #[test]
-fn get_stake_msg_zero_fee() {
+fn get_stake_msg_splits_fee_and_claims() {
     let mut deps = mock_dependencies();
 
     setup_config(&mut deps);
@@ -330,11 +381,29 @@ fn get_stake_msg_zero_fee() {
     LAST_ICA_BALANCE_CHANGE_HEIGHT
         .save(deps.as_mut().storage, &1)
         .unwrap();
+    NON_NATIVE_REWARDS_CONFIG
+        .save(deps.as_mut().storage, &vec![])
+        .unwrap();
+    FEE_RECIPIENTS
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                FeeRecipient {
+                    address: "treasury".to_string(),
+                    share: Decimal::percent(33),
+                },
+                FeeRecipient {
+                    address: "insurance".to_string(),
+                    share: Decimal::percent(67),
+                },
+            ],
+        )
+        .unwrap();
 
-    let stake_msg: CosmosMsg<NeutronMsg> = get_stake_msg(
+    get_stake_msg::<NeutronMsg>(
         deps.as_mut(),
         &mock_env(),
-        &get_default_config(None),
+        &get_default_config(Decimal::from_atomics(1u32, 1).ok()),
         &MessageInfo {
             sender: Addr::unchecked("addr0000"),
             funds: vec![Coin::new(200, "untrn")],
@@ -343,17 +412,1491 @@ fn get_stake_msg_zero_fee() {
     .unwrap()
     .unwrap();
 
+    let treasury_fee = FeeItem {
+        address: "treasury".to_string(),
+        denom: "remote_denom".to_string(),
+        amount: Uint128::new(6),
+    };
+    let insurance_fee = FeeItem {
+        address: "insurance".to_string(),
+        denom: "remote_denom".to_string(),
+        amount: Uint128::new(14),
+    };
+    let collected_fees: CollectedFeesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::CollectedFees {}).unwrap()).unwrap();
+    assert_eq!(
+        collected_fees,
+        CollectedFeesResponse {
+            collected: vec![insurance_fee.clone(), treasury_fee.clone()],
+            to_claim: vec![],
+        }
+    );
+    // fees are not transferred until claimed
+    assert_eq!(
+        get_non_native_rewards_and_fee_transfer_msg::<NeutronMsg>(
+            deps.as_mut(),
+            mock_info("addr0000", &[]),
+            &mock_env()
+        )
+        .unwrap(),
+        None
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::ClaimFees {},
+    )
+    .unwrap();
+
+    let collected_fees: CollectedFeesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::CollectedFees {}).unwrap()).unwrap();
+    assert_eq!(
+        collected_fees,
+        CollectedFeesResponse {
+            collected: vec![],
+            to_claim: vec![insurance_fee.clone(), treasury_fee.clone()],
+        }
+    );
+    assert_eq!(
+        get_non_native_rewards_and_fee_transfer_msg::<NeutronMsg>(
+            deps.as_mut(),
+            mock_info("addr0000", &[]),
+            &mock_env()
+        )
+        .unwrap(),
+        Some(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "puppeteer_contract".to_string(),
+            msg: to_json_binary(&drop_staking_base::msg::puppeteer::ExecuteMsg::Transfer {
+                items: vec![
+                    ("insurance".to_string(), Coin::new(14, "remote_denom")),
+                    ("treasury".to_string(), Coin::new(6, "remote_denom")),
+                ],
+                ibc_items: vec![],
+                timeout: Some(60),
+                reply_to: "cosmos2contract".to_string()
+            })
+            .unwrap(),
+            funds: vec![]
+        }))
+    );
+
+    let fee_history: Vec<FeeHistoryItem> =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::FeeHistory {}).unwrap()).unwrap();
+    assert_eq!(
+        fee_history,
+        vec![
+            FeeHistoryItem {
+                address: "insurance".to_string(),
+                denom: "remote_denom".to_string(),
+                collected: Uint128::new(14),
+                claimed: Uint128::zero(),
+            },
+            FeeHistoryItem {
+                address: "treasury".to_string(),
+                denom: "remote_denom".to_string(),
+                collected: Uint128::new(6),
+                claimed: Uint128::zero(),
+            },
+        ]
+    );
+
+    // fees waiting on the ICA are neither restaked nor charged again
+    let stake_msg = get_stake_msg::<NeutronMsg>(
+        deps.as_mut(),
+        &mock_env(),
+        &get_default_config(Decimal::from_atomics(1u32, 1).ok()),
+        &MessageInfo {
+            sender: Addr::unchecked("addr0000"),
+            funds: vec![],
+        },
+    )
+    .unwrap()
+    .unwrap();
     assert_eq!(
         stake_msg,
         CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: "puppeteer_contract".to_string(),
             msg: to_json_binary(&drop_staking_base::msg::puppeteer::ExecuteMsg::Delegate {
-                items: vec![("valoper_address".to_string(), Uint128::new(200))],
+                items: vec![("valoper_address".to_string(), Uint128::new(162))],
                 timeout: Some(60),
                 reply_to: "cosmos2contract".to_string(),
             })
             .unwrap(),
+            funds: vec![],
+        })
+    );
+    // fees claimed while the transfer is in flight are kept for the next one
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::ClaimFees {},
+    )
+    .unwrap();
+    settle_claimed_fees(deps.as_mut().storage).unwrap();
+
+    let collected_fees: CollectedFeesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::CollectedFees {}).unwrap()).unwrap();
+    assert_eq!(
+        collected_fees,
+        CollectedFeesResponse {
+            collected: vec![],
+            to_claim: vec![
+                FeeItem {
+                    amount: Uint128::new(13),
+                    ..insurance_fee
+                },
+                FeeItem {
+                    amount: Uint128::new(5),
+                    ..treasury_fee
+                },
+            ],
+        }
+    );
+    let fee_history: Vec<FeeHistoryItem> =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::FeeHistory {}).unwrap()).unwrap();
+    assert_eq!(
+        fee_history,
+        vec![
+            FeeHistoryItem {
+                address: "insurance".to_string(),
+                denom: "remote_denom".to_string(),
+                collected: Uint128::new(27),
+                claimed: Uint128::new(14),
+            },
+            FeeHistoryItem {
+                address: "treasury".to_string(),
+                denom: "remote_denom".to_string(),
+                collected: Uint128::new(11),
+                claimed: Uint128::new(6),
+            },
+        ]
+    );
+}
+
+#[test]
+fn migrate_drops_legacy_collected_fees() {
+    let mut deps = mock_dependencies();
+    let fee_item = FeeItem {
+        address: "fee_address".to_string(),
+        denom: "remote_denom".to_string(),
+        amount: Uint128::new(20),
+    };
+    LEGACY_COLLECTED_FEES
+        .save(deps.as_mut().storage, "remote_denom".to_string(), &fee_item)
+        .unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    // the legacy totals were transferred already, claiming them again would pay them twice
+    let collected_fees: CollectedFeesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::CollectedFees {}).unwrap()).unwrap();
+    assert_eq!(
+        collected_fees,
+        CollectedFeesResponse {
+            collected: vec![],
+            to_claim: vec![],
+        }
+    );
+    assert!(LEGACY_COLLECTED_FEES.is_empty(deps.as_ref().storage));
+    assert_eq!(
+        cw2::get_contract_version(deps.as_ref().storage)
+            .unwrap()
+            .version,
+        env!("CARGO_PKG_VERSION")
+    );
+}
+
+#[test]
+fn non_native_rewards_routing() {
+    let mut deps = mock_dependencies();
+    setup_config(&mut deps);
+    cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("owner")).unwrap();
+
+    let remote = NonNativeRewardsDestination::Remote {
+        address: "remote_address".to_string(),
+    };
+    let neutron = NonNativeRewardsDestination::Neutron {
+        channel: "channel-0".to_string(),
+        address: "rewards_manager".to_string(),
+    };
+    let mut item = NonNativeRewardsItem {
+        address: "address".to_string(),
+        denom: "denom".to_string(),
+        min_amount: Uint128::new(100),
+        fee: Decimal::percent(10),
+        fee_address: "fee_address".to_string(),
+        routes: vec![
+            NonNativeRewardsRoute {
+                destination: remote.clone(),
+                weight: Decimal::percent(50),
+                min_amount: Uint128::zero(),
+            },
+            NonNativeRewardsRoute {
+                destination: neutron.clone(),
+                weight: Decimal::percent(40),
+                min_amount: Uint128::new(100),
+            },
+        ],
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateNonNativeRewardsReceivers {
+            items: vec![item.clone()],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidNonNativeRewardsRoutes {
+            denom: "denom".to_string(),
+            reason: "weights should sum up to 1".to_string()
+        }
+    );
+
+    item.routes[1].weight = Decimal::percent(50);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateNonNativeRewardsReceivers { items: vec![item] },
+    )
+    .unwrap();
+
+    // 150 on the ICA: 15 goes to fees, the neutron share of 68 is below its threshold
+    let routing: Vec<NonNativeRewardsRoutingResponseItem> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::NonNativeRewardsRouting {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        routing,
+        vec![NonNativeRewardsRoutingResponseItem {
+            denom: "denom".to_string(),
+            balance: Uint128::new(150),
+            fee: Uint128::new(15),
+            routes: vec![
+                NonNativeRewardsRouteAmount {
+                    destination: remote.clone(),
+                    transfer: Uint128::new(67),
+                    held: Uint128::zero(),
+                },
+                NonNativeRewardsRouteAmount {
+                    destination: neutron.clone(),
+                    transfer: Uint128::zero(),
+                    held: Uint128::new(68),
+                },
+            ],
+        }]
+    );
+
+    // once the held share is settled, only the rest of the balance is split
+    NON_NATIVE_REWARDS_HELD
+        .save(
+            deps.as_mut().storage,
+            ("denom".to_string(), 1),
+            &Uint128::new(68),
+        )
+        .unwrap();
+    let result: CosmosMsg<NeutronMsg> = get_non_native_rewards_and_fee_transfer_msg(
+        deps.as_mut(),
+        mock_info("addr0000", &[]),
+        &mock_env(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        result,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "puppeteer_contract".to_string(),
+            msg: to_json_binary(&drop_staking_base::msg::puppeteer::ExecuteMsg::Transfer {
+                items: vec![
+                    ("remote_address".to_string(), Coin::new(37, "denom")),
+                    ("fee_address".to_string(), Coin::new(8, "denom")),
+                ],
+                ibc_items: vec![IBCTransferItem {
+                    channel: "channel-0".to_string(),
+                    receiver: "rewards_manager".to_string(),
+                    amount: Coin::new(105, "denom"),
+                }],
+                timeout: Some(60),
+                reply_to: "cosmos2contract".to_string()
+            })
+            .unwrap(),
+            funds: vec![]
+        })
+    );
+}
+
+#[test]
+fn non_native_rewards_denoms_sync() {
+    let mut deps = mock_dependencies();
+    setup_config(&mut deps);
+    cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("owner")).unwrap();
+
+    let item = NonNativeRewardsItem {
+        address: "address".to_string(),
+        denom: "denom".to_string(),
+        min_amount: Uint128::new(100),
+        fee: Decimal::zero(),
+        fee_address: "fee_address".to_string(),
+        routes: vec![],
+    };
+    // the denoms are already in sync, so the attached fee is refunded
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[Coin::new(1000, "untrn")]),
+        ExecuteMsg::UpdateNonNativeRewardsReceivers {
+            items: vec![item.clone()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|message| message.msg)
+            .collect::<Vec<_>>(),
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            to_address: "owner".to_string(),
+            amount: vec![Coin::new(1000, "untrn")],
+        })]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[Coin::new(1000, "untrn")]),
+        ExecuteMsg::UpdateNonNativeRewardsReceivers {
+            items: vec![
+                NonNativeRewardsItem {
+                    denom: "other_denom".to_string(),
+                    ..item.clone()
+                },
+                item,
+            ],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "puppeteer_contract".to_string(),
+            msg: to_json_binary(
+                &drop_staking_base::msg::puppeteer::ExecuteMsg::RegisterNonNativeRewardsBalancesQuery {
+                    denoms: vec!["denom".to_string(), "other_denom".to_string()],
+                }
+            )
+            .unwrap(),
+            funds: vec![Coin::new(1000, "untrn")],
+        })
+    );
+
+    let consistency: NonNativeRewardsConsistencyResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::NonNativeRewardsConsistency {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        consistency,
+        NonNativeRewardsConsistencyResponse {
+            missing_in_puppeteer: vec!["other_denom".to_string()],
+            missing_in_core: vec![],
+        }
+    );
+}
+
+#[test]
+fn update_fee_recipients_invalid_shares() {
+    let mut deps = mock_dependencies();
+    cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("owner")).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateFeeRecipients {
+            recipients: vec![
+                FeeRecipient {
+                    address: "treasury".to_string(),
+                    share: Decimal::percent(50),
+                },
+                FeeRecipient {
+                    address: "insurance".to_string(),
+                    share: Decimal::percent(40),
+                },
+            ],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidFeeRecipients {
+            reason: "shares should sum up to 1".to_string()
+        }
+    );
+}
+
+#[test]
+fn get_stake_msg_sends_insurance_fee_share() {
+    let mut deps = mock_dependencies();
+    let config = Config {
+        insurance_contract: Some("insurance_contract".to_string()),
+        insurance_fee_share: Some(Decimal::percent(25)),
+        insurance_fee_channel: Some("channel-1".to_string()),
+        ..get_default_config(Decimal::from_atomics(1u32, 1).ok())
+    };
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+    LAST_ICA_BALANCE_CHANGE_HEIGHT
+        .save(deps.as_mut().storage, &1)
+        .unwrap();
+    NON_NATIVE_REWARDS_CONFIG
+        .save(deps.as_mut().storage, &vec![])
+        .unwrap();
+
+    get_stake_msg::<NeutronMsg>(
+        deps.as_mut(),
+        &mock_env(),
+        &config,
+        &MessageInfo {
+            sender: Addr::unchecked("addr0000"),
+            funds: vec![],
+        },
+    )
+    .unwrap()
+    .unwrap();
+
+    // a quarter of the 20 fee is carved out for the insurance fund
+    let collected_fees: CollectedFeesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::CollectedFees {}).unwrap()).unwrap();
+    assert_eq!(
+        collected_fees.collected,
+        vec![FeeItem {
+            address: "fee_address".to_string(),
+            denom: "remote_denom".to_string(),
+            amount: Uint128::new(15),
+        }]
+    );
+    assert_eq!(
+        get_non_native_rewards_and_fee_transfer_msg::<NeutronMsg>(
+            deps.as_mut(),
+            mock_info("addr0000", &[]),
+            &mock_env()
+        )
+        .unwrap(),
+        Some(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "puppeteer_contract".to_string(),
+            msg: to_json_binary(&drop_staking_base::msg::puppeteer::ExecuteMsg::Transfer {
+                items: vec![],
+                ibc_items: vec![IBCTransferItem {
+                    channel: "channel-1".to_string(),
+                    receiver: "insurance_contract".to_string(),
+                    amount: Coin::new(5, "remote_denom"),
+                }],
+                timeout: Some(60),
+                reply_to: "cosmos2contract".to_string()
+            })
+            .unwrap(),
+            funds: vec![]
+        }))
+    );
+
+    settle_claimed_fees(deps.as_mut().storage).unwrap();
+    assert_eq!(
+        INSURANCE_FEES.load(deps.as_ref().storage).unwrap(),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn batches_shortfall_is_covered_only_in_full() {
+    let mut deps = mock_dependencies();
+    let config = Config {
+        insurance_contract: Some(MOCK_INSURANCE_CONTRACT_ADDR.to_string()),
+        ..get_default_config(None)
+    };
+    let batches = [(0u128, 100u128), (1u128, 60u128)]
+        .into_iter()
+        .map(|(id, expected_amount)| {
+            (
+                id,
+                UnbondBatch {
+                    total_amount: Uint128::new(expected_amount),
+                    expected_amount: Uint128::new(expected_amount),
+                    expected_release: 0,
+                    unbond_items: vec![],
+                    status: UnbondBatchStatus::Unbonding,
+                    slashing_effect: None,
+                    unbonded_amount: None,
+                    withdrawed_amount: None,
+                    created: 0,
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    deps.querier.base.update_balance(
+        MOCK_INSURANCE_CONTRACT_ADDR,
+        vec![Coin::new(25, "base_denom")],
+    );
+
+    // 140 splits into 87 and 53, the fund covers both 13 and 7
+    let cover_msgs = get_batches_insurance_cover_msgs::<NeutronMsg>(
+        deps.as_ref(),
+        &config,
+        &batches,
+        Uint128::new(140),
+        Uint128::new(160),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        cover_msgs,
+        vec![
+            (
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: MOCK_INSURANCE_CONTRACT_ADDR.to_string(),
+                    msg: to_json_binary(&InsuranceFundExecuteMsg::CoverShortfall {
+                        batch_id: 0,
+                        amount: Uint128::new(13),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                Uint128::new(13)
+            ),
+            (
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: MOCK_INSURANCE_CONTRACT_ADDR.to_string(),
+                    msg: to_json_binary(&InsuranceFundExecuteMsg::CoverShortfall {
+                        batch_id: 1,
+                        amount: Uint128::new(7),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                Uint128::new(7)
+            ),
+        ]
+    );
+
+    // 130 leaves batch 0 short of 19, above the per batch payout
+    assert_eq!(
+        get_batches_insurance_cover_msgs::<NeutronMsg>(
+            deps.as_ref(),
+            &config,
+            &batches,
+            Uint128::new(130),
+            Uint128::new(160),
+        )
+        .unwrap(),
+        None
+    );
+
+    // the covers fit the payout limit one by one but not the fund together
+    deps.querier.base.update_balance(
+        MOCK_INSURANCE_CONTRACT_ADDR,
+        vec![Coin::new(15, "base_denom")],
+    );
+    assert_eq!(
+        get_batches_insurance_cover_msgs::<NeutronMsg>(
+            deps.as_ref(),
+            &config,
+            &batches,
+            Uint128::new(140),
+            Uint128::new(160),
+        )
+        .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn get_stake_msg_credits_referrers() {
+    let mut deps = mock_dependencies();
+
+    setup_config(&mut deps);
+
+    LAST_ICA_BALANCE_CHANGE_HEIGHT
+        .save(deps.as_mut().storage, &1)
+        .unwrap();
+    record_referral(deps.as_mut().storage, "referrer1", Uint128::new(100)).unwrap();
+    record_referral(deps.as_mut().storage, "referrer2", Uint128::new(100)).unwrap();
+    record_referral(deps.as_mut().storage, "referrer1", Uint128::new(200)).unwrap();
+
+    get_stake_msg::<NeutronMsg>(
+        deps.as_mut(),
+        &mock_env(),
+        &Config {
+            referral_fee_share: Some(Decimal::percent(50)),
+            ..get_default_config(Decimal::from_atomics(1u32, 1).ok())
+        },
+        &MessageInfo {
+            sender: Addr::unchecked("addr0000"),
             funds: vec![Coin::new(200, "untrn")],
+        },
+    )
+    .unwrap()
+    .unwrap();
+
+    // the referral part of the 20 fee is split by referred volume
+    let top_referrers: Vec<ReferrerResponse> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TopReferrers { limit: None },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        top_referrers,
+        vec![
+            ReferrerResponse {
+                address: "referrer1".to_string(),
+                bonded_amount: Uint128::new(300),
+                bonds_count: 2,
+                fee_credit: Uint128::new(7),
+            },
+            ReferrerResponse {
+                address: "referrer2".to_string(),
+                bonded_amount: Uint128::new(100),
+                bonds_count: 1,
+                fee_credit: Uint128::new(2),
+            },
+        ]
+    );
+
+    // volume bonded after the fee collection doesn't earn from it
+    record_referral(deps.as_mut().storage, "referrer2", Uint128::new(100)).unwrap();
+    let referrer: Option<ReferrerResponse> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Referrer {
+                address: "referrer2".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        referrer,
+        Some(ReferrerResponse {
+            address: "referrer2".to_string(),
+            bonded_amount: Uint128::new(200),
+            bonds_count: 2,
+            fee_credit: Uint128::new(2),
+        })
+    );
+
+    // the referral part is carved out of the fee recipients' share
+    let collected_fees: CollectedFeesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::CollectedFees {}).unwrap()).unwrap();
+    assert_eq!(
+        collected_fees.collected,
+        vec![FeeItem {
+            address: "fee_address".to_string(),
+            denom: "remote_denom".to_string(),
+            amount: Uint128::new(10),
+        }]
+    );
+    // the unclaimed referral part stays reserved on the ICA
+    assert_eq!(
+        TOTAL_REFERRAL_FEE_CREDIT
+            .load(deps.as_ref().storage)
+            .unwrap(),
+        Uint128::new(10)
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("referrer1", &[]),
+        ExecuteMsg::ClaimReferralFee {
+            receiver: "remote_receiver".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.events[0].attributes[3], attr("amount", "7remote_denom"));
+    assert_eq!(
+        TOTAL_REFERRAL_FEE_CREDIT
+            .load(deps.as_ref().storage)
+            .unwrap(),
+        Uint128::new(3)
+    );
+    let collected_fees: CollectedFeesResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::CollectedFees {}).unwrap()).unwrap();
+    assert_eq!(
+        collected_fees.collected,
+        vec![
+            FeeItem {
+                address: "fee_address".to_string(),
+                denom: "remote_denom".to_string(),
+                amount: Uint128::new(10),
+            },
+            FeeItem {
+                address: "remote_receiver".to_string(),
+                denom: "remote_denom".to_string(),
+                amount: Uint128::new(7),
+            },
+        ]
+    );
+
+    // the credit can be claimed only once
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("referrer1", &[]),
+        ExecuteMsg::ClaimReferralFee {
+            receiver: "remote_receiver".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoFeesToClaim {});
+}
+
+#[test]
+fn get_stake_msg_zero_fee() {
+    let mut deps = mock_dependencies();
+
+    setup_config(&mut deps);
+
+    LAST_ICA_BALANCE_CHANGE_HEIGHT
+        .save(deps.as_mut().storage, &1)
+        .unwrap();
+
+    let stake_msg: CosmosMsg<NeutronMsg> = get_stake_msg(
+        deps.as_mut(),
+        &mock_env(),
+        &get_default_config(None),
+        &MessageInfo {
+            sender: Addr::unchecked("addr0000"),
+            funds: vec![Coin::new(200, "untrn")],
+        },
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        stake_msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "puppeteer_contract".to_string(),
+            msg: to_json_binary(&drop_staking_base::msg::puppeteer::ExecuteMsg::Delegate {
+                items: vec![("valoper_address".to_string(), Uint128::new(200))],
+                timeout: Some(60),
+                reply_to: "cosmos2contract".to_string(),
+            })
+            .unwrap(),
+            funds: vec![Coin::new(200, "untrn")],
+        })
+    );
+}
+
+fn get_default_liquid_staking_caps() -> LiquidStakingCaps {
+    LiquidStakingCaps {
+        validator_bond_factor: Some(Decimal::from_ratio(250u128, 1u128)),
+        global_liquid_staking_cap: Decimal::percent(25),
+        validator_liquid_staking_cap: Decimal::percent(50),
+        total_liquid_staked_tokens: Uint128::new(200),
+        total_bonded_tokens: Uint128::new(1000),
+        validators: vec![ValidatorLiquidStaking {
+            valoper_address: "valoper_address".to_string(),
+            tokens: Uint128::new(500),
+            delegator_shares: Decimal::from_ratio(500u128, 1u128),
+            total_validator_bond_shares: Decimal::one(),
+            total_liquid_shares: Decimal::from_ratio(100u128, 1u128),
+        }],
+    }
+}
+
+#[test]
+fn check_lsm_caps_success() {
+    check_lsm_caps(
+        &get_default_liquid_staking_caps(),
+        "valoper_address",
+        Uint128::new(50),
+    )
+    .unwrap();
+}
+
+#[test]
+fn check_lsm_caps_global_cap_exceeded() {
+    let err = check_lsm_caps(
+        &get_default_liquid_staking_caps(),
+        "valoper_address",
+        Uint128::new(51),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::GlobalLiquidStakingCapExceeded {});
+}
+
+#[test]
+fn check_lsm_caps_validator_bond_cap_exceeded() {
+    let mut caps = get_default_liquid_staking_caps();
+    caps.validator_bond_factor = Some(Decimal::from_ratio(120u128, 1u128));
+    let err = check_lsm_caps(&caps, "valoper_address", Uint128::new(50)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ValidatorBondCapExceeded {
+            validator: "valoper_address".to_string()
+        }
+    );
+}
+
+#[test]
+fn check_lsm_caps_validator_bond_cap_disabled() {
+    let mut caps = get_default_liquid_staking_caps();
+    caps.validator_bond_factor = None;
+    caps.validators[0].total_validator_bond_shares = Decimal::zero();
+    check_lsm_caps(&caps, "valoper_address", Uint128::new(50)).unwrap();
+}
+
+#[test]
+fn check_lsm_caps_validator_liquid_cap_exceeded() {
+    let mut caps = get_default_liquid_staking_caps();
+    caps.validator_liquid_staking_cap = Decimal::percent(25);
+    let err = check_lsm_caps(&caps, "valoper_address", Uint128::new(50)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ValidatorLiquidStakingCapExceeded {
+            validator: "valoper_address".to_string()
+        }
+    );
+}
+
+#[test]
+fn check_lsm_caps_validator_is_not_tracked() {
+    let err = check_lsm_caps(
+        &get_default_liquid_staking_caps(),
+        "unknown_valoper_address",
+        Uint128::new(50),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::LiquidStakingCapsAreNotTracked {
+            validator: "unknown_valoper_address".to_string()
+        }
+    );
+}
+
+#[test]
+fn lsm_share_path_whitelist() {
+    let mut deps = mock_dependencies();
+    let config = get_default_config(None);
+
+    assert!(is_lsm_share_path_allowed(&deps.storage, &config, "transfer", "channel").unwrap());
+    assert!(!is_lsm_share_path_allowed(&deps.storage, &config, "transfer", "channel-1").unwrap());
+    assert!(!is_lsm_share_path_allowed(&deps.storage, &config, "custom", "channel").unwrap());
+
+    LSM_SHARE_PATHS
+        .save(
+            deps.as_mut().storage,
+            &vec![("transfer".to_string(), "channel-1".to_string())],
+        )
+        .unwrap();
+
+    assert!(is_lsm_share_path_allowed(&deps.storage, &config, "transfer", "channel-1").unwrap());
+    assert!(!is_lsm_share_path_allowed(
+        &deps.storage,
+        &config,
+        "transfer",
+        "channel-1/transfer/channel-2"
+    )
+    .unwrap());
+}
+
+#[test]
+fn bond_with_swap_router_is_not_set() {
+    let mut deps = mock_dependencies();
+    setup_config(&mut deps);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[Coin::new(1000, "untrn")]),
+        ExecuteMsg::BondWithSwap {
+            swap_operations: vec![SwapOperation::NativeSwap {
+                offer_denom: "untrn".to_string(),
+                ask_denom: "base_denom".to_string(),
+            }],
+            min_ld_out: Uint128::new(900),
+            receiver: None,
+            r#ref: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::RouterContractIsNotSet {});
+}
+
+#[test]
+fn bond_with_swap_invalid_operations() {
+    let mut deps = mock_dependencies();
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                router_contract: Some("router_contract".to_string()),
+                ..get_default_config(None)
+            },
+        )
+        .unwrap();
+    let bond_with_swap = |swap_operations: Vec<SwapOperation>| ExecuteMsg::BondWithSwap {
+        swap_operations,
+        min_ld_out: Uint128::new(900),
+        receiver: None,
+        r#ref: None,
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[Coin::new(1000, "untrn")]),
+        bond_with_swap(vec![]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidSwapOperations {
+            reason: "no swap operations provided".to_string()
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[Coin::new(1000, "untrn")]),
+        bond_with_swap(vec![SwapOperation::NativeSwap {
+            offer_denom: "uusdc".to_string(),
+            ask_denom: "base_denom".to_string(),
+        }]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidSwapOperations {
+            reason: "first operation should offer untrn".to_string()
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[Coin::new(1000, "untrn")]),
+        bond_with_swap(vec![
+            SwapOperation::NativeSwap {
+                offer_denom: "untrn".to_string(),
+                ask_denom: "uusdc".to_string(),
+            },
+            SwapOperation::NativeSwap {
+                offer_denom: "uusdc".to_string(),
+                ask_denom: "uatom".to_string(),
+            },
+        ]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidSwapOperations {
+            reason: "last operation should ask for base_denom".to_string()
+        }
+    );
+}
+
+#[test]
+fn bond_mint_cw20_wraps_minted_tokens() {
+    let mut deps = mock_dependencies();
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                ld_denom: Some("ld_denom".to_string()),
+                ..get_default_config(None)
+            },
+        )
+        .unwrap();
+    BONDED_AMOUNT
+        .save(deps.as_mut().storage, &Uint128::zero())
+        .unwrap();
+    FSM.set_initial_state(deps.as_mut().storage, ContractState::Staking)
+        .unwrap();
+    let bond = ExecuteMsg::Bond {
+        receiver: Some("receiver".to_string()),
+        r#ref: None,
+        mint_cw20: true,
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[Coin::new(1000, "base_denom")]),
+        bond.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Cw20WrapperContractIsNotSet {});
+
+    CONFIG
+        .update(deps.as_mut().storage, |config| {
+            StdResult::Ok(Config {
+                cw20_wrapper_contract: Some("cw20_wrapper_contract".to_string()),
+                ..config
+            })
+        })
+        .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[Coin::new(1000, "base_denom")]),
+        bond,
+    )
+    .unwrap();
+    // core mints to itself first and wraps the minted amount for the receiver
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|msg| msg.msg)
+            .collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token_contract".to_string(),
+                msg: to_json_binary(&drop_staking_base::msg::token::ExecuteMsg::Mint {
+                    amount: Uint128::new(1000),
+                    receiver: "cosmos2contract".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "cw20_wrapper_contract".to_string(),
+                msg: to_json_binary(&drop_staking_base::msg::cw20_wrapper::ExecuteMsg::Wrap {
+                    recipient: Some("receiver".to_string()),
+                })
+                .unwrap(),
+                funds: vec![Coin::new(1000, "ld_denom")],
+            }),
+        ]
+    );
+    assert!(res.events[0]
+        .attributes
+        .contains(&attr("receiver", "receiver")));
+}
+
+#[test]
+fn exchange_rate_subscriber_failure_is_isolated() {
+    let mut deps = mock_dependencies();
+    cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("owner")).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        ExecuteMsg::AddExchangeRateSubscriber {
+            address: "subscriber".to_string(),
+            gas_limit: 100_000,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddExchangeRateSubscriber {
+            address: "subscriber".to_string(),
+            gas_limit: 100_000,
+        },
+    )
+    .unwrap();
+    PENDING_NOTIFICATIONS
+        .save(deps.as_mut().storage, &vec!["subscriber".to_string()])
+        .unwrap();
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: EXCHANGE_RATE_NOTIFICATION_REPLY_ID,
+            result: SubMsgResult::Err("out of gas".to_string()),
+        },
+    )
+    .unwrap();
+
+    let subscribers: Vec<(String, Subscriber)> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ExchangeRateSubscribers {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        subscribers,
+        vec![(
+            "subscriber".to_string(),
+            Subscriber {
+                gas_limit: 100_000,
+                failures: 1,
+            }
+        )]
+    );
+}
+
+#[test]
+fn lifecycle_hook_failure_is_isolated() {
+    let mut deps = mock_dependencies();
+    cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("owner")).unwrap();
+    for hook in ["points_program", "vault"] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::AddLifecycleHook {
+                address: hook.to_string(),
+                gas_limit: 200_000,
+            },
+        )
+        .unwrap();
+    }
+    PENDING_HOOK_CALLS
+        .save(
+            deps.as_mut().storage,
+            &vec!["points_program".to_string(), "vault".to_string()],
+        )
+        .unwrap();
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: LIFECYCLE_HOOK_REPLY_ID + 1,
+            result: SubMsgResult::Err("out of gas".to_string()),
+        },
+    )
+    .unwrap();
+
+    let hooks: Vec<(String, Subscriber)> =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::LifecycleHooks {}).unwrap()).unwrap();
+    assert_eq!(
+        hooks,
+        vec![
+            (
+                "points_program".to_string(),
+                Subscriber {
+                    gas_limit: 200_000,
+                    failures: 0,
+                }
+            ),
+            (
+                "vault".to_string(),
+                Subscriber {
+                    gas_limit: 200_000,
+                    failures: 1,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn compound_rewards() {
+    let mut deps = mock_dependencies();
+    setup_config(&mut deps);
+    FSM.set_initial_state(deps.as_mut().storage, ContractState::Staking)
+        .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rewards_manager", &[Coin::new(100, "untrn")]),
+        ExecuteMsg::CompoundRewards {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PaymentError(cw_utils::PaymentError::MissingDenom(
+            "base_denom".to_string()
+        ))
+    );
+
+    for _ in 0..2 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("rewards_manager", &[Coin::new(100, "base_denom")]),
+            ExecuteMsg::CompoundRewards {},
+        )
+        .unwrap();
+    }
+    let total: Uint128 =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::CompoundedRewards {}).unwrap())
+            .unwrap();
+    assert_eq!(total, Uint128::new(200));
+}
+
+#[test]
+fn pump_hook_leaves_withdrawing_batches_to_reconciliation() {
+    let mut deps = mock_dependencies();
+    let mut config = get_default_config(Decimal::from_atomics(1u32, 1).ok());
+    config.pump_contract = Some("pump_contract".to_string());
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+    cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("owner")).unwrap();
+    FSM.set_initial_state(deps.as_mut().storage, ContractState::Idle)
+        .unwrap();
+    for (id, unbonded_amount) in [(0u128, 100u128), (1u128, 50u128)] {
+        unbond_batches_map()
+            .save(
+                deps.as_mut().storage,
+                id,
+                &UnbondBatch {
+                    total_amount: Uint128::new(unbonded_amount),
+                    expected_amount: Uint128::new(unbonded_amount),
+                    expected_release: 0,
+                    unbond_items: vec![],
+                    status: UnbondBatchStatus::Withdrawing,
+                    slashing_effect: None,
+                    unbonded_amount: Some(Uint128::new(unbonded_amount)),
+                    withdrawed_amount: None,
+                    created: 0,
+                },
+            )
+            .unwrap();
+    }
+    let hook = PumpTransferHookMsg {
+        amount: Coin::new(120, "remote_denom"),
+        receiver: "withdrawal_manager_contract".to_string(),
+        succeeded: true,
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::PumpHook(hook.clone()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pump_contract", &[]),
+        ExecuteMsg::PumpHook(hook),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    // a delivered amount doesn't tell which batches it belongs to
+    for id in [0, 1] {
+        assert_eq!(
+            unbond_batches_map()
+                .load(deps.as_ref().storage, id)
+                .unwrap()
+                .status,
+            UnbondBatchStatus::Withdrawing
+        );
+    }
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::ReconcileWithdrawingBatches { batch_ids: vec![0] },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::OwnershipError(_)));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ReconcileWithdrawingBatches { batch_ids: vec![0] },
+    )
+    .unwrap();
+    assert_eq!(
+        res.events[0].attributes.last().unwrap(),
+        &cosmwasm_std::attr("withdrawn_batch_id", "0")
+    );
+    assert_eq!(
+        unbond_batches_map()
+            .load(deps.as_ref().storage, 0)
+            .unwrap()
+            .status,
+        UnbondBatchStatus::Withdrawn
+    );
+    assert_eq!(
+        unbond_batches_map()
+            .load(deps.as_ref().storage, 1)
+            .unwrap()
+            .status,
+        UnbondBatchStatus::Withdrawing
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ReconcileWithdrawingBatches {
+            batch_ids: vec![1, 0],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BatchIsNotWithdrawing { batch_id: 0 });
+}
+
+#[test]
+fn failed_pump_transfer_blocks_reconciliation() {
+    let mut deps = mock_dependencies();
+    let mut config = get_default_config(Decimal::from_atomics(1u32, 1).ok());
+    config.pump_contract = Some("pump_contract".to_string());
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+    cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("owner")).unwrap();
+    for (id, status) in [
+        (0u128, UnbondBatchStatus::Withdrawing),
+        (1u128, UnbondBatchStatus::WithdrawingEmergency),
+    ] {
+        unbond_batches_map()
+            .save(
+                deps.as_mut().storage,
+                id,
+                &UnbondBatch {
+                    total_amount: Uint128::new(100),
+                    expected_amount: Uint128::new(100),
+                    expected_release: 0,
+                    unbond_items: vec![],
+                    status,
+                    slashing_effect: None,
+                    unbonded_amount: None,
+                    withdrawed_amount: None,
+                    created: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pump_contract", &[]),
+        ExecuteMsg::PumpHook(PumpTransferHookMsg {
+            amount: Coin::new(100, "remote_denom"),
+            receiver: "withdrawal_manager_contract".to_string(),
+            succeeded: false,
+        }),
+    )
+    .unwrap();
+    let failed_amount: Uint128 =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::FailedPumpTransfers {}).unwrap())
+            .unwrap();
+    assert_eq!(failed_amount, Uint128::new(100));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ReconcileWithdrawingBatches { batch_ids: vec![0] },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::FailedPumpTransfersPending {
+            amount: Uint128::new(100)
+        }
+    );
+    // emergency batches don't go through the pump
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ReconcileWithdrawingBatches { batch_ids: vec![1] },
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::ClearFailedPumpTransfers {
+            amount: Uint128::new(100),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::OwnershipError(_)));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ClearFailedPumpTransfers {
+            amount: Uint128::new(100),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ReconcileWithdrawingBatches { batch_ids: vec![0] },
+    )
+    .unwrap();
+    assert_eq!(
+        unbond_batches_map()
+            .load(deps.as_ref().storage, 0)
+            .unwrap()
+            .status,
+        UnbondBatchStatus::Withdrawn
+    );
+}
+
+#[test]
+fn pending_lsm_share_goes_back_over_its_path() {
+    let mut deps = mock_dependencies();
+    let config = get_default_config(None);
+    PENDING_LSM_SHARES
+        .save(
+            deps.as_mut().storage,
+            "ibc/share".to_string(),
+            &("valoper/1".to_string(), Uint128::new(100)),
+        )
+        .unwrap();
+    PENDING_LSM_SHARE_PATHS
+        .save(
+            deps.as_mut().storage,
+            "ibc/share".to_string(),
+            &("transfer".to_string(), "channel-1".to_string()),
+        )
+        .unwrap();
+
+    let msg: CosmosMsg<NeutronMsg> =
+        get_pending_lsm_share_msg(deps.as_mut(), &config, &mock_env(), vec![])
+            .unwrap()
+            .unwrap();
+    assert_eq!(
+        msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "puppeteer_contract".to_string(),
+            msg: to_json_binary(
+                &drop_staking_base::msg::puppeteer::ExecuteMsg::IBCTransfer {
+                    timeout: 60,
+                    reason: IBCTransferReason::LSMShare,
+                    reply_to: "cosmos2contract".to_string(),
+                    source_path: Some(("transfer".to_string(), "channel-1".to_string())),
+                }
+            )
+            .unwrap(),
+            funds: vec![Coin::new(100, "ibc/share")],
         })
     );
 }
@@ -0,0 +1,199 @@
+use cosmwasm_std::{
+    attr, coins, entry_point, to_json_binary, Attribute, BankMsg, Binary, Deps, DepsMut, Env,
+    MessageInfo, Response, StdResult, Uint128,
+};
+use cw2::set_contract_version;
+use cw20_base::{
+    allowances::{
+        execute_burn_from, execute_decrease_allowance, execute_increase_allowance,
+        execute_send_from, execute_transfer_from, query_allowance,
+    },
+    contract::{
+        execute_burn, execute_mint, execute_send, execute_transfer, execute_update_marketing,
+        execute_upload_logo, query_balance, query_download_logo, query_marketing_info,
+        query_token_info,
+    },
+    enumerable::{query_all_accounts, query_owner_allowances, query_spender_allowances},
+};
+use drop_helpers::answer::response;
+use drop_staking_base::{
+    msg::cw20_wrapper::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
+    state::cw20_wrapper::{Config, CONFIG},
+};
+
+use crate::error::ContractResult;
+
+const CONTRACT_NAME: &str = concat!("crates.io:drop-staking__", env!("CARGO_PKG_NAME"));
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> ContractResult<Response> {
+    let attrs: Vec<Attribute> = vec![
+        attr("ld_denom", &msg.ld_denom),
+        attr("name", &msg.name),
+        attr("symbol", &msg.symbol),
+    ];
+    // the wrapper is the only minter, the supply is backed by the ld denom it holds
+    cw20_base::contract::instantiate(
+        deps.branch(),
+        env.clone(),
+        info,
+        cw20_base::msg::InstantiateMsg {
+            name: msg.name,
+            symbol: msg.symbol,
+            decimals: msg.decimals,
+            initial_balances: vec![],
+            mint: Some(cw20::MinterResponse {
+                minter: env.contract.address.to_string(),
+                cap: None,
+            }),
+            marketing: msg.marketing,
+        },
+    )?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            ld_denom: msg.ld_denom,
+        },
+    )?;
+    Ok(response("instantiate", CONTRACT_NAME, attrs))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::Balance { address } => to_json_binary(&query_balance(deps, address)?),
+        QueryMsg::TokenInfo {} => to_json_binary(&query_token_info(deps)?),
+        QueryMsg::Allowance { owner, spender } => {
+            to_json_binary(&query_allowance(deps, owner, spender)?)
+        }
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&query_owner_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::AllSpenderAllowances {
+            spender,
+            start_after,
+            limit,
+        } => to_json_binary(&query_spender_allowances(
+            deps,
+            spender,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AllAccounts { start_after, limit } => {
+            to_json_binary(&query_all_accounts(deps, start_after, limit)?)
+        }
+        QueryMsg::MarketingInfo {} => to_json_binary(&query_marketing_info(deps)?),
+        QueryMsg::DownloadLogo {} => to_json_binary(&query_download_logo(deps)?),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> ContractResult<Response> {
+    Ok(match msg {
+        ExecuteMsg::Wrap { recipient } => execute_wrap(deps, env, info, recipient)?,
+        ExecuteMsg::Unwrap { amount, recipient } => {
+            execute_unwrap(deps, env, info, amount, recipient)?
+        }
+        ExecuteMsg::Burn { amount } => execute_unwrap(deps, env, info, amount, None)?,
+        ExecuteMsg::BurnFrom { owner, amount } => {
+            let config = CONFIG.load(deps.storage)?;
+            let to_address = info.sender.to_string();
+            execute_burn_from(deps, env, info, owner, amount)?.add_message(BankMsg::Send {
+                to_address,
+                amount: coins(amount.u128(), config.ld_denom),
+            })
+        }
+        ExecuteMsg::Transfer { recipient, amount } => {
+            execute_transfer(deps, env, info, recipient, amount)?
+        }
+        ExecuteMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => execute_send(deps, env, info, contract, amount, msg)?,
+        ExecuteMsg::IncreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => execute_increase_allowance(deps, env, info, spender, amount, expires)?,
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            amount,
+            expires,
+        } => execute_decrease_allowance(deps, env, info, spender, amount, expires)?,
+        ExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+        } => execute_transfer_from(deps, env, info, owner, recipient, amount)?,
+        ExecuteMsg::SendFrom {
+            owner,
+            contract,
+            amount,
+            msg,
+        } => execute_send_from(deps, env, info, owner, contract, amount, msg)?,
+        ExecuteMsg::UpdateMarketing {
+            project,
+            description,
+            marketing,
+        } => execute_update_marketing(deps, env, info, project, description, marketing)?,
+        ExecuteMsg::UploadLogo(logo) => execute_upload_logo(deps, env, info, logo)?,
+    })
+}
+
+fn execute_wrap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let amount = cw_utils::must_pay(&info, &config.ld_denom)?;
+    let recipient = recipient.unwrap_or(info.sender.to_string());
+    let minter = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    Ok(execute_mint(deps, env, minter, recipient, amount)?)
+}
+
+fn execute_unwrap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    recipient: Option<String>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let to_address = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?.to_string(),
+        None => info.sender.to_string(),
+    };
+    Ok(
+        execute_burn(deps, env, info, amount)?.add_message(BankMsg::Send {
+            to_address,
+            amount: coins(amount.u128(), config.ld_denom),
+        }),
+    )
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new())
+}
@@ -0,0 +1,16 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Cw20Error(#[from] cw20_base::ContractError),
+
+    #[error("{0}")]
+    PaymentError(#[from] cw_utils::PaymentError),
+}
+
+pub type ContractResult<T> = Result<T, ContractError>;
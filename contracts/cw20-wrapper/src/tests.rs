@@ -0,0 +1,223 @@
+use cosmwasm_std::{
+    coins, from_json,
+    testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier},
+    BankMsg, Binary, CosmosMsg, MemoryStorage, OwnedDeps, Uint128,
+};
+use cw20::{BalanceResponse, Cw20ReceiveMsg, TokenInfoResponse};
+use drop_staking_base::msg::cw20_wrapper::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+use crate::{
+    contract::{execute, instantiate, query},
+    error::ContractError,
+};
+
+fn setup() -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        InstantiateMsg {
+            ld_denom: "ld_denom".to_string(),
+            name: "Drop Token".to_string(),
+            symbol: "DROP".to_string(),
+            decimals: 6,
+            marketing: None,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &coins(1000, "ld_denom")),
+        ExecuteMsg::Wrap { recipient: None },
+    )
+    .unwrap();
+    deps
+}
+
+fn query_cw20_balance(
+    deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    address: &str,
+) -> u128 {
+    let balance: BalanceResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    balance.balance.u128()
+}
+
+fn query_total_supply(deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>) -> u128 {
+    let token_info: TokenInfoResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::TokenInfo {}).unwrap()).unwrap();
+    token_info.total_supply.u128()
+}
+
+#[test]
+fn wrap() {
+    let mut deps = setup();
+    assert_eq!(query_cw20_balance(&deps, "holder"), 1000);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bonder", &coins(500, "ld_denom")),
+        ExecuteMsg::Wrap {
+            recipient: Some("receiver".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(query_cw20_balance(&deps, "receiver"), 500);
+    assert_eq!(query_cw20_balance(&deps, "bonder"), 0);
+    assert_eq!(query_total_supply(&deps), 1500);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bonder", &coins(500, "other_denom")),
+        ExecuteMsg::Wrap { recipient: None },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PaymentError(cw_utils::PaymentError::MissingDenom("ld_denom".to_string()))
+    );
+}
+
+#[test]
+fn unwrap_releases_ld_denom() {
+    let mut deps = setup();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::Unwrap {
+            amount: Uint128::new(300),
+            recipient: Some("receiver".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "receiver".to_string(),
+            amount: coins(300, "ld_denom"),
+        })
+    );
+    assert_eq!(query_cw20_balance(&deps, "holder"), 700);
+    assert_eq!(query_total_supply(&deps), 700);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::Burn {
+            amount: Uint128::new(200),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "holder".to_string(),
+            amount: coins(200, "ld_denom"),
+        })
+    );
+    assert_eq!(query_total_supply(&deps), 500);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::Unwrap {
+            amount: Uint128::new(600),
+            recipient: None,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::Cw20Error(cw20_base::ContractError::Std(_))
+    ));
+}
+
+#[test]
+fn burn_from_releases_ld_denom_to_spender() {
+    let mut deps = setup();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::IncreaseAllowance {
+            spender: "bridge".to_string(),
+            amount: Uint128::new(400),
+            expires: None,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bridge", &[]),
+        ExecuteMsg::BurnFrom {
+            owner: "holder".to_string(),
+            amount: Uint128::new(400),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "bridge".to_string(),
+            amount: coins(400, "ld_denom"),
+        })
+    );
+    assert_eq!(query_cw20_balance(&deps, "holder"), 600);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bridge", &[]),
+        ExecuteMsg::BurnFrom {
+            owner: "holder".to_string(),
+            amount: Uint128::new(1),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Cw20Error(_)));
+}
+
+#[test]
+fn send() {
+    let mut deps = setup();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::Send {
+            contract: "protocol".to_string(),
+            amount: Uint128::new(100),
+            msg: Binary::from(b"{}"),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        Cw20ReceiveMsg {
+            sender: "holder".to_string(),
+            amount: Uint128::new(100),
+            msg: Binary::from(b"{}"),
+        }
+        .into_cosmos_msg("protocol")
+        .unwrap()
+    );
+    assert_eq!(query_cw20_balance(&deps, "protocol"), 100);
+    assert_eq!(query_total_supply(&deps), 1000);
+}
@@ -0,0 +1,14 @@
+use cosmwasm_schema::write_api;
+use drop_staking_base::msg::{
+    dex_exchange_handler::QueryMsg,
+    dex_exchange_handler::{ExecuteMsg, InstantiateMsg, MigrateMsg},
+};
+
+fn main() {
+    write_api! {
+        instantiate: InstantiateMsg,
+        query: QueryMsg,
+        execute: ExecuteMsg,
+        migrate: MigrateMsg
+    }
+}
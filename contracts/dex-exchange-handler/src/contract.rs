@@ -0,0 +1,338 @@
+use cosmwasm_std::{
+    attr, coin, entry_point, to_json_binary, Attribute, Binary, Decimal, Deps, DepsMut, Env,
+    Int128, MessageInfo, QueryRequest, Response, StdError, StdResult, Uint128,
+};
+use cw2::set_contract_version;
+use drop_helpers::answer::{attr_coin, response};
+use drop_staking_base::error::dex_exchange_handler::{ContractError, ContractResult};
+use drop_staking_base::msg::dex_exchange_handler::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+};
+use drop_staking_base::msg::reward_handler::SimulateExchangeResponse;
+use drop_staking_base::state::dex_exchange_handler::{Config, CONFIG, ROUTES};
+use neutron_sdk::bindings::{
+    dex::{
+        msg::DexMsg,
+        query::{DexQuery, EstimateMultiHopSwapResponse},
+        types::{MultiHopRoute, PrecDec},
+    },
+    msg::NeutronMsg,
+    query::NeutronQuery,
+};
+
+const CONTRACT_NAME: &str = concat!("crates.io:drop-staking__", env!("CARGO_PKG_NAME"));
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut<NeutronQuery>,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> ContractResult<Response<NeutronMsg>> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let owner = deps.api.addr_validate(&msg.owner)?;
+    cw_ownable::initialize_owner(deps.storage, deps.api, Some(owner.as_ref()))?;
+
+    let core_contract = deps.api.addr_validate(&msg.core_contract)?;
+    validate_max_spread(msg.max_spread)?;
+
+    let config = Config {
+        owner: msg.owner.clone(),
+        core_contract: core_contract.to_string(),
+        from_denom: msg.from_denom.clone(),
+        to_denom: msg.to_denom.clone(),
+        min_rewards: msg.min_rewards,
+        max_spread: msg.max_spread,
+    };
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(response(
+        "instantiate",
+        CONTRACT_NAME,
+        [
+            attr("owner", msg.owner),
+            attr("core_contract", msg.core_contract),
+            attr("from_denom", msg.from_denom),
+            attr("to_denom", msg.to_denom),
+            attr("min_rewards", msg.min_rewards),
+            attr("max_spread", msg.max_spread.to_string()),
+        ],
+    ))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps<NeutronQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => query_config(deps),
+        QueryMsg::SimulateExchange { amount } => {
+            let config = CONFIG.load(deps.storage)?;
+            if amount.denom != config.from_denom {
+                return Err(StdError::generic_err(format!(
+                    "Invalid denom {}, expected {}",
+                    amount.denom, config.from_denom
+                )));
+            }
+            let routes = get_routes(deps, &config)?;
+            to_json_binary(&SimulateExchangeResponse {
+                amount: estimate_swap(deps, &env, &config, routes, amount.amount)?,
+            })
+        }
+    }
+}
+
+fn query_config(deps: Deps<NeutronQuery>) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let routes = ROUTES.may_load(deps.storage)?.unwrap_or_default();
+
+    to_json_binary(&ConfigResponse {
+        owner: config.owner,
+        core_contract: config.core_contract,
+        from_denom: config.from_denom,
+        to_denom: config.to_denom,
+        min_rewards: config.min_rewards,
+        max_spread: config.max_spread,
+        routes,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> ContractResult<Response<NeutronMsg>> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            owner,
+            core_contract,
+            from_denom,
+            to_denom,
+            min_rewards,
+            max_spread,
+        } => exec_update_config(
+            deps,
+            info,
+            owner,
+            core_contract,
+            from_denom,
+            to_denom,
+            min_rewards,
+            max_spread,
+        ),
+        ExecuteMsg::UpdateRoutes { routes } => exec_update_routes(deps, info, routes),
+        ExecuteMsg::Exchange {} => exec_exchange(deps, env, info),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn exec_update_config(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    owner: Option<String>,
+    core_contract: Option<String>,
+    from_denom: Option<String>,
+    to_denom: Option<String>,
+    min_rewards: Option<Uint128>,
+    max_spread: Option<Decimal>,
+) -> ContractResult<Response<NeutronMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let mut attrs: Vec<Attribute> = Vec::new();
+    if let Some(owner) = owner {
+        let owner = deps.api.addr_validate(&owner)?;
+        config.owner = owner.to_string();
+        cw_ownable::initialize_owner(deps.storage, deps.api, Some(owner.as_ref()))?;
+        attrs.push(attr("owner", owner))
+    }
+
+    if let Some(core_contract) = core_contract {
+        let core_contract = deps.api.addr_validate(&core_contract)?;
+        config.core_contract = core_contract.to_string();
+        attrs.push(attr("core_contract", core_contract))
+    }
+
+    if from_denom.is_some() || to_denom.is_some() {
+        ROUTES.remove(deps.storage);
+        attrs.push(attr("clear_routes", "1".to_string()));
+    }
+
+    if let Some(from_denom) = from_denom {
+        config.from_denom = from_denom.to_string();
+        attrs.push(attr("from_denom", from_denom))
+    }
+
+    if let Some(to_denom) = to_denom {
+        config.to_denom = to_denom.to_string();
+        attrs.push(attr("to_denom", to_denom))
+    }
+
+    if let Some(min_rewards) = min_rewards {
+        config.min_rewards = min_rewards;
+        attrs.push(attr("min_rewards", min_rewards))
+    }
+
+    if let Some(max_spread) = max_spread {
+        validate_max_spread(max_spread)?;
+        config.max_spread = max_spread;
+        attrs.push(attr("max_spread", max_spread.to_string()))
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(response("config_update", CONTRACT_NAME, attrs))
+}
+
+fn exec_update_routes(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    routes: Vec<Vec<String>>,
+) -> ContractResult<Response<NeutronMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    for route in &routes {
+        if route.len() < 2 {
+            return Err(ContractError::InvalidRoute {
+                reason: "route must contain at least two denoms".to_string(),
+            });
+        }
+        if route.first() != Some(&config.from_denom) || route.last() != Some(&config.to_denom) {
+            return Err(ContractError::InvalidRoute {
+                reason: format!(
+                    "route must start with {} and end with {}",
+                    config.from_denom, config.to_denom
+                ),
+            });
+        }
+    }
+
+    let attrs = vec![attr("new_routes", routes.len().to_string())];
+    if routes.is_empty() {
+        ROUTES.remove(deps.storage);
+    } else {
+        ROUTES.save(deps.storage, &routes)?;
+    }
+
+    Ok(response("update_routes", CONTRACT_NAME, attrs))
+}
+
+fn exec_exchange(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only the attached funds are swapped, the proceeds go back to the caller
+    let balance = coin(
+        info.funds
+            .iter()
+            .filter(|fund| fund.denom == config.from_denom)
+            .map(|fund| fund.amount.u128())
+            .sum(),
+        config.from_denom.clone(),
+    );
+
+    if balance.amount < config.min_rewards {
+        return Err(ContractError::LowBalance {
+            min_amount: config.min_rewards,
+            amount: balance.amount,
+            denom: config.from_denom,
+        });
+    }
+
+    let routes = get_routes(deps.as_ref(), &config)?;
+    let estimated_amount =
+        estimate_swap(deps.as_ref(), &env, &config, routes.clone(), balance.amount)?;
+    if estimated_amount.is_zero() {
+        return Err(ContractError::ZeroSimulatedReturn {
+            amount: balance.amount,
+            denom: balance.denom,
+        });
+    }
+
+    // The swap fails if the execution price drops below the estimated one by
+    // more than the allowed spread.
+    let exit_limit_price = Decimal::from_ratio(estimated_amount, balance.amount)
+        * (Decimal::one() - config.max_spread);
+
+    let exchange_rewards_msg = NeutronMsg::Dex(DexMsg::MultiHopSwap {
+        receiver: info.sender.to_string(),
+        routes,
+        amount_in: to_int128(balance.amount)?,
+        exit_limit_price: PrecDec {
+            i: exit_limit_price.to_string(),
+        },
+        pick_best_route: true,
+    });
+
+    Ok(response(
+        "exchange",
+        CONTRACT_NAME,
+        [
+            attr_coin("swap_amount", balance.amount, balance.denom),
+            attr("estimated_amount", estimated_amount),
+            attr("exit_limit_price", exit_limit_price.to_string()),
+        ],
+    )
+    .add_message(exchange_rewards_msg))
+}
+
+fn get_routes(deps: Deps<NeutronQuery>, config: &Config) -> StdResult<Vec<MultiHopRoute>> {
+    let routes = ROUTES
+        .may_load(deps.storage)?
+        .unwrap_or_else(|| vec![vec![config.from_denom.clone(), config.to_denom.clone()]]);
+    Ok(routes
+        .into_iter()
+        .map(|hops| MultiHopRoute { hops })
+        .collect())
+}
+
+fn estimate_swap(
+    deps: Deps<NeutronQuery>,
+    env: &Env,
+    config: &Config,
+    routes: Vec<MultiHopRoute>,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let estimation: EstimateMultiHopSwapResponse = deps.querier.query(&QueryRequest::Custom(
+        NeutronQuery::Dex(DexQuery::EstimateMultiHopSwap {
+            creator: env.contract.address.to_string(),
+            receiver: config.core_contract.clone(),
+            routes,
+            amount_in: to_int128(amount)?,
+            // the lowest possible limit, so the estimation never fails on price
+            exit_limit_price: PrecDec {
+                i: Decimal::raw(1).to_string(),
+            },
+            pick_best_route: true,
+        }),
+    ))?;
+    Ok(estimation.coin_out.amount)
+}
+
+fn to_int128(amount: Uint128) -> StdResult<Int128> {
+    Int128::try_from(amount).map_err(StdError::from)
+}
+
+fn validate_max_spread(max_spread: Decimal) -> ContractResult<()> {
+    if max_spread >= Decimal::one() {
+        return Err(ContractError::InvalidMaxSpread { max_spread });
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut<NeutronQuery>,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> StdResult<Response<NeutronMsg>> {
+    deps.api.debug("WASMDEBUG: migrate");
+    Ok(Response::default())
+}
@@ -0,0 +1,161 @@
+use std::marker::PhantomData;
+
+use cosmwasm_std::{
+    from_json,
+    testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR},
+    to_json_binary, Coin, ContractResult, CosmosMsg, Decimal, Int128, OwnedDeps, SystemResult,
+    Uint128,
+};
+use drop_staking_base::{
+    error::dex_exchange_handler::ContractError,
+    msg::{
+        dex_exchange_handler::{ExecuteMsg, InstantiateMsg, QueryMsg},
+        reward_handler::SimulateExchangeResponse,
+    },
+};
+use neutron_sdk::bindings::{
+    dex::{
+        msg::DexMsg,
+        query::{DexQuery, EstimateMultiHopSwapResponse},
+        types::{MultiHopRoute, PrecDec},
+    },
+    msg::NeutronMsg,
+    query::NeutronQuery,
+};
+
+use crate::contract::{execute, instantiate, query};
+
+fn mock_dependencies() -> OwnedDeps<MockStorage, MockApi, MockQuerier<NeutronQuery>, NeutronQuery> {
+    let querier =
+        MockQuerier::<NeutronQuery>::new(&[(MOCK_CONTRACT_ADDR, &[Coin::new(100, "ueth")])])
+            .with_custom_handler(|request| match request {
+                NeutronQuery::Dex(DexQuery::EstimateMultiHopSwap {
+                    routes, amount_in, ..
+                }) => {
+                    // every extra hop costs a tenth of the output
+                    let hops = routes.iter().map(|route| route.hops.len()).max().unwrap();
+                    let amount = amount_in.i128() as u128 * 2 * (12 - hops as u128) / 10;
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&EstimateMultiHopSwapResponse {
+                            coin_out: Coin::new(amount, "untrn"),
+                        })
+                        .unwrap(),
+                    ))
+                }
+                _ => unimplemented!(),
+            });
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier,
+        custom_query_type: PhantomData,
+    }
+}
+
+fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier<NeutronQuery>, NeutronQuery> {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        InstantiateMsg {
+            owner: "owner".to_string(),
+            core_contract: "core_contract".to_string(),
+            from_denom: "ueth".to_string(),
+            to_denom: "untrn".to_string(),
+            min_rewards: Uint128::one(),
+            max_spread: Decimal::percent(5),
+        },
+    )
+    .unwrap();
+    deps
+}
+
+#[test]
+fn exchange() {
+    let mut deps = setup();
+
+    let simulation: SimulateExchangeResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateExchange {
+                amount: Coin::new(100, "ueth"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.amount, Uint128::new(200));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("rewards_manager", &[Coin::new(100, "ueth")]),
+        ExecuteMsg::Exchange {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Custom(NeutronMsg::Dex(DexMsg::MultiHopSwap {
+            receiver: "rewards_manager".to_string(),
+            routes: vec![MultiHopRoute {
+                hops: vec!["ueth".to_string(), "untrn".to_string()],
+            }],
+            amount_in: Int128::new(100),
+            exit_limit_price: PrecDec {
+                i: "1.9".to_string(),
+            },
+            pick_best_route: true,
+        }))
+    );
+}
+
+#[test]
+fn update_routes() {
+    let mut deps = setup();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateRoutes {
+            routes: vec![vec!["uatom".to_string(), "untrn".to_string()]],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidRoute {
+            reason: "route must start with ueth and end with untrn".to_string()
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateRoutes {
+            routes: vec![vec![
+                "ueth".to_string(),
+                "uatom".to_string(),
+                "untrn".to_string(),
+            ]],
+        },
+    )
+    .unwrap();
+
+    let simulation: SimulateExchangeResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateExchange {
+                amount: Coin::new(100, "ueth"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.amount, Uint128::new(180));
+}
@@ -273,17 +273,12 @@ fn execute_proxy_msg(
             crate::msg::CoreMsg::UpdateNonNativeRewardsReceivers { items } => {
                 messages.push(get_proxied_message(
                     state.core_contract,
+                    // core updates the puppeteer query when the denoms change
                     drop_staking_base::msg::core::ExecuteMsg::UpdateNonNativeRewardsReceivers {
-                        items: items.clone(),
+                        items,
                     },
-                    vec![],
+                    info.funds,
                 )?);
-                messages.push(
-                    get_proxied_message(
-                        state.puppeteer_contract,
-                        drop_staking_base::msg::puppeteer::ExecuteMsg::RegisterNonNativeRewardsBalancesQuery {
-                            denoms: items.iter().map(|one|{one.denom.to_string()}).collect() }, info.funds)?
-                );
             }
             crate::msg::CoreMsg::Pause {} => {
                 messages.push(get_proxied_message(
@@ -540,6 +535,14 @@ fn execute_init(
                 fee_address: None,
                 emergency_address: None,
                 min_stake_amount: core_params.min_stake_amount,
+                router_contract: None,
+                referral_fee_share: None,
+                insurance_contract: None,
+                exchange_rate_notify_delta: None,
+                pump_contract: None,
+                cw20_wrapper_contract: None,
+                insurance_fee_share: None,
+                insurance_fee_channel: None,
             })?,
             funds: vec![],
             salt: Binary::from(salt),
@@ -575,6 +578,9 @@ fn execute_init(
             label: get_contract_label("rewards manager"),
             msg: to_json_binary(&RewardsMangerInstantiateMsg {
                 owner: env.contract.address.to_string(),
+                core_contract: core_contract.to_string(),
+                base_denom: base_denom.to_string(),
+                epoch_duration: None,
             })?,
             funds: vec![],
             salt: Binary::from(salt),
@@ -228,6 +228,7 @@ fn execute_tokenize_share(
             &drop_staking_base::msg::puppeteer::ExecuteMsg::TokenizeShare {
                 validator,
                 amount,
+                tokenized_share_owner: None,
                 timeout,
                 reply_to: env.contract.address.to_string(),
             },
@@ -0,0 +1,258 @@
+use cosmwasm_std::{
+    attr, ensure, ensure_eq, entry_point, to_json_binary, Attribute, BankMsg, Binary, Coin,
+    Decimal, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Uint128,
+};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use drop_helpers::answer::{attr_coin, response};
+use drop_staking_base::{
+    msg::{
+        core::QueryMsg as CoreQueryMsg,
+        insurance_fund::{CoverageRatioResponse, ExecuteMsg, InstantiateMsg, QueryMsg},
+    },
+    state::insurance_fund::{Config, Payout, CONFIG, PAYOUTS, TOTAL_PAID_OUT},
+};
+
+use crate::error::{ContractError, ContractResult};
+
+const CONTRACT_NAME: &str = concat!("crates.io:drop-staking__", env!("CARGO_PKG_NAME"));
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub const PAGINATION_DEFAULT_LIMIT: u32 = 100;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> ContractResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    cw_ownable::initialize_owner(deps.storage, deps.api, Some(msg.owner.as_ref()))?;
+
+    let attrs: Vec<Attribute> = vec![
+        attr("owner", &msg.owner),
+        attr("core_contract", &msg.core_contract),
+        attr(
+            "withdrawal_manager_contract",
+            &msg.withdrawal_manager_contract,
+        ),
+        attr("base_denom", &msg.base_denom),
+        attr("max_payout_per_batch", msg.max_payout_per_batch),
+    ];
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            core_contract: deps.api.addr_validate(&msg.core_contract)?.to_string(),
+            withdrawal_manager_contract: deps
+                .api
+                .addr_validate(&msg.withdrawal_manager_contract)?
+                .to_string(),
+            base_denom: msg.base_denom,
+            max_payout_per_batch: msg.max_payout_per_batch,
+        },
+    )?;
+    TOTAL_PAID_OUT.save(deps.storage, &Uint128::zero())?;
+    Ok(response("instantiate", CONTRACT_NAME, attrs))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> ContractResult<Binary> {
+    Ok(match msg {
+        QueryMsg::Ownership {} => to_json_binary(&cw_ownable::get_ownership(deps.storage)?)?,
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?)?,
+        QueryMsg::Balance {} => {
+            let config = CONFIG.load(deps.storage)?;
+            to_json_binary(&query_balance(deps, &env, &config)?)?
+        }
+        QueryMsg::AvailableCoverage { shortfall } => {
+            let config = CONFIG.load(deps.storage)?;
+            to_json_binary(&available_coverage(deps, &env, &config, shortfall)?)?
+        }
+        QueryMsg::Payouts { start_after, limit } => query_payouts(deps, start_after, limit)?,
+        QueryMsg::CoverageRatio {} => query_coverage_ratio(deps, env)?,
+    })
+}
+
+fn query_balance(deps: Deps, env: &Env, config: &Config) -> StdResult<Uint128> {
+    Ok(deps
+        .querier
+        .query_balance(&env.contract.address, &config.base_denom)?
+        .amount)
+}
+
+fn available_coverage(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    shortfall: Uint128,
+) -> StdResult<Uint128> {
+    Ok(shortfall
+        .min(config.max_payout_per_batch)
+        .min(query_balance(deps, env, config)?))
+}
+
+fn query_payouts(
+    deps: Deps,
+    start_after: Option<u128>,
+    limit: Option<u32>,
+) -> ContractResult<Binary> {
+    let payouts = PAYOUTS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit.unwrap_or(PAGINATION_DEFAULT_LIMIT) as usize)
+        .map(|item| item.map(|(_, payout)| payout))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&payouts)?)
+}
+
+fn query_coverage_ratio(deps: Deps, env: Env) -> ContractResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let balance = query_balance(deps, &env, &config)?;
+    let total_bonded: Uint128 = deps
+        .querier
+        .query_wasm_smart(&config.core_contract, &CoreQueryMsg::TotalBonded {})?;
+    let ratio = if total_bonded.is_zero() {
+        Decimal::zero()
+    } else {
+        Decimal::from_ratio(balance, total_bonded)
+    };
+    Ok(to_json_binary(&CoverageRatioResponse {
+        balance,
+        total_bonded,
+        total_paid_out: TOTAL_PAID_OUT.load(deps.storage)?,
+        ratio,
+    })?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> ContractResult<Response> {
+    match msg {
+        ExecuteMsg::UpdateOwnership(action) => {
+            cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
+            Ok(response::<(&str, &str), _>(
+                "execute-update-ownership",
+                CONTRACT_NAME,
+                [],
+            ))
+        }
+        ExecuteMsg::UpdateConfig {
+            core_contract,
+            withdrawal_manager_contract,
+            max_payout_per_batch,
+        } => execute_update_config(
+            deps,
+            info,
+            core_contract,
+            withdrawal_manager_contract,
+            max_payout_per_batch,
+        ),
+        ExecuteMsg::Deposit {} => execute_deposit(deps, info),
+        ExecuteMsg::CoverShortfall { batch_id, amount } => {
+            execute_cover_shortfall(deps, env, info, batch_id, amount)
+        }
+    }
+}
+
+fn execute_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    core_contract: Option<String>,
+    withdrawal_manager_contract: Option<String>,
+    max_payout_per_batch: Option<Uint128>,
+) -> ContractResult<Response> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut attrs: Vec<Attribute> = Vec::new();
+    if let Some(core_contract) = core_contract {
+        config.core_contract = deps.api.addr_validate(&core_contract)?.to_string();
+        attrs.push(attr("core_contract", core_contract));
+    }
+    if let Some(withdrawal_manager_contract) = withdrawal_manager_contract {
+        config.withdrawal_manager_contract = deps
+            .api
+            .addr_validate(&withdrawal_manager_contract)?
+            .to_string();
+        attrs.push(attr(
+            "withdrawal_manager_contract",
+            withdrawal_manager_contract,
+        ));
+    }
+    if let Some(max_payout_per_batch) = max_payout_per_batch {
+        config.max_payout_per_batch = max_payout_per_batch;
+        attrs.push(attr("max_payout_per_batch", max_payout_per_batch));
+    }
+    CONFIG.save(deps.storage, &config)?;
+    Ok(response("execute-update_config", CONTRACT_NAME, attrs))
+}
+
+fn execute_deposit(deps: DepsMut, info: MessageInfo) -> ContractResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let amount = cw_utils::must_pay(&info, &config.base_denom)?;
+    Ok(response(
+        "execute-deposit",
+        CONTRACT_NAME,
+        [
+            attr("sender", info.sender),
+            attr_coin("amount", amount, config.base_denom),
+        ],
+    ))
+}
+
+fn execute_cover_shortfall(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    batch_id: u128,
+    amount: Uint128,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_eq!(
+        info.sender,
+        config.core_contract,
+        ContractError::Unauthorized {}
+    );
+    ensure!(
+        !PAYOUTS.has(deps.storage, batch_id),
+        ContractError::BatchIsAlreadyCovered { batch_id }
+    );
+    let available = available_coverage(deps.as_ref(), &env, &config, amount)?;
+    ensure!(
+        amount <= available,
+        ContractError::PayoutExceedsCoverage { amount, available }
+    );
+    PAYOUTS.save(
+        deps.storage,
+        batch_id,
+        &Payout {
+            batch_id,
+            amount,
+            timestamp: env.block.time.seconds(),
+        },
+    )?;
+    TOTAL_PAID_OUT.update(deps.storage, |total| StdResult::Ok(total + amount))?;
+    Ok(response(
+        "execute-cover_shortfall",
+        CONTRACT_NAME,
+        [
+            attr("batch_id", batch_id.to_string()),
+            attr_coin("amount", amount, &config.base_denom),
+        ],
+    )
+    .add_message(BankMsg::Send {
+        to_address: config.withdrawal_manager_contract,
+        amount: vec![Coin {
+            denom: config.base_denom,
+            amount,
+        }],
+    }))
+}
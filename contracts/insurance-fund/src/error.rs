@@ -0,0 +1,29 @@
+use cosmwasm_std::{OverflowError, StdError, Uint128};
+use cw_ownable::OwnershipError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    OverflowError(#[from] OverflowError),
+
+    #[error("{0}")]
+    OwnershipError(#[from] OwnershipError),
+
+    #[error("{0}")]
+    PaymentError(#[from] cw_utils::PaymentError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Payout {amount} exceeds available coverage {available}")]
+    PayoutExceedsCoverage { amount: Uint128, available: Uint128 },
+
+    #[error("Batch {batch_id} is already covered")]
+    BatchIsAlreadyCovered { batch_id: u128 },
+}
+
+pub type ContractResult<T> = Result<T, ContractError>;
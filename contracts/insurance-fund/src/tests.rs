@@ -0,0 +1,155 @@
+use cosmwasm_std::{
+    from_json,
+    testing::{mock_dependencies_with_balance, mock_env, mock_info, MockApi, MockQuerier},
+    BankMsg, Coin, CosmosMsg, MemoryStorage, OwnedDeps, Uint128,
+};
+use drop_staking_base::{
+    msg::insurance_fund::{ExecuteMsg, InstantiateMsg, QueryMsg},
+    state::insurance_fund::Payout,
+};
+
+use crate::{
+    contract::{execute, instantiate, query},
+    error::ContractError,
+};
+
+fn setup(balance: u128) -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies_with_balance(&[Coin::new(balance, "base_denom")]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        InstantiateMsg {
+            owner: "owner".to_string(),
+            core_contract: "core_contract".to_string(),
+            withdrawal_manager_contract: "withdrawal_manager_contract".to_string(),
+            base_denom: "base_denom".to_string(),
+            max_payout_per_batch: Uint128::new(500),
+        },
+    )
+    .unwrap();
+    deps
+}
+
+#[test]
+fn available_coverage_is_capped() {
+    let deps = setup(1000);
+    let available = |shortfall: u128| -> Uint128 {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::AvailableCoverage {
+                    shortfall: Uint128::new(shortfall),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(available(100), Uint128::new(100));
+    assert_eq!(available(700), Uint128::new(500));
+
+    let deps = setup(300);
+    let available: Uint128 = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AvailableCoverage {
+                shortfall: Uint128::new(400),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(available, Uint128::new(300));
+}
+
+#[test]
+fn cover_shortfall_unauthorized() {
+    let mut deps = setup(1000);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::CoverShortfall {
+            batch_id: 0,
+            amount: Uint128::new(100),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn cover_shortfall() {
+    let mut deps = setup(1000);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("core_contract", &[]),
+        ExecuteMsg::CoverShortfall {
+            batch_id: 1,
+            amount: Uint128::new(100),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "withdrawal_manager_contract".to_string(),
+            amount: vec![Coin::new(100, "base_denom")],
+        })
+    );
+
+    let payouts: Vec<Payout> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Payouts {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        payouts,
+        vec![Payout {
+            batch_id: 1,
+            amount: Uint128::new(100),
+            timestamp: mock_env().block.time.seconds(),
+        }]
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("core_contract", &[]),
+        ExecuteMsg::CoverShortfall {
+            batch_id: 1,
+            amount: Uint128::new(100),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BatchIsAlreadyCovered { batch_id: 1 });
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("core_contract", &[]),
+        ExecuteMsg::CoverShortfall {
+            batch_id: 2,
+            amount: Uint128::new(600),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PayoutExceedsCoverage {
+            amount: Uint128::new(600),
+            available: Uint128::new(500),
+        }
+    );
+}
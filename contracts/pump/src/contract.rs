@@ -1,30 +1,46 @@
 use cosmos_sdk_proto::cosmos::base::abci::v1beta1::TxMsgData;
 use cosmos_sdk_proto::cosmos::base::v1beta1::Coin as ProtoCoin;
 use cosmos_sdk_proto::ibc::applications::transfer::v1::{MsgTransfer, MsgTransferResponse};
+use cosmos_sdk_proto::ibc::core::client::v1::QueryClientStateRequest;
+use cosmos_sdk_proto::ibc::core::connection::v1::QueryConnectionRequest;
 use cosmwasm_std::{
-    attr, coin, ensure, ensure_eq, entry_point, to_json_binary, Addr, Coin, CosmosMsg, Deps,
-    StdError, Uint128,
+    attr, coin, ensure, ensure_eq, entry_point, to_json_binary, Addr, Coin, CosmosMsg, Deps, Order,
+    QueryRequest, Reply, StdError, SubMsg, Uint128, Uint64, WasmMsg,
 };
-use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{Attribute, Binary, DepsMut, Env, MessageInfo, Response, StdResult};
 use cw_utils::must_pay;
 use drop_helpers::answer::response;
+use drop_helpers::icq::{new_multiple_balances_query_msg, update_multiple_balances_query_msg};
+use drop_helpers::query_id::get_query_id;
+use drop_puppeteer_base::r#trait::PuppeteerReconstruct;
 use drop_staking_base::msg::pump::{
-    ExecuteMsg, InstantiateMsg, MigrateMsg, OpenAckVersion, QueryMsg, UpdateConfigMsg,
+    ExecuteMsg, InstantiateMsg, MigrateMsg, OpenAckVersion, PumpTransferHookMsg, QueryMsg,
+    ReceiverExecuteMsg, UpdateConfigMsg,
 };
-use drop_staking_base::state::pump::{Config, CONFIG, ICA, ICA_ID};
-use neutron_sdk::bindings::msg::{IbcFee, NeutronMsg};
+use drop_staking_base::msg::puppeteer::MultiBalances;
+use drop_staking_base::state::pump::{
+    Config, PumpRoute, PumpTransfer, PumpTransferStatus, BALANCES_QUERY_ID, CONFIG, ICA,
+    ICA_BALANCES, ICA_ID, LAST_SETTLED_TRANSFER_HEIGHT, LAST_SWEEP_HEIGHT, ROUTES,
+    SUBMITTED_TRANSFERS, TRANSFERS,
+};
+use neutron_sdk::bindings::msg::{IbcFee, MsgSubmitTxResponse, NeutronMsg};
 use neutron_sdk::bindings::query::NeutronQuery;
 use neutron_sdk::bindings::types::ProtobufAny;
+use neutron_sdk::interchain_queries::queries::get_raw_interchain_query_result;
 use neutron_sdk::interchain_txs::helpers::decode_message_response;
 use neutron_sdk::sudo::msg::{RequestPacket, SudoMsg};
 use neutron_sdk::{NeutronError, NeutronResult};
 use prost::Message;
+use serde::Deserialize;
 
 use crate::error::{ContractError, ContractResult};
 
 const CONTRACT_NAME: &str = concat!("crates.io:drop-neutron-contracts__", env!("CARGO_PKG_NAME"));
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const DEFAULT_TIMEOUT_SECONDS: u64 = 60;
+pub const SUBMIT_TX_REPLY_ID: u64 = 1;
+pub const HOOK_REPLY_ID: u64 = 2;
+pub const BALANCES_QUERY_REPLY_ID: u64 = 3;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -59,6 +75,12 @@ pub fn instantiate(
             ibc_fees: msg.ibc_fees,
             timeout: msg.timeout,
             local_denom: msg.local_denom,
+            max_retries: msg.max_retries,
+            core_contract: msg
+                .core_contract
+                .map(|a| deps.api.addr_validate(&a))
+                .transpose()?,
+            sweep: msg.sweep,
         },
     )?;
     Ok(response("instantiate", CONTRACT_NAME, attrs))
@@ -69,9 +91,28 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> NeutronResult<Binary> {
     match msg {
         QueryMsg::Config {} => query_config(deps),
         QueryMsg::Ica {} => query_ica(deps),
+        QueryMsg::Routes {} => query_routes(deps),
+        QueryMsg::PendingTransfers {} => query_pending_transfers(deps),
+        QueryMsg::IcaBalances {} => {
+            to_json_binary(&ICA_BALANCES.may_load(deps.storage)?).map_err(NeutronError::Std)
+        }
     }
 }
 
+fn query_pending_transfers(deps: Deps) -> NeutronResult<Binary> {
+    let transfers = TRANSFERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&transfers).map_err(NeutronError::Std)
+}
+
+fn query_routes(deps: Deps) -> NeutronResult<Binary> {
+    let routes = ROUTES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&routes).map_err(NeutronError::Std)
+}
+
 fn query_config(deps: Deps) -> NeutronResult<Binary> {
     let config = CONFIG.load(deps.storage)?;
     to_json_binary(&config).map_err(NeutronError::Std)
@@ -92,15 +133,171 @@ pub fn execute(
     match msg {
         ExecuteMsg::RegisterICA {} => execute_register_ica(deps, info),
         ExecuteMsg::Push { coins } => execute_push(deps, env, info, coins),
-        ExecuteMsg::Refund {} => execute_refund(deps, env),
+        ExecuteMsg::Refund {} => execute_refund(deps, env, info),
         ExecuteMsg::UpdateConfig { new_config } => {
             execute_update_config(deps, env, info, *new_config)
         }
+        ExecuteMsg::SetRoute { denom, route } => execute_set_route(deps, info, denom, route),
+        ExecuteMsg::RemoveRoute { denom } => execute_remove_route(deps, info, denom),
+        ExecuteMsg::RegisterBalancesQuery {} => execute_register_balances_query(deps, info),
+        ExecuteMsg::Sweep {} => execute_sweep(deps, env),
+    }
+}
+
+fn execute_register_balances_query(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_eq!(info.sender, config.owner, ContractError::Unauthorized {});
+    let sweep = config.sweep.ok_or(ContractError::SweepIsNotConfigured {})?;
+    let ica = ICA.get_address(deps.storage)?;
+    let attrs = vec![
+        attr("action", "register_balances_query"),
+        attr("denoms", sweep.denoms.join(",")),
+    ];
+    let res = response("register_balances_query", CONTRACT_NAME, attrs);
+    Ok(match BALANCES_QUERY_ID.may_load(deps.storage)? {
+        // nothing to watch anymore
+        Some(query_id) if sweep.denoms.is_empty() => {
+            BALANCES_QUERY_ID.remove(deps.storage);
+            ICA_BALANCES.remove(deps.storage);
+            res.add_message(NeutronMsg::remove_interchain_query(query_id))
+        }
+        None if sweep.denoms.is_empty() => res,
+        Some(query_id) => res.add_message(update_multiple_balances_query_msg(
+            query_id,
+            ica,
+            sweep.denoms,
+        )?),
+        None => res.add_submessage(SubMsg::reply_on_success(
+            new_multiple_balances_query_msg(
+                config.connection_id,
+                ica,
+                sweep.denoms,
+                sweep.update_period,
+            )?,
+            BALANCES_QUERY_REPLY_ID,
+        )),
+    })
+}
+
+fn execute_sweep(deps: DepsMut<NeutronQuery>, env: Env) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    let sweep = config
+        .sweep
+        .as_ref()
+        .ok_or(ContractError::SweepIsNotConfigured {})?;
+    let (balances, height, _) = ICA_BALANCES
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NothingToSweep {})?;
+    // both heights are remote, the result must be taken after the last swept one and
+    // after the last transfer could have moved the funds
+    let min_height = LAST_SWEEP_HEIGHT
+        .may_load(deps.storage)?
+        .max(LAST_SETTLED_TRANSFER_HEIGHT.may_load(deps.storage)?);
+    ensure!(
+        min_height.map_or(true, |min_height| height > min_height),
+        ContractError::BalancesNotUpdated {}
+    );
+    // funds of unsettled transfers can still be part of the observed balances
+    let unsettled = TRANSFERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .any(|(_, transfer)| transfer.status != PumpTransferStatus::Failed);
+    ensure!(
+        !unsettled
+            && SUBMITTED_TRANSFERS
+                .may_load(deps.storage)?
+                .unwrap_or_default()
+                .is_empty(),
+        ContractError::TransfersNotSettled {}
+    );
+    let coins: Vec<Coin> = balances
+        .coins
+        .into_iter()
+        .filter(|coin| sweep.denoms.contains(&coin.denom) && !coin.amount.is_zero())
+        .collect();
+    ensure!(!coins.is_empty(), ContractError::NothingToSweep {});
+    let attrs = vec![
+        attr("action", "sweep"),
+        attr("height", height.to_string()),
+        attr("coins", format!("{:?}", coins)),
+    ];
+    let ica = ICA.get_address(deps.storage)?;
+    let mut submessages = vec![];
+    let mut required_fees = Uint128::zero();
+    for coin in coins {
+        let route = get_route(deps.as_ref(), &config, &coin.denom)?;
+        required_fees += get_route_fees(&route);
+        submessages.push(submit_transfer(
+            deps.storage,
+            &env,
+            &config,
+            &ica,
+            PumpTransfer {
+                amount: coin,
+                route,
+                status: PumpTransferStatus::Pending,
+                retries: 0,
+            },
+        )?);
     }
+    // the fees are paid from the contract balance, anyone can top it up
+    let available = deps
+        .querier
+        .query_balance(&env.contract.address, &config.local_denom)?
+        .amount;
+    ensure!(
+        available >= required_fees,
+        ContractError::InsufficientFeeReserve {
+            required: required_fees,
+            available,
+        }
+    );
+    LAST_SWEEP_HEIGHT.save(deps.storage, &height)?;
+    Ok(response("sweep", CONTRACT_NAME, attrs).add_submessages(submessages))
 }
 
-fn execute_refund(deps: DepsMut<NeutronQuery>, env: Env) -> ContractResult<Response<NeutronMsg>> {
+fn execute_set_route(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    denom: String,
+    route: PumpRoute,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_eq!(info.sender, config.owner, ContractError::Unauthorized {});
+    let attrs = vec![
+        attr("action", "set_route"),
+        attr("denom", &denom),
+        attr("dest_address", &route.dest_address),
+        attr("dest_channel", &route.dest_channel),
+    ];
+    ROUTES.save(deps.storage, &denom, &route)?;
+    Ok(response("set_route", CONTRACT_NAME, attrs))
+}
+
+fn execute_remove_route(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    denom: String,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_eq!(info.sender, config.owner, ContractError::Unauthorized {});
+    let attrs = vec![attr("action", "remove_route"), attr("denom", &denom)];
+    ROUTES.remove(deps.storage, &denom);
+    Ok(response("remove_route", CONTRACT_NAME, attrs))
+}
+
+// the balance also pays the fees of retried transfers, so only the owner may drain it
+fn execute_refund(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> ContractResult<Response<NeutronMsg>> {
     let config = CONFIG.load(deps.storage)?;
+    ensure_eq!(info.sender, config.owner, ContractError::Unauthorized {});
     let refundee = config.refundee.ok_or(ContractError::RefundeeIsNotSet {})?;
     let balances = deps.querier.query_all_balances(env.contract.address)?;
     let attrs = vec![attr("action", "refund"), attr("refundee", &refundee)];
@@ -150,6 +347,15 @@ fn execute_update_config(
     if let Some(local_denom) = new_config.local_denom {
         config.local_denom = local_denom;
     }
+    if let Some(max_retries) = new_config.max_retries {
+        config.max_retries = max_retries;
+    }
+    if let Some(core_contract) = new_config.core_contract {
+        config.core_contract = Some(deps.api.addr_validate(&core_contract)?);
+    }
+    if let Some(sweep) = new_config.sweep {
+        config.sweep = Some(sweep);
+    }
     CONFIG.save(deps.storage, &config)?;
     Ok(response("update_config", CONTRACT_NAME, attrs))
 }
@@ -181,60 +387,152 @@ fn execute_push(
     coins: Vec<Coin>,
 ) -> ContractResult<Response<NeutronMsg>> {
     let config = CONFIG.load(deps.storage)?;
-    let mut messages = vec![];
-    check_funds(
-        &info,
-        &config,
-        config.ibc_fees.ack_fee + config.ibc_fees.recv_fee + config.ibc_fees.timeout_fee,
-    )?;
+    let mut submessages = vec![];
     let attrs = vec![
         attr("action", "push"),
         attr("connection_id", &config.connection_id),
         attr("ica_id", ICA_ID),
         attr("coins", format!("{:?}", coins)),
     ];
-    let fee = IbcFee {
-        recv_fee: uint_into_vec_coin(config.ibc_fees.recv_fee, &config.local_denom),
-        ack_fee: uint_into_vec_coin(config.ibc_fees.ack_fee, &config.local_denom),
-        timeout_fee: uint_into_vec_coin(config.ibc_fees.timeout_fee, &config.local_denom),
-    };
     let ica = ICA.get_address(deps.storage)?;
-    let timeout_timestamp = env.block.time.plus_seconds(config.timeout.remote).nanos();
-    let dst_port = &config
-        .dest_port
-        .as_ref()
-        .ok_or(ContractError::NoDestinationPort {})?;
-    let dst_channel = &config
-        .dest_channel
-        .as_ref()
-        .ok_or(ContractError::NoDestinationChannel {})?;
-    let dst_address = config
-        .dest_address
-        .as_ref()
-        .ok_or(ContractError::NoDestinationAddress {})?
-        .to_string();
+    // every transfer is a separate ICA tx paying its own fees
+    let mut required_fees = Uint128::zero();
     for coin in coins {
-        let msg = MsgTransfer {
-            source_port: dst_port.to_string(),
-            source_channel: dst_channel.to_string(),
-            token: Some(ProtoCoin {
-                denom: coin.denom,
-                amount: coin.amount.to_string(),
-            }),
-            sender: ica.to_string(),
-            receiver: dst_address.to_string(),
-            timeout_height: None,
-            timeout_timestamp,
-        };
-        messages.push(compose_msg(
+        let route = get_route(deps.as_ref(), &config, &coin.denom)?;
+        required_fees += get_route_fees(&route);
+        submessages.push(submit_transfer(
+            deps.storage,
+            &env,
             &config,
-            msg,
-            &fee,
-            "/ibc.applications.transfer.v1.MsgTransfer".to_string(),
-            config.timeout.local,
+            &ica,
+            PumpTransfer {
+                amount: coin,
+                route,
+                status: PumpTransferStatus::Pending,
+                retries: 0,
+            },
         )?);
     }
-    Ok(response("push", CONTRACT_NAME, attrs).add_messages(messages))
+    check_funds(&info, &config, required_fees)?;
+    Ok(response("push", CONTRACT_NAME, attrs).add_submessages(submessages))
+}
+
+fn get_route_fees(route: &PumpRoute) -> Uint128 {
+    route.ibc_fees.ack_fee + route.ibc_fees.recv_fee + route.ibc_fees.timeout_fee
+}
+
+// the sequence of the transfer is known only in the submit tx reply
+fn submit_transfer(
+    storage: &mut dyn cosmwasm_std::Storage,
+    env: &Env,
+    config: &Config,
+    ica: &str,
+    transfer: PumpTransfer,
+) -> NeutronResult<SubMsg<NeutronMsg>> {
+    let route = &transfer.route;
+    let fee = IbcFee {
+        recv_fee: uint_into_vec_coin(route.ibc_fees.recv_fee, &config.local_denom),
+        ack_fee: uint_into_vec_coin(route.ibc_fees.ack_fee, &config.local_denom),
+        timeout_fee: uint_into_vec_coin(route.ibc_fees.timeout_fee, &config.local_denom),
+    };
+    let msg = MsgTransfer {
+        source_port: route.dest_port.to_string(),
+        source_channel: route.dest_channel.to_string(),
+        token: Some(ProtoCoin {
+            denom: transfer.amount.denom.to_string(),
+            amount: transfer.amount.amount.to_string(),
+        }),
+        sender: ica.to_string(),
+        receiver: route.dest_address.to_string(),
+        timeout_height: None,
+        timeout_timestamp: env.block.time.plus_seconds(route.timeout.remote).nanos(),
+    };
+    let msg = compose_msg(
+        config,
+        msg,
+        &fee,
+        "/ibc.applications.transfer.v1.MsgTransfer".to_string(),
+        route.timeout.local,
+    )?;
+    let mut submitted = SUBMITTED_TRANSFERS.may_load(storage)?.unwrap_or_default();
+    submitted.push(transfer);
+    SUBMITTED_TRANSFERS.save(storage, &submitted)?;
+    Ok(SubMsg::reply_on_success(msg, SUBMIT_TX_REPLY_ID))
+}
+
+// resubmits the transfer while retries are left and the contract can pay the fees,
+// otherwise marks it as failed and notifies the core contract
+fn retry_transfer(
+    deps: DepsMut<NeutronQuery>,
+    env: &Env,
+    config: &Config,
+    key: (String, u64),
+    mut transfer: PumpTransfer,
+    reserved_fees: &mut Uint128,
+) -> NeutronResult<(Vec<SubMsg<NeutronMsg>>, Vec<Attribute>)> {
+    let fees = get_route_fees(&transfer.route);
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &config.local_denom)?
+        .amount;
+    let can_retry = transfer.retries < config.max_retries && balance >= *reserved_fees + fees;
+    if let (true, Ok(ica)) = (can_retry, ICA.get_address(deps.storage)) {
+        *reserved_fees += fees;
+        TRANSFERS.remove(deps.storage, key);
+        transfer.retries += 1;
+        transfer.status = PumpTransferStatus::Pending;
+        let attrs = vec![attr("retry", transfer.retries.to_string())];
+        let submsg = submit_transfer(deps.storage, env, config, &ica, transfer)?;
+        return Ok((vec![submsg], attrs));
+    }
+    transfer.status = PumpTransferStatus::Failed;
+    TRANSFERS.save(deps.storage, key, &transfer)?;
+    let attrs = vec![attr("failed", transfer.amount.to_string())];
+    Ok((get_hook_msg(config, &transfer, false)?, attrs))
+}
+
+fn get_hook_msg(
+    config: &Config,
+    transfer: &PumpTransfer,
+    succeeded: bool,
+) -> StdResult<Vec<SubMsg<NeutronMsg>>> {
+    let Some(core_contract) = &config.core_contract else {
+        return Ok(vec![]);
+    };
+    let msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: core_contract.to_string(),
+        msg: to_json_binary(&ReceiverExecuteMsg::PumpHook(PumpTransferHookMsg {
+            amount: transfer.amount.clone(),
+            receiver: transfer.route.dest_address.to_string(),
+            succeeded,
+        }))?,
+        funds: vec![],
+    });
+    // a failing hook must not revert the transfer bookkeeping
+    Ok(vec![SubMsg::reply_on_error(msg, HOOK_REPLY_ID)])
+}
+
+fn get_route(deps: Deps<NeutronQuery>, config: &Config, denom: &str) -> ContractResult<PumpRoute> {
+    if let Some(route) = ROUTES.may_load(deps.storage, denom)? {
+        return Ok(route);
+    }
+    Ok(PumpRoute {
+        dest_address: config
+            .dest_address
+            .as_ref()
+            .ok_or(ContractError::NoDestinationAddress {})?
+            .to_string(),
+        dest_channel: config
+            .dest_channel
+            .clone()
+            .ok_or(ContractError::NoDestinationChannel {})?,
+        dest_port: config
+            .dest_port
+            .clone()
+            .ok_or(ContractError::NoDestinationPort {})?,
+        ibc_fees: config.ibc_fees.clone(),
+        timeout: config.timeout.clone(),
+    })
 }
 
 fn compose_msg<T: prost::Message>(
@@ -267,7 +565,11 @@ fn compose_msg<T: prost::Message>(
 }
 
 #[entry_point]
-pub fn sudo(deps: DepsMut<NeutronQuery>, env: Env, msg: SudoMsg) -> NeutronResult<Response> {
+pub fn sudo(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> NeutronResult<Response<NeutronMsg>> {
     deps.api.debug(&format!(
         "WASMDEBUG: sudo call: {:?} block: {:?}",
         msg, env.block
@@ -276,11 +578,10 @@ pub fn sudo(deps: DepsMut<NeutronQuery>, env: Env, msg: SudoMsg) -> NeutronResul
         SudoMsg::Response { request, data } => sudo_response(deps, env, request, data),
         SudoMsg::Error { request, details } => sudo_error(deps, env, request, details),
         SudoMsg::Timeout { request } => sudo_timeout(deps, env, request),
-        SudoMsg::KVQueryResult { .. } | SudoMsg::TxQueryResult { .. } => {
-            Err(NeutronError::Std(StdError::GenericErr {
-                msg: "KVQueryResult is not supported".to_string(),
-            }))
-        }
+        SudoMsg::KVQueryResult { query_id } => sudo_kv_query_result(deps, env, query_id),
+        SudoMsg::TxQueryResult { .. } => Err(NeutronError::Std(StdError::GenericErr {
+            msg: "TxQueryResult is not supported".to_string(),
+        })),
         SudoMsg::OpenAck {
             port_id,
             channel_id,
@@ -297,19 +598,76 @@ pub fn sudo(deps: DepsMut<NeutronQuery>, env: Env, msg: SudoMsg) -> NeutronResul
     }
 }
 
-pub fn sudo_open_ack(
+fn sudo_kv_query_result(
     deps: DepsMut<NeutronQuery>,
-    _env: Env,
+    env: Env,
+    query_id: u64,
+) -> NeutronResult<Response<NeutronMsg>> {
+    ensure_eq!(
+        BALANCES_QUERY_ID.may_load(deps.storage)?,
+        Some(query_id),
+        NeutronError::Std(StdError::generic_err(format!(
+            "unknown query id: {query_id}"
+        )))
+    );
+    let config = CONFIG.load(deps.storage)?;
+    let sdk_version = config
+        .sweep
+        .map(|sweep| sweep.sdk_version)
+        .ok_or_else(|| StdError::generic_err("sweep is not configured"))?;
+    let registered_query_result = get_raw_interchain_query_result(deps.as_ref(), query_id)?;
+    let balances =
+        MultiBalances::reconstruct(&registered_query_result.result.kv_results, &sdk_version)?;
+    let height = registered_query_result.result.height;
+    ICA_BALANCES.save(deps.storage, &(balances, height, env.block.time))?;
+    Ok(response(
+        "sudo-kv-query-result",
+        CONTRACT_NAME,
+        [
+            attr("query_id", query_id.to_string()),
+            attr("height", height.to_string()),
+        ],
+    ))
+}
+
+pub fn sudo_open_ack(
+    mut deps: DepsMut<NeutronQuery>,
+    env: Env,
     _port_id: String,
     _channel_id: String,
     _counterparty_channel_id: String,
     counterparty_version: String,
-) -> NeutronResult<Response> {
+) -> NeutronResult<Response<NeutronMsg>> {
     let parsed_version: Result<OpenAckVersion, _> =
         serde_json_wasm::from_str(counterparty_version.as_str());
     if let Ok(parsed_version) = parsed_version {
         ICA.set_address(deps.storage, parsed_version.address)?;
-        Ok(Response::default())
+        // transfers timed out on the closed channel are resubmitted on the new one
+        let config = CONFIG.load(deps.storage)?;
+        let timed_out = TRANSFERS
+            .range(deps.storage, None, None, Order::Ascending)
+            .filter(|item| {
+                item.as_ref()
+                    .map(|(_, transfer)| transfer.status == PumpTransferStatus::TimedOut)
+                    .unwrap_or(true)
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut reserved_fees = Uint128::zero();
+        let mut submessages = vec![];
+        let mut attrs = vec![attr("action", "sudo_open_ack")];
+        for (key, transfer) in timed_out {
+            let (msgs, retry_attrs) = retry_transfer(
+                deps.branch(),
+                &env,
+                &config,
+                key,
+                transfer,
+                &mut reserved_fees,
+            )?;
+            submessages.extend(msgs);
+            attrs.extend(retry_attrs);
+        }
+        Ok(response("sudo-open-ack", CONTRACT_NAME, attrs).add_submessages(submessages))
     } else {
         Err(NeutronError::Std(StdError::generic_err(
             "can't parse version",
@@ -322,14 +680,12 @@ fn sudo_response(
     _env: Env,
     request: RequestPacket,
     data: Binary,
-) -> NeutronResult<Response> {
+) -> NeutronResult<Response<NeutronMsg>> {
     let attrs = vec![
         attr("action", "sudo_response"),
         attr("request_id", request.sequence.unwrap_or(0).to_string()),
     ];
-    let _seq_id = request
-        .sequence
-        .ok_or_else(|| StdError::generic_err("sequence not found"))?;
+    let key = get_transfer_key(&request)?;
 
     let msg_data: TxMsgData = TxMsgData::decode(data.as_slice())?;
     deps.api
@@ -351,19 +707,97 @@ fn sudo_response(
             }
         };
     }
-    Ok(response("sudo-response", CONTRACT_NAME, attrs))
+    let mut submessages = vec![];
+    if let Some(transfer) = TRANSFERS.may_load(deps.storage, key.clone())? {
+        let config = CONFIG.load(deps.storage)?;
+        TRANSFERS.remove(deps.storage, key);
+        // only the sweep relies on the ICA balances
+        if config.sweep.is_some() {
+            let height = query_remote_height(deps.as_ref(), &config.connection_id)?;
+            LAST_SETTLED_TRANSFER_HEIGHT.save(deps.storage, &height)?;
+        }
+        submessages = get_hook_msg(&config, &transfer, true)?;
+    }
+    Ok(response("sudo-response", CONTRACT_NAME, attrs).add_submessages(submessages))
+}
+
+#[derive(Deserialize)]
+struct QueryConnectionResponse {
+    connection: ConnectionEnd,
+}
+
+#[derive(Deserialize)]
+struct ConnectionEnd {
+    client_id: String,
+}
+
+#[derive(Deserialize)]
+struct QueryClientStateResponse {
+    client_state: ClientState,
+}
+
+#[derive(Deserialize)]
+struct ClientState {
+    latest_height: Height,
+}
+
+#[derive(Deserialize)]
+struct Height {
+    revision_height: Uint64,
+}
+
+// latest remote height known to the light client of the connection, an acknowledged
+// transfer is executed on the remote chain before it
+fn query_remote_height(deps: Deps<NeutronQuery>, connection_id: &str) -> StdResult<u64> {
+    let connection: QueryConnectionResponse = deps.querier.query(&QueryRequest::Stargate {
+        path: "/ibc.core.connection.v1.Query/Connection".to_string(),
+        data: QueryConnectionRequest {
+            connection_id: connection_id.to_string(),
+        }
+        .encode_to_vec()
+        .into(),
+    })?;
+    let client_state: QueryClientStateResponse = deps.querier.query(&QueryRequest::Stargate {
+        path: "/ibc.core.client.v1.Query/ClientState".to_string(),
+        data: QueryClientStateRequest {
+            client_id: connection.connection.client_id,
+        }
+        .encode_to_vec()
+        .into(),
+    })?;
+    Ok(client_state
+        .client_state
+        .latest_height
+        .revision_height
+        .u64())
+}
+
+fn get_transfer_key(request: &RequestPacket) -> StdResult<(String, u64)> {
+    let seq_id = request
+        .sequence
+        .ok_or_else(|| StdError::generic_err("sequence not found"))?;
+    let channel_id = request
+        .source_channel
+        .clone()
+        .ok_or_else(|| StdError::generic_err("source channel not found"))?;
+    Ok((channel_id, seq_id))
 }
 
 fn sudo_timeout(
     deps: DepsMut<NeutronQuery>,
     _env: Env,
     request: RequestPacket,
-) -> NeutronResult<Response> {
+) -> NeutronResult<Response<NeutronMsg>> {
     let attrs = vec![
         attr("action", "sudo_timeout"),
         attr("request_id", request.sequence.unwrap_or(0).to_string()),
     ];
     ICA.set_timeout(deps.storage)?;
+    let key = get_transfer_key(&request)?;
+    if let Some(mut transfer) = TRANSFERS.may_load(deps.storage, key.clone())? {
+        transfer.status = PumpTransferStatus::TimedOut;
+        TRANSFERS.save(deps.storage, key, &transfer)?;
+    }
     deps.api.debug(&format!(
         "WASMDEBUG: sudo_timeout: request: {request:?}",
         request = request
@@ -373,11 +807,11 @@ fn sudo_timeout(
 
 fn sudo_error(
     deps: DepsMut<NeutronQuery>,
-    _env: Env,
+    env: Env,
     request: RequestPacket,
     details: String,
-) -> NeutronResult<Response> {
-    let attrs = vec![
+) -> NeutronResult<Response<NeutronMsg>> {
+    let mut attrs = vec![
         attr("action", "sudo_error"),
         attr("request_id", request.sequence.unwrap_or(0).to_string()),
         attr("details", details.clone()),
@@ -387,10 +821,75 @@ fn sudo_error(
         request = request,
         details = details
     ));
-    let _seq_id = request
-        .sequence
-        .ok_or_else(|| StdError::generic_err("sequence not found"))?;
-    Ok(response("sudo-error", CONTRACT_NAME, attrs))
+    let key = get_transfer_key(&request)?;
+    let mut submessages = vec![];
+    if let Some(transfer) = TRANSFERS.may_load(deps.storage, key.clone())? {
+        let config = CONFIG.load(deps.storage)?;
+        let (msgs, retry_attrs) =
+            retry_transfer(deps, &env, &config, key, transfer, &mut Uint128::zero())?;
+        submessages = msgs;
+        attrs.extend(retry_attrs);
+    }
+    Ok(response("sudo-error", CONTRACT_NAME, attrs).add_submessages(submessages))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    deps: DepsMut<NeutronQuery>,
+    _env: Env,
+    msg: Reply,
+) -> NeutronResult<Response<NeutronMsg>> {
+    match msg.id {
+        SUBMIT_TX_REPLY_ID => submit_tx_reply(deps, msg),
+        BALANCES_QUERY_REPLY_ID => {
+            let query_id = get_query_id(msg.result)?;
+            BALANCES_QUERY_ID.save(deps.storage, &query_id)?;
+            Ok(response(
+                "balances-query-reply",
+                CONTRACT_NAME,
+                [attr("query_id", query_id.to_string())],
+            ))
+        }
+        HOOK_REPLY_ID => Ok(response(
+            "hook-failed",
+            CONTRACT_NAME,
+            [attr("error", msg.result.unwrap_err())],
+        )),
+        id => Err(NeutronError::Std(StdError::generic_err(format!(
+            "unknown reply id: {id}"
+        )))),
+    }
+}
+
+fn submit_tx_reply(deps: DepsMut<NeutronQuery>, msg: Reply) -> NeutronResult<Response<NeutronMsg>> {
+    let resp: MsgSubmitTxResponse = serde_json_wasm::from_slice(
+        msg.result
+            .into_result()
+            .map_err(StdError::generic_err)?
+            .data
+            .ok_or_else(|| StdError::generic_err("no result"))?
+            .as_slice(),
+    )
+    .map_err(|e| StdError::generic_err(format!("failed to parse response: {e:?}")))?;
+    let mut submitted = SUBMITTED_TRANSFERS.load(deps.storage)?;
+    if submitted.is_empty() {
+        return Err(NeutronError::Std(StdError::generic_err(
+            "no submitted transfer",
+        )));
+    }
+    // submessages are replied in the order they were submitted
+    let transfer = submitted.remove(0);
+    SUBMITTED_TRANSFERS.save(deps.storage, &submitted)?;
+    TRANSFERS.save(
+        deps.storage,
+        (resp.channel.to_string(), resp.sequence_id),
+        &transfer,
+    )?;
+    let attrs = vec![
+        attr("channel_id", resp.channel),
+        attr("seq_id", resp.sequence_id.to_string()),
+    ];
+    Ok(response("submit-tx-reply", CONTRACT_NAME, attrs))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -413,7 +912,7 @@ fn check_funds(info: &MessageInfo, config: &Config, amount: Uint128) -> Contract
         ContractError::InvalidFunds {
             reason: format!(
                 "invalid amount: expected at least {}, got {}",
-                config.ibc_fees.register_fee, info_amount
+                amount, info_amount
             )
         }
     );
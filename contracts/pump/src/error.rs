@@ -1,4 +1,4 @@
-use cosmwasm_std::{OverflowError, StdError};
+use cosmwasm_std::{OverflowError, StdError, Uint128};
 use cw_utils::PaymentError;
 use neutron_sdk::NeutronError;
 use thiserror::Error;
@@ -46,6 +46,24 @@ pub enum ContractError {
 
     #[error("Refundee is not set")]
     RefundeeIsNotSet {},
+
+    #[error("Sweep is not configured")]
+    SweepIsNotConfigured {},
+
+    #[error("Nothing to sweep")]
+    NothingToSweep {},
+
+    #[error("ICA balances are not updated since the last sweep or settled transfer")]
+    BalancesNotUpdated {},
+
+    #[error("Previous transfers are not settled yet")]
+    TransfersNotSettled {},
+
+    #[error("Fee reserve is insufficient: required {required}, available {available}")]
+    InsufficientFeeReserve {
+        required: Uint128,
+        available: Uint128,
+    },
 }
 
 pub type ContractResult<T> = Result<T, ContractError>;
@@ -0,0 +1,664 @@
+use cosmos_sdk_proto::ibc::applications::transfer::v1::MsgTransfer;
+use cosmwasm_std::{
+    coin, coins, from_json,
+    testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR},
+    to_json_binary, Binary, ContractResult, CosmosMsg, Order, OwnedDeps, Querier, QuerierResult,
+    QueryRequest, Reply, StdError, StdResult, SubMsg, SubMsgResponse, SubMsgResult, SystemResult,
+    Timestamp, Uint128, WasmMsg,
+};
+use drop_helpers::ica::IcaState;
+use drop_staking_base::{
+    msg::{
+        pump::{
+            ExecuteMsg, InstantiateMsg, OpenAckVersion, PumpTransferHookMsg, ReceiverExecuteMsg,
+        },
+        puppeteer::MultiBalances,
+    },
+    state::pump::{
+        IBCFees, PumpRoute, PumpSweepConfig, PumpTimeout, PumpTransfer, PumpTransferStatus, CONFIG,
+        ICA, ICA_BALANCES, LAST_SETTLED_TRANSFER_HEIGHT, LAST_SWEEP_HEIGHT, SUBMITTED_TRANSFERS,
+        TRANSFERS,
+    },
+};
+use neutron_sdk::{
+    bindings::{
+        msg::{MsgSubmitTxResponse, NeutronMsg},
+        query::NeutronQuery,
+    },
+    sudo::msg::{RequestPacket, SudoMsg},
+    NeutronError,
+};
+use prost::Message;
+use std::marker::PhantomData;
+
+use crate::{
+    contract::{execute, instantiate, reply, sudo, HOOK_REPLY_ID, SUBMIT_TX_REPLY_ID},
+    error::ContractError,
+};
+
+struct PumpQuerier {
+    base: MockQuerier<NeutronQuery>,
+}
+
+// the light client of the connection is at the remote height 15
+impl Querier for PumpQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<NeutronQuery> = from_json(bin_request).unwrap();
+        let reply: &[u8] = match &request {
+            QueryRequest::Stargate { path, .. }
+                if path == "/ibc.core.connection.v1.Query/Connection" =>
+            {
+                br#"{"connection":{"client_id":"07-tendermint-0","state":"STATE_OPEN"}}"#
+            }
+            QueryRequest::Stargate { path, .. } if path == "/ibc.core.client.v1.Query/ClientState" => {
+                br#"{"client_state":{"@type":"/ibc.lightclients.tendermint.v1.ClientState","latest_height":{"revision_number":"1","revision_height":"15"}}}"#
+            }
+            _ => return self.base.raw_query(bin_request),
+        };
+        SystemResult::Ok(ContractResult::Ok(Binary::from(reply)))
+    }
+}
+
+fn mock_dependencies() -> OwnedDeps<MockStorage, MockApi, PumpQuerier, NeutronQuery> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: PumpQuerier {
+            base: MockQuerier::new(&[(MOCK_CONTRACT_ADDR, &coins(60, "untrn"))]),
+        },
+        custom_query_type: PhantomData,
+    }
+}
+
+fn ibc_fees() -> IBCFees {
+    IBCFees {
+        recv_fee: Uint128::new(10),
+        ack_fee: Uint128::new(20),
+        timeout_fee: Uint128::new(30),
+        register_fee: Uint128::new(100),
+    }
+}
+
+fn default_route() -> PumpRoute {
+    PumpRoute {
+        dest_address: "dest_address".to_string(),
+        dest_channel: "channel-0".to_string(),
+        dest_port: "transfer".to_string(),
+        ibc_fees: ibc_fees(),
+        timeout: PumpTimeout {
+            local: None,
+            remote: 100,
+        },
+    }
+}
+
+fn pending_transfer(amount: u128, denom: &str) -> PumpTransfer {
+    PumpTransfer {
+        amount: coin(amount, denom),
+        route: default_route(),
+        status: PumpTransferStatus::Pending,
+        retries: 0,
+    }
+}
+
+// the contract balance covers the fees of a single transfer of the default route
+fn setup() -> OwnedDeps<MockStorage, MockApi, PumpQuerier, NeutronQuery> {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut().into_empty(),
+        mock_env(),
+        mock_info("admin", &[]),
+        InstantiateMsg {
+            dest_address: Some("dest_address".to_string()),
+            dest_channel: Some("channel-0".to_string()),
+            dest_port: Some("transfer".to_string()),
+            connection_id: "connection-0".to_string(),
+            ibc_fees: ibc_fees(),
+            refundee: None,
+            timeout: PumpTimeout {
+                local: None,
+                remote: 100,
+            },
+            local_denom: "untrn".to_string(),
+            owner: Some("owner".to_string()),
+            max_retries: 1,
+            core_contract: Some("core".to_string()),
+            sweep: Some(PumpSweepConfig {
+                denoms: vec!["uatom".to_string()],
+                update_period: 100,
+                sdk_version: "0.47.10".to_string(),
+            }),
+        },
+    )
+    .unwrap();
+    ICA.set_address(deps.as_mut().storage, "ica_address")
+        .unwrap();
+    deps
+}
+
+fn get_transfer_msg(submsg: &SubMsg<NeutronMsg>) -> MsgTransfer {
+    assert_eq!(submsg.id, SUBMIT_TX_REPLY_ID);
+    let CosmosMsg::Custom(NeutronMsg::SubmitTx { msgs, .. }) = &submsg.msg else {
+        panic!("unexpected message: {:?}", submsg.msg);
+    };
+    MsgTransfer::decode(msgs[0].value.as_slice()).unwrap()
+}
+
+fn get_hook_msg(amount: u128, denom: &str, succeeded: bool) -> SubMsg<NeutronMsg> {
+    SubMsg::reply_on_error(
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "core".to_string(),
+            msg: to_json_binary(&ReceiverExecuteMsg::PumpHook(PumpTransferHookMsg {
+                amount: coin(amount, denom),
+                receiver: "dest_address".to_string(),
+                succeeded,
+            }))
+            .unwrap(),
+            funds: vec![],
+        }),
+        HOOK_REPLY_ID,
+    )
+}
+
+fn submit_tx_reply(sequence_id: u64) -> Reply {
+    Reply {
+        id: SUBMIT_TX_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(
+                to_json_binary(&MsgSubmitTxResponse {
+                    sequence_id,
+                    channel: "channel-ica".to_string(),
+                })
+                .unwrap(),
+            ),
+        }),
+    }
+}
+
+fn request_packet(sequence: u64) -> RequestPacket {
+    RequestPacket {
+        sequence: Some(sequence),
+        source_port: Some("icacontroller".to_string()),
+        source_channel: Some("channel-ica".to_string()),
+        destination_port: Some("icahost".to_string()),
+        destination_channel: Some("channel-host".to_string()),
+        data: None,
+        timeout_height: None,
+        timeout_timestamp: None,
+    }
+}
+
+#[test]
+fn push_routes_transfers_by_denom() {
+    let mut deps = setup();
+    let osmo_route = PumpRoute {
+        dest_address: "osmo_address".to_string(),
+        dest_channel: "channel-1".to_string(),
+        dest_port: "transfer".to_string(),
+        ibc_fees: IBCFees {
+            recv_fee: Uint128::new(1),
+            ack_fee: Uint128::new(1),
+            timeout_fee: Uint128::new(1),
+            register_fee: Uint128::zero(),
+        },
+        timeout: PumpTimeout {
+            local: None,
+            remote: 200,
+        },
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetRoute {
+            denom: "uosmo".to_string(),
+            route: osmo_route,
+        },
+    )
+    .unwrap();
+
+    let push = ExecuteMsg::Push {
+        coins: vec![coin(100, "uatom"), coin(200, "uosmo")],
+    };
+    // every route pays its own fees
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pusher", &coins(62, "untrn")),
+        push.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidFunds {
+            reason: "invalid amount: expected at least 63, got 62".to_string()
+        }
+    );
+    // a failed tx is reverted on chain, but not in the mocked storage
+    SUBMITTED_TRANSFERS.remove(deps.as_mut().storage);
+
+    let env = mock_env();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("pusher", &coins(63, "untrn")),
+        push,
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 2);
+
+    let transfer = get_transfer_msg(&res.messages[0]);
+    assert_eq!(transfer.receiver, "dest_address");
+    assert_eq!(transfer.source_channel, "channel-0");
+    assert_eq!(transfer.sender, "ica_address");
+    assert_eq!(transfer.token.unwrap().amount, "100");
+    assert_eq!(
+        transfer.timeout_timestamp,
+        env.block.time.plus_seconds(100).nanos()
+    );
+
+    let transfer = get_transfer_msg(&res.messages[1]);
+    assert_eq!(transfer.receiver, "osmo_address");
+    assert_eq!(transfer.source_channel, "channel-1");
+    assert_eq!(transfer.token.unwrap().denom, "uosmo");
+    assert_eq!(
+        transfer.timeout_timestamp,
+        env.block.time.plus_seconds(200).nanos()
+    );
+
+    // without a route and a default destination the denom can't be pushed
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveRoute {
+            denom: "uosmo".to_string(),
+        },
+    )
+    .unwrap();
+    let mut config = CONFIG.load(deps.as_ref().storage).unwrap();
+    config.dest_address = None;
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pusher", &coins(60, "untrn")),
+        ExecuteMsg::Push {
+            coins: vec![coin(200, "uosmo")],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoDestinationAddress {});
+}
+
+#[test]
+fn submit_tx_replies_follow_submission_order() {
+    let mut deps = setup();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pusher", &coins(120, "untrn")),
+        ExecuteMsg::Push {
+            coins: vec![coin(100, "uatom"), coin(200, "uosmo")],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        SUBMITTED_TRANSFERS.load(deps.as_ref().storage).unwrap(),
+        vec![
+            pending_transfer(100, "uatom"),
+            pending_transfer(200, "uosmo")
+        ]
+    );
+
+    reply(deps.as_mut(), mock_env(), submit_tx_reply(1)).unwrap();
+    reply(deps.as_mut(), mock_env(), submit_tx_reply(2)).unwrap();
+    assert_eq!(
+        TRANSFERS
+            .load(deps.as_ref().storage, ("channel-ica".to_string(), 1))
+            .unwrap(),
+        pending_transfer(100, "uatom")
+    );
+    assert_eq!(
+        TRANSFERS
+            .load(deps.as_ref().storage, ("channel-ica".to_string(), 2))
+            .unwrap(),
+        pending_transfer(200, "uosmo")
+    );
+    assert!(SUBMITTED_TRANSFERS
+        .load(deps.as_ref().storage)
+        .unwrap()
+        .is_empty());
+
+    let err = reply(deps.as_mut(), mock_env(), submit_tx_reply(3)).unwrap_err();
+    assert_eq!(
+        err,
+        NeutronError::Std(StdError::generic_err("no submitted transfer"))
+    );
+}
+
+#[test]
+fn sudo_response_settles_transfer() {
+    let mut deps = setup();
+    TRANSFERS
+        .save(
+            deps.as_mut().storage,
+            ("channel-ica".to_string(), 1),
+            &pending_transfer(100, "uatom"),
+        )
+        .unwrap();
+
+    let res = sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::Response {
+            request: request_packet(1),
+            data: Binary::default(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![get_hook_msg(100, "uatom", true)]);
+    assert!(TRANSFERS.is_empty(deps.as_ref().storage));
+    assert_eq!(
+        LAST_SETTLED_TRANSFER_HEIGHT
+            .load(deps.as_ref().storage)
+            .unwrap(),
+        15
+    );
+}
+
+#[test]
+fn failed_transfer_is_retried_until_retries_are_exhausted() {
+    let mut deps = setup();
+    TRANSFERS
+        .save(
+            deps.as_mut().storage,
+            ("channel-ica".to_string(), 1),
+            &pending_transfer(100, "uatom"),
+        )
+        .unwrap();
+
+    let res = sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::Error {
+            request: request_packet(1),
+            details: "insufficient funds".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(get_transfer_msg(&res.messages[0]).receiver, "dest_address");
+    assert!(TRANSFERS.is_empty(deps.as_ref().storage));
+    assert_eq!(
+        SUBMITTED_TRANSFERS.load(deps.as_ref().storage).unwrap(),
+        vec![PumpTransfer {
+            retries: 1,
+            ..pending_transfer(100, "uatom")
+        }]
+    );
+
+    reply(deps.as_mut(), mock_env(), submit_tx_reply(2)).unwrap();
+    let res = sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::Error {
+            request: request_packet(2),
+            details: "insufficient funds".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![get_hook_msg(100, "uatom", false)]);
+    assert_eq!(
+        TRANSFERS
+            .load(deps.as_ref().storage, ("channel-ica".to_string(), 2))
+            .unwrap(),
+        PumpTransfer {
+            retries: 1,
+            status: PumpTransferStatus::Failed,
+            ..pending_transfer(100, "uatom")
+        }
+    );
+}
+
+#[test]
+fn timed_out_transfers_are_resubmitted_on_open_ack() {
+    let mut deps = setup();
+    for (sequence, denom) in [(1, "uatom"), (2, "uosmo")] {
+        TRANSFERS
+            .save(
+                deps.as_mut().storage,
+                ("channel-ica".to_string(), sequence),
+                &pending_transfer(100, denom),
+            )
+            .unwrap();
+        sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::Timeout {
+                request: request_packet(sequence),
+            },
+        )
+        .unwrap();
+    }
+    assert_eq!(ICA.load(deps.as_ref().storage).unwrap(), IcaState::Timeout);
+    assert_eq!(
+        TRANSFERS
+            .load(deps.as_ref().storage, ("channel-ica".to_string(), 1))
+            .unwrap()
+            .status,
+        PumpTransferStatus::TimedOut
+    );
+
+    let res = sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::OpenAck {
+            port_id: "icacontroller".to_string(),
+            channel_id: "channel-ica-2".to_string(),
+            counterparty_channel_id: "channel-host-2".to_string(),
+            counterparty_version: serde_json_wasm::to_string(&OpenAckVersion {
+                version: "ics27-1".to_string(),
+                controller_connection_id: "connection-0".to_string(),
+                host_connection_id: "connection-1".to_string(),
+                address: "new_ica_address".to_string(),
+                encoding: "proto3".to_string(),
+                tx_type: "sdk_multi_msg".to_string(),
+            })
+            .unwrap(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        ICA.get_address(deps.as_ref().storage).unwrap(),
+        "new_ica_address"
+    );
+    // the balance covers the fees of the first transfer only
+    assert_eq!(res.messages.len(), 2);
+    let transfer = get_transfer_msg(&res.messages[0]);
+    assert_eq!(transfer.sender, "new_ica_address");
+    assert_eq!(transfer.token.unwrap().denom, "uatom");
+    assert_eq!(res.messages[1], get_hook_msg(100, "uosmo", false));
+    assert_eq!(
+        TRANSFERS
+            .range(deps.as_ref().storage, None, None, Order::Ascending)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        vec![(
+            ("channel-ica".to_string(), 2),
+            PumpTransfer {
+                status: PumpTransferStatus::Failed,
+                ..pending_transfer(100, "uosmo")
+            }
+        )]
+    );
+}
+
+#[test]
+fn sweep_pushes_watched_balances_once() {
+    let mut deps = setup();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Sweep {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToSweep {});
+
+    ICA_BALANCES
+        .save(
+            deps.as_mut().storage,
+            &(
+                MultiBalances {
+                    coins: vec![coin(100, "uatom"), coin(50, "uosmo")],
+                },
+                10,
+                Timestamp::from_seconds(100),
+            ),
+        )
+        .unwrap();
+    TRANSFERS
+        .save(
+            deps.as_mut().storage,
+            ("channel-ica".to_string(), 1),
+            &pending_transfer(100, "uatom"),
+        )
+        .unwrap();
+    // the pending transfer can still be part of the observed balance
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Sweep {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::TransfersNotSettled {});
+
+    // the balances are taken before the transfer is executed on the remote chain
+    sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::Response {
+            request: request_packet(1),
+            data: Binary::default(),
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Sweep {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BalancesNotUpdated {});
+
+    ICA_BALANCES
+        .save(
+            deps.as_mut().storage,
+            &(
+                MultiBalances {
+                    coins: vec![coin(100, "uatom"), coin(50, "uosmo")],
+                },
+                16,
+                Timestamp::from_seconds(200),
+            ),
+        )
+        .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Sweep {},
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    let transfer = get_transfer_msg(&res.messages[0]);
+    assert_eq!(transfer.receiver, "dest_address");
+    assert_eq!(transfer.token.unwrap().amount, "100");
+    assert_eq!(LAST_SWEEP_HEIGHT.load(deps.as_ref().storage).unwrap(), 16);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Sweep {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BalancesNotUpdated {});
+}
+
+#[test]
+fn sweep_requires_fee_reserve() {
+    let mut deps = setup();
+    deps.querier
+        .base
+        .update_balance(MOCK_CONTRACT_ADDR, coins(59, "untrn"));
+    ICA_BALANCES
+        .save(
+            deps.as_mut().storage,
+            &(
+                MultiBalances {
+                    coins: vec![coin(100, "uatom")],
+                },
+                10,
+                Timestamp::from_seconds(100),
+            ),
+        )
+        .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Sweep {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientFeeReserve {
+            required: Uint128::new(60),
+            available: Uint128::new(59),
+        }
+    );
+}
+
+#[test]
+fn refund_is_owner_only() {
+    let mut deps = setup();
+    deps.querier
+        .base
+        .update_balance(MOCK_CONTRACT_ADDR, coins(60, "untrn"));
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| {
+            config.refundee = Some(cosmwasm_std::Addr::unchecked("refundee"));
+            StdResult::Ok(config)
+        })
+        .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Refund {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Refund {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(cosmwasm_std::BankMsg::Send {
+            to_address: "refundee".to_string(),
+            amount: coins(60, "untrn"),
+        })
+    );
+}
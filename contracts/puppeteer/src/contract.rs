@@ -4,8 +4,10 @@ use crate::{
         distribution::v1beta1::MsgWithdrawDelegatorReward,
         staking::v1beta1::{
             MsgBeginRedelegate, MsgBeginRedelegateResponse, MsgDelegateResponse,
-            MsgRedeemTokensforShares, MsgRedeemTokensforSharesResponse, MsgTokenizeShares,
-            MsgTokenizeSharesResponse, MsgUndelegateResponse,
+            MsgDisableTokenizeShares, MsgDisableTokenizeSharesResponse, MsgRedeemTokensforShares,
+            MsgRedeemTokensforSharesResponse, MsgTokenizeShares, MsgTokenizeSharesResponse,
+            MsgTransferTokenizeShareRecord, MsgTransferTokenizeShareRecordResponse,
+            MsgUndelegateResponse,
         },
     },
 };
@@ -14,6 +16,7 @@ use cosmos_sdk_proto::cosmos::{
     base::{abci::v1beta1::TxMsgData, v1beta1::Coin},
     staking::v1beta1::{MsgDelegate, MsgUndelegate},
 };
+use cosmos_sdk_proto::ibc::applications::transfer::v1::{MsgTransfer, MsgTransferResponse};
 use cosmwasm_std::{
     attr, ensure_eq, entry_point, to_json_binary, Addr, Attribute, CosmosMsg, Deps, Order, Reply,
     StdError, SubMsg, Timestamp, Uint128, WasmMsg,
@@ -23,15 +26,17 @@ use cw2::set_contract_version;
 use drop_helpers::{
     answer::response,
     icq::{
-        new_delegations_and_balance_query_msg, new_multiple_balances_query_msg,
-        update_balance_and_delegations_query_msg, update_multiple_balances_query_msg,
+        new_delegations_and_balance_query_msg, new_liquid_staking_caps_query_msg,
+        new_multiple_balances_query_msg, update_balance_and_delegations_query_msg,
+        update_liquid_staking_caps_query_msg, update_multiple_balances_query_msg,
     },
 };
 use drop_puppeteer_base::{
     error::{ContractError, ContractResult},
     msg::{
-        IBCTransferReason, QueryMsg, ReceiverExecuteMsg, ResponseAnswer, ResponseHookErrorMsg,
-        ResponseHookMsg, ResponseHookSuccessMsg, Transaction, TransferReadyBatchesMsg,
+        IBCTransferItem, IBCTransferReason, QueryMsg, ReceiverExecuteMsg, ResponseAnswer,
+        ResponseHookErrorMsg, ResponseHookMsg, ResponseHookSuccessMsg, Transaction,
+        TransferReadyBatchesMsg,
     },
     proto::MsgIBCTransfer,
     state::{
@@ -44,7 +49,8 @@ use drop_staking_base::{
         BalancesAndDelegations, ExecuteMsg, FeesResponse, InstantiateMsg, MigrateMsg, QueryExtMsg,
     },
     state::puppeteer::{
-        Config, ConfigOptional, KVQueryType, DELEGATIONS_AND_BALANCE, NON_NATIVE_REWARD_BALANCES,
+        Config, ConfigOptional, KVQueryType, DELEGATIONS_AND_BALANCE, LIQUID_STAKING_CAPS,
+        NON_NATIVE_REWARDS_DENOMS, NON_NATIVE_REWARD_BALANCES,
     },
 };
 use neutron_sdk::interchain_queries::v045::new_register_delegator_unbonding_delegations_query_msg;
@@ -120,6 +126,7 @@ pub fn query(
             QueryExtMsg::Delegations {} => query_delegations(deps),
             QueryExtMsg::Balances {} => query_balances(deps),
             QueryExtMsg::NonNativeRewardsBalances {} => query_non_native_rewards_balances(deps),
+            QueryExtMsg::NonNativeRewardsDenoms {} => query_non_native_rewards_denoms(deps),
             QueryExtMsg::UnbondingDelegations {} => to_json_binary(
                 &Puppeteer::default()
                     .unbonding_delegations
@@ -129,6 +136,7 @@ pub fn query(
             )
             .map_err(ContractError::Std),
             QueryExtMsg::Fees {} => query_fees(deps),
+            QueryExtMsg::LiquidStakingCaps {} => query_liquid_staking_caps(deps),
         },
         _ => Puppeteer::default().query(deps, env, msg),
     }
@@ -159,6 +167,23 @@ fn query_non_native_rewards_balances(deps: Deps<NeutronQuery>) -> ContractResult
     to_json_binary(&(data.0, data.1, data.2)).map_err(ContractError::Std)
 }
 
+fn query_non_native_rewards_denoms(deps: Deps<NeutronQuery>) -> ContractResult<Binary> {
+    let denoms = match NON_NATIVE_REWARDS_DENOMS.may_load(deps.storage)? {
+        Some(denoms) => denoms,
+        // registered before the denoms were stored, take them from the last query result
+        None => NON_NATIVE_REWARD_BALANCES
+            .may_load(deps.storage)?
+            .map(|(balances, _, _)| balances.coins.into_iter().map(|c| c.denom).collect())
+            .unwrap_or_default(),
+    };
+    to_json_binary(&denoms).map_err(ContractError::Std)
+}
+
+fn query_liquid_staking_caps(deps: Deps<NeutronQuery>) -> ContractResult<Binary> {
+    let data = LIQUID_STAKING_CAPS.may_load(deps.storage)?;
+    to_json_binary(&data).map_err(ContractError::Std)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut<NeutronQuery>,
@@ -197,9 +222,29 @@ pub fn execute(
         ExecuteMsg::TokenizeShare {
             validator,
             amount,
+            tokenized_share_owner,
             timeout,
             reply_to,
-        } => execute_tokenize_share(deps, info, validator, amount, timeout, reply_to),
+        } => execute_tokenize_share(
+            deps,
+            info,
+            validator,
+            amount,
+            tokenized_share_owner,
+            timeout,
+            reply_to,
+        ),
+        ExecuteMsg::TransferTokenizeShareRecord {
+            record_id,
+            new_owner,
+            timeout,
+            reply_to,
+        } => execute_transfer_tokenize_share_record(
+            deps, info, record_id, new_owner, timeout, reply_to,
+        ),
+        ExecuteMsg::DisableTokenizeShares { timeout, reply_to } => {
+            execute_disable_tokenize_shares(deps, info, timeout, reply_to)
+        }
         ExecuteMsg::RedeemShares {
             items,
             timeout,
@@ -222,16 +267,21 @@ pub fn execute(
         ExecuteMsg::RegisterNonNativeRewardsBalancesQuery { denoms } => {
             register_non_native_rewards_balances_query(deps, info, denoms)
         }
+        ExecuteMsg::RegisterLiquidStakingCapsQuery { validators } => {
+            register_liquid_staking_caps_query(deps, info, validators)
+        }
         ExecuteMsg::IBCTransfer {
             timeout,
             reply_to,
             reason,
-        } => execute_ibc_transfer(deps, env, info, reason, timeout, reply_to),
+            source_path,
+        } => execute_ibc_transfer(deps, env, info, reason, timeout, reply_to, source_path),
         ExecuteMsg::Transfer {
             items,
+            ibc_items,
             timeout,
             reply_to,
-        } => execute_transfer(deps, info, items, timeout, reply_to),
+        } => execute_transfer(deps, env, info, items, ibc_items, timeout, reply_to),
         ExecuteMsg::UpdateConfig { new_config } => execute_update_config(deps, info, new_config),
         _ => puppeteer_base.execute(deps, env, info, msg.to_base_enum()),
     }
@@ -300,11 +350,15 @@ fn execute_ibc_transfer(
     reason: IBCTransferReason,
     timeout: u64,
     reply_to: String,
+    source_path: Option<(String, String)>,
 ) -> ContractResult<Response<NeutronMsg>> {
     let puppeteer_base = Puppeteer::default();
     let config = puppeteer_base.config.load(deps.storage)?;
     validate_sender(&config, &info.sender)?;
     puppeteer_base.validate_tx_idle_state(deps.as_ref())?;
+    // IBC vouchers have to go back over the path they arrived through
+    let (source_port, source_channel) =
+        source_path.unwrap_or((config.port_id, config.transfer_channel_id));
     // exclude fees, no need to send local denom tokens to remote zone
     let message_funds: Vec<_> = info
         .funds
@@ -323,8 +377,8 @@ fn execute_ibc_transfer(
     })?;
     let ica_address = puppeteer_base.ica.get_address(deps.storage)?;
     let msg = NeutronMsg::IbcTransfer {
-        source_port: config.port_id,
-        source_channel: config.transfer_channel_id,
+        source_port,
+        source_channel,
         token: (*coin).clone(),
         sender: env.contract.address.to_string(),
         receiver: ica_address.to_string(),
@@ -362,7 +416,10 @@ fn register_non_native_rewards_balances_query(
     ));
     let puppeteer_base = Puppeteer::default();
     let config = puppeteer_base.config.load(deps.storage)?;
-    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    // core keeps the query in sync with its non-native rewards receivers
+    if cw_ownable::assert_owner(deps.storage, &info.sender).is_err() {
+        validate_sender(&config, &info.sender)?;
+    }
     let kv_queries = puppeteer_base
         .kv_queries
         .range(deps.storage, None, None, Order::Ascending)
@@ -370,6 +427,16 @@ fn register_non_native_rewards_balances_query(
     let ica = puppeteer_base.ica.get_address(deps.storage)?;
     let mut messages = vec![];
     let mut submessages = vec![];
+    NON_NATIVE_REWARDS_DENOMS.save(deps.storage, &denoms)?;
+    if denoms.is_empty() {
+        for (query_id, query_type) in kv_queries {
+            if query_type == KVQueryType::NonNativeRewardsBalances {
+                messages.push(NeutronMsg::remove_interchain_query(query_id));
+                puppeteer_base.kv_queries.remove(deps.storage, query_id);
+            }
+        }
+        return Ok(Response::new().add_messages(messages));
+    }
     for (query_id, query_type) in kv_queries {
         if query_type == KVQueryType::NonNativeRewardsBalances {
             messages.push(update_multiple_balances_query_msg(
@@ -443,6 +510,47 @@ fn register_balance_delegations_query(
         .add_submessages(submessages))
 }
 
+fn register_liquid_staking_caps_query(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    validators: Vec<String>,
+) -> ContractResult<Response<NeutronMsg>> {
+    let puppeteer_base = Puppeteer::default();
+    let config = puppeteer_base.config.load(deps.storage)?;
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let kv_queries = puppeteer_base
+        .kv_queries
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<Result<Vec<(u64, KVQueryType)>, _>>()?;
+    let mut messages = vec![];
+    let mut submessages = vec![];
+    for (query_id, query_type) in kv_queries {
+        if query_type == KVQueryType::LiquidStakingCaps {
+            messages.push(update_liquid_staking_caps_query_msg(
+                query_id,
+                config.remote_denom.to_string(),
+                validators.clone(),
+                config.sdk_version.as_str(),
+            )?);
+        }
+    }
+    if messages.is_empty() {
+        submessages.push(SubMsg::reply_on_success(
+            new_liquid_staking_caps_query_msg(
+                config.connection_id.clone(),
+                config.remote_denom.clone(),
+                validators,
+                config.update_period,
+                config.sdk_version.as_str(),
+            )?,
+            ReplyMsg::KvLiquidStakingCaps.to_reply_id(),
+        ));
+    }
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(submessages))
+}
+
 fn register_unbonding_delegations_query(
     deps: DepsMut<NeutronQuery>,
     info: MessageInfo,
@@ -540,8 +648,10 @@ fn execute_delegate(
 
 fn execute_transfer(
     mut deps: DepsMut<NeutronQuery>,
+    env: Env,
     info: MessageInfo,
     items: Vec<(String, cosmwasm_std::Coin)>,
+    ibc_items: Vec<IBCTransferItem>,
     timeout: Option<u64>,
     reply_to: String,
 ) -> ContractResult<Response<NeutronMsg>> {
@@ -570,6 +680,29 @@ fn execute_transfer(
             "/cosmos.bank.v1beta1.MsgSend",
         )?);
     }
+    let timeout_timestamp = env
+        .block
+        .time
+        .plus_seconds(timeout.unwrap_or(DEFAULT_TIMEOUT_SECONDS))
+        .nanos();
+    for item in ibc_items.clone() {
+        let transfer_msg = MsgTransfer {
+            source_port: "transfer".to_string(),
+            source_channel: item.channel,
+            token: Some(Coin {
+                amount: item.amount.amount.to_string(),
+                denom: item.amount.denom,
+            }),
+            sender: ica.to_string(),
+            receiver: item.receiver,
+            timeout_height: None,
+            timeout_timestamp,
+        };
+        any_msgs.push(prepare_any_msg(
+            transfer_msg,
+            "/ibc.applications.transfer.v1.MsgTransfer",
+        )?);
+    }
     let submsg = compose_submsg(
         deps.branch(),
         config.clone(),
@@ -577,6 +710,7 @@ fn execute_transfer(
         Transaction::Transfer {
             interchain_account_id: ICA_ID.to_string(),
             items,
+            ibc_items,
         },
         timeout,
         reply_to,
@@ -740,6 +874,7 @@ fn execute_tokenize_share(
     info: MessageInfo,
     validator: String,
     amount: Uint128,
+    tokenized_share_owner: Option<String>,
     timeout: Option<u64>,
     reply_to: String,
 ) -> ContractResult<Response<NeutronMsg>> {
@@ -749,10 +884,12 @@ fn execute_tokenize_share(
     validate_sender(&config, &info.sender)?;
     puppeteer_base.validate_tx_idle_state(deps.as_ref())?;
     let delegator = puppeteer_base.ica.get_address(deps.storage)?;
+    // the record owner is a remote chain address, so it can't be validated here
+    let tokenized_share_owner = tokenized_share_owner.unwrap_or_else(|| delegator.clone());
     let tokenize_msg = MsgTokenizeShares {
-        delegator_address: delegator.clone(),
+        delegator_address: delegator,
         validator_address: validator.to_string(),
-        tokenized_share_owner: delegator,
+        tokenized_share_owner: tokenized_share_owner.clone(),
         amount: Some(ProtoCoin {
             denom: config.remote_denom.to_string(),
             amount: amount.to_string(),
@@ -770,6 +907,79 @@ fn execute_tokenize_share(
             validator,
             denom: config.remote_denom,
             amount: amount.into(),
+            tokenized_share_owner: Some(tokenized_share_owner),
+        },
+        timeout,
+        reply_to,
+        ReplyMsg::SudoPayload.to_reply_id(),
+    )?;
+
+    Ok(Response::default().add_submessages(vec![submsg]))
+}
+
+fn execute_transfer_tokenize_share_record(
+    mut deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    record_id: u64,
+    new_owner: String,
+    timeout: Option<u64>,
+    reply_to: String,
+) -> ContractResult<Response<NeutronMsg>> {
+    let puppeteer_base = Puppeteer::default();
+    deps.api.addr_validate(&reply_to)?;
+    let config: Config = puppeteer_base.config.load(deps.storage)?;
+    validate_sender(&config, &info.sender)?;
+    puppeteer_base.validate_tx_idle_state(deps.as_ref())?;
+    let ica = puppeteer_base.ica.get_address(deps.storage)?;
+    let transfer_msg = MsgTransferTokenizeShareRecord {
+        tokenize_share_record_id: record_id,
+        sender: ica,
+        new_owner: new_owner.to_string(),
+    };
+    let submsg = compose_submsg(
+        deps.branch(),
+        config,
+        vec![prepare_any_msg(
+            transfer_msg,
+            "/cosmos.staking.v1beta1.MsgTransferTokenizeShareRecord",
+        )?],
+        Transaction::TransferTokenizeShareRecord {
+            interchain_account_id: ICA_ID.to_string(),
+            record_id,
+            new_owner,
+        },
+        timeout,
+        reply_to,
+        ReplyMsg::SudoPayload.to_reply_id(),
+    )?;
+
+    Ok(Response::default().add_submessages(vec![submsg]))
+}
+
+fn execute_disable_tokenize_shares(
+    mut deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    timeout: Option<u64>,
+    reply_to: String,
+) -> ContractResult<Response<NeutronMsg>> {
+    let puppeteer_base = Puppeteer::default();
+    deps.api.addr_validate(&reply_to)?;
+    let config: Config = puppeteer_base.config.load(deps.storage)?;
+    validate_sender(&config, &info.sender)?;
+    puppeteer_base.validate_tx_idle_state(deps.as_ref())?;
+    let delegator = puppeteer_base.ica.get_address(deps.storage)?;
+    let disable_msg = MsgDisableTokenizeShares {
+        delegator_address: delegator,
+    };
+    let submsg = compose_submsg(
+        deps.branch(),
+        config,
+        vec![prepare_any_msg(
+            disable_msg,
+            "/cosmos.staking.v1beta1.MsgDisableTokenizeShares",
+        )?],
+        Transaction::DisableTokenizeShares {
+            interchain_account_id: ICA_ID.to_string(),
         },
         timeout,
         reply_to,
@@ -907,6 +1117,13 @@ pub fn sudo(deps: DepsMut<NeutronQuery>, env: Env, msg: SudoMsg) -> NeutronResul
                 KVQueryType::UnbondingDelegations => {
                     puppeteer_base.sudo_unbonding_delegations_kv_query_result(deps, env, query_id)
                 }
+                KVQueryType::LiquidStakingCaps => puppeteer_base.sudo_kv_query_result(
+                    deps,
+                    env,
+                    query_id,
+                    &config.sdk_version,
+                    LIQUID_STAKING_CAPS,
+                ),
             }
         }
         SudoMsg::OpenAck {
@@ -1038,10 +1255,27 @@ fn get_answers_from_msg_data(
                     },
                 )
             }
+            "/cosmos.staking.v1beta1.MsgTransferTokenizeShareRecord" => {
+                let _out: MsgTransferTokenizeShareRecordResponse =
+                    decode_message_response(&item.data)?;
+                ResponseAnswer::TransferTokenizeShareRecordResponse(
+                    drop_puppeteer_base::proto::MsgTransferTokenizeShareRecordResponse {},
+                )
+            }
+            "/cosmos.staking.v1beta1.MsgDisableTokenizeShares" => {
+                let _out: MsgDisableTokenizeSharesResponse = decode_message_response(&item.data)?;
+                ResponseAnswer::DisableTokenizeSharesResponse(
+                    drop_puppeteer_base::proto::MsgDisableTokenizeSharesResponse {},
+                )
+            }
             "/cosmos.bank.v1beta1.MsgSend" => {
                 let _out: MsgSendResponse = decode_message_response(&item.data)?;
                 ResponseAnswer::TransferResponse(drop_puppeteer_base::proto::MsgSendResponse {})
             }
+            "/ibc.applications.transfer.v1.MsgTransfer" => {
+                let _out: MsgTransferResponse = decode_message_response(&item.data)?;
+                ResponseAnswer::IBCTransfer(MsgIBCTransfer {})
+            }
             _ => {
                 deps.api.debug(
                     format!("This type of acknowledgement is not implemented: {item:?}").as_str(),
@@ -1186,6 +1420,9 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> StdResult<Response> {
             ));
             puppeteer_base.register_kv_query_reply(deps, msg, KVQueryType::NonNativeRewardsBalances)
         }
+        ReplyMsg::KvLiquidStakingCaps => {
+            puppeteer_base.register_kv_query_reply(deps, msg, KVQueryType::LiquidStakingCaps)
+        }
         ReplyMsg::KvUnbondingDelegations { validator_index } => {
             deps.api.debug(&format!(
                 "WASMDEBUG: UNBONDING_DELEGATIONS_REPLY_ID {:?}",
@@ -349,6 +349,10 @@ pub struct Params {
     /// comes from liquid staking providers
     #[prost(string, tag = "8")]
     pub global_liquid_staking_cap: ::prost::alloc::string::String,
+    /// validator_liquid_staking_cap represents a cap on the portion of stake that
+    /// comes from liquid staking providers for a specific validator
+    #[prost(string, tag = "9")]
+    pub validator_liquid_staking_cap: ::prost::alloc::string::String,
 }
 /// DelegationResponse is equivalent to Delegation except that it contains a
 /// balance in addition to shares which is more suitable for client responses.
@@ -1058,6 +1062,16 @@ pub struct MsgTransferTokenizeShareRecord {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct MsgTransferTokenizeShareRecordResponse {}
+/// MsgDisableTokenizeShares prevents the tokenization of shares for a given address
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgDisableTokenizeShares {
+    #[prost(string, tag = "1")]
+    pub delegator_address: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgDisableTokenizeSharesResponse {}
 /// MsgValidatorBond defines a SDK message for performing validator self-bond of delegated coins
 /// from a delegator to a validator.
 #[allow(clippy::derive_partial_eq_without_eq)]
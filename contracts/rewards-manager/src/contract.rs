@@ -1,34 +1,66 @@
-use cosmwasm_std::{attr, entry_point, to_json_binary, Attribute, CosmosMsg, Deps, Order, WasmMsg};
+use cosmwasm_std::{
+    attr, coin, entry_point, to_json_binary, Attribute, Coin, CosmosMsg, Deps, Order, Reply,
+    StdError, Storage, SubMsg, Uint128, WasmMsg,
+};
 use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Response, StdResult};
 use cw2::set_contract_version;
-use drop_helpers::answer::response;
+use cw_storage_plus::Bound;
+use drop_helpers::answer::{attr_coin, response};
 use drop_helpers::pause::{assert_paused, is_paused, set_pause, unpause, PauseInfoResponse};
-use drop_staking_base::error::rewards_manager::ContractResult;
+use drop_staking_base::error::rewards_manager::{ContractError, ContractResult};
+use drop_staking_base::msg::core::ExecuteMsg as CoreExecuteMsg;
 use drop_staking_base::msg::rewards_manager::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RewardsHistoryResponseItem,
+};
+use drop_staking_base::state::rewards_manager::{
+    Config, HandlerConfig, RewardsHistoryItem, BASE_DENOM_BALANCE, CONFIG, DEFAULT_EPOCH_DURATION,
+    LEGACY_REWARDS_HANDLERS, PENDING_EXCHANGES, REWARDS_HANDLERS, REWARDS_HISTORY,
 };
-use drop_staking_base::state::rewards_manager::{HandlerConfig, REWARDS_HANDLERS};
 
-use drop_staking_base::msg::reward_handler::HandlerExecuteMsg;
+use drop_staking_base::msg::reward_handler::{
+    HandlerExecuteMsg, HandlerQueryMsg, SimulateExchangeResponse,
+};
 
 const CONTRACT_NAME: &str = concat!("crates.io:drop-staking__", env!("CARGO_PKG_NAME"));
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+pub const EXCHANGE_REPLY_ID: u64 = 1;
+const DEFAULT_HISTORY_LIMIT: u32 = 50;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> ContractResult<Response> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     let owner = deps.api.addr_validate(&msg.owner)?;
     cw_ownable::initialize_owner(deps.storage, deps.api, Some(owner.as_ref()))?;
 
+    let epoch_duration = msg.epoch_duration.unwrap_or(DEFAULT_EPOCH_DURATION);
+    if epoch_duration == 0 {
+        return Err(ContractError::InvalidEpochDuration {});
+    }
+    let core_contract = deps.api.addr_validate(&msg.core_contract)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            core_contract: core_contract.to_string(),
+            base_denom: msg.base_denom.clone(),
+            epoch_duration,
+        },
+    )?;
+
     Ok(response(
         "instantiate",
         CONTRACT_NAME,
-        [attr("owner", msg.owner)],
+        [
+            attr("owner", msg.owner),
+            attr("core_contract", core_contract),
+            attr("base_denom", msg.base_denom),
+            attr("epoch_duration", epoch_duration.to_string()),
+        ],
     ))
 }
 
@@ -38,20 +70,50 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Config {} => query_config(deps, env),
         QueryMsg::Handlers {} => query_handlers(deps, env),
         QueryMsg::PauseInfo {} => query_pause_info(deps),
+        QueryMsg::RewardsHistory {
+            start_after_epoch,
+            limit,
+        } => query_rewards_history(deps, start_after_epoch, limit),
     }
 }
 
 fn query_config(deps: Deps, _env: Env) -> StdResult<Binary> {
     let owner = cw_ownable::get_ownership(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
 
     to_json_binary(&ConfigResponse {
         owner: owner
             .owner
             .map(|addr| addr.into_string())
             .unwrap_or_default(),
+        core_contract: config.core_contract,
+        base_denom: config.base_denom,
+        epoch_duration: config.epoch_duration,
     })
 }
 
+fn query_rewards_history(
+    deps: Deps,
+    start_after_epoch: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let min = start_after_epoch.map(|epoch| Bound::inclusive((epoch + 1, String::new())));
+    let history = REWARDS_HISTORY
+        .range(deps.storage, min, None, Order::Ascending)
+        .take(limit.unwrap_or(DEFAULT_HISTORY_LIMIT) as usize)
+        .map(|item| {
+            item.map(|((epoch, denom), item)| RewardsHistoryResponseItem {
+                epoch,
+                denom,
+                amount_in: item.amount_in,
+                amount_out: item.amount_out,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_json_binary(&history)
+}
+
 fn query_pause_info(deps: Deps) -> StdResult<Binary> {
     if is_paused(deps.storage) {
         to_json_binary(&PauseInfoResponse::Paused {})
@@ -79,9 +141,16 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> ContractResult<Response> {
     match msg {
-        ExecuteMsg::UpdateConfig { owner } => exec_update_config(deps, info, owner),
+        ExecuteMsg::UpdateConfig {
+            owner,
+            core_contract,
+            base_denom,
+            epoch_duration,
+        } => exec_update_config(deps, info, owner, core_contract, base_denom, epoch_duration),
         ExecuteMsg::AddHandler { config } => exec_add_handler(deps, info, config),
-        ExecuteMsg::RemoveHandler { denom } => exec_remove_handler(deps, info, denom),
+        ExecuteMsg::RemoveHandler { denom, address } => {
+            exec_remove_handler(deps, info, denom, address)
+        }
         ExecuteMsg::ExchangeRewards {} => exec_exchange_rewards(deps, env, info),
         ExecuteMsg::Pause {} => exec_pause(deps, info),
         ExecuteMsg::Unpause {} => exec_unpause(deps, info),
@@ -116,9 +185,14 @@ fn exec_update_config(
     deps: DepsMut,
     info: MessageInfo,
     owner: Option<String>,
+    core_contract: Option<String>,
+    base_denom: Option<String>,
+    epoch_duration: Option<u64>,
 ) -> ContractResult<Response> {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
+    let mut config = CONFIG.load(deps.storage)?;
+
     let mut attrs: Vec<Attribute> = Vec::new();
     if let Some(owner) = owner {
         let owner = deps.api.addr_validate(&owner)?;
@@ -126,6 +200,27 @@ fn exec_update_config(
         attrs.push(attr("owner", owner))
     }
 
+    if let Some(core_contract) = core_contract {
+        let core_contract = deps.api.addr_validate(&core_contract)?;
+        config.core_contract = core_contract.to_string();
+        attrs.push(attr("core_contract", core_contract))
+    }
+
+    if let Some(base_denom) = base_denom {
+        config.base_denom = base_denom.clone();
+        attrs.push(attr("base_denom", base_denom))
+    }
+
+    if let Some(epoch_duration) = epoch_duration {
+        if epoch_duration == 0 {
+            return Err(ContractError::InvalidEpochDuration {});
+        }
+        config.epoch_duration = epoch_duration;
+        attrs.push(attr("epoch_duration", epoch_duration.to_string()))
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
     Ok(response("config_update", CONTRACT_NAME, attrs))
 }
 
@@ -136,7 +231,12 @@ fn exec_add_handler(
 ) -> ContractResult<Response> {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    REWARDS_HANDLERS.save(deps.storage, config.denom.clone(), &config)?;
+    let address = deps.api.addr_validate(&config.address)?;
+    REWARDS_HANDLERS.save(
+        deps.storage,
+        (config.denom.clone(), address.to_string()),
+        &config,
+    )?;
 
     Ok(response(
         "add_handler",
@@ -153,36 +253,91 @@ fn exec_remove_handler(
     deps: DepsMut,
     info: MessageInfo,
     denom: String,
+    address: Option<String>,
 ) -> ContractResult<Response> {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    REWARDS_HANDLERS.remove(deps.storage, denom.clone());
+    let mut attrs = vec![attr("denom", denom.clone())];
+    if let Some(address) = address {
+        REWARDS_HANDLERS.remove(deps.storage, (denom, address.clone()));
+        attrs.push(attr("address", address));
+    } else {
+        let addresses = REWARDS_HANDLERS
+            .prefix(denom.clone())
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for address in addresses {
+            REWARDS_HANDLERS.remove(deps.storage, (denom.clone(), address));
+        }
+    }
+
+    Ok(response("remove_handler", CONTRACT_NAME, attrs))
+}
 
-    Ok(response(
-        "remove_handler",
-        CONTRACT_NAME,
-        [attr("denom", denom)],
-    ))
+/// Picks the handler quoting the largest output for `balance`. Handlers that
+/// fail to quote are skipped.
+fn best_quoting_handler(
+    deps: Deps,
+    handlers: Vec<HandlerConfig>,
+    balance: &Coin,
+) -> Option<HandlerConfig> {
+    handlers
+        .into_iter()
+        .filter_map(|handler| {
+            let quote: SimulateExchangeResponse = deps
+                .querier
+                .query_wasm_smart(
+                    &handler.address,
+                    &HandlerQueryMsg::SimulateExchange {
+                        amount: balance.clone(),
+                    },
+                )
+                .ok()?;
+            Some((quote.amount, handler))
+        })
+        .max_by_key(|(amount, _)| *amount)
+        .map(|(_, handler)| handler)
 }
 
 fn exec_exchange_rewards(deps: DepsMut, env: Env, _info: MessageInfo) -> ContractResult<Response> {
     assert_paused(deps.storage)?;
 
+    let config = CONFIG.load(deps.storage)?;
+    let epoch = env.block.time.seconds() / config.epoch_duration;
     let balances = deps.querier.query_all_balances(env.contract.address)?;
 
-    let mut messages: Vec<CosmosMsg> = Vec::new();
+    let mut sub_msgs: Vec<SubMsg> = Vec::new();
+    let mut pending: Vec<Coin> = Vec::new();
     let mut attrs: Vec<Attribute> = Vec::new();
+    let mut base_balance = Uint128::zero();
 
     for balance in &balances {
         let denom = balance.denom.clone();
         let amount = balance.amount;
 
-        if REWARDS_HANDLERS.has(deps.storage, denom.clone()) {
-            let handler = REWARDS_HANDLERS.load(deps.storage, denom.clone())?;
+        // rewards already in the base denom need no exchange
+        if denom == config.base_denom {
+            base_balance = amount;
+            record_exchange(deps.storage, epoch, &denom, amount, amount)?;
+            continue;
+        }
+
+        let mut handlers = REWARDS_HANDLERS
+            .prefix(denom.clone())
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_key, value)| value))
+            .collect::<StdResult<Vec<_>>>()?;
+        handlers.retain(|handler| amount >= handler.min_rewards);
 
-            if amount < handler.min_rewards {
-                continue;
-            }
+        let handler = if handlers.len() > 1 {
+            best_quoting_handler(deps.as_ref(), handlers, balance)
+        } else {
+            handlers.pop()
+        };
+
+        if let Some(handler) = handler {
+            attrs.push(attr("denom", denom));
+            attrs.push(attr("handler", handler.address.clone()));
 
             let exchange_rewards_msg = CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: handler.address,
@@ -190,22 +345,154 @@ fn exec_exchange_rewards(deps: DepsMut, env: Env, _info: MessageInfo) -> Contrac
                 funds: vec![balance.clone()],
             });
 
-            messages.push(exchange_rewards_msg);
-            attrs.push(attr("denom", denom));
+            sub_msgs.push(SubMsg::reply_on_success(
+                exchange_rewards_msg,
+                EXCHANGE_REPLY_ID,
+            ));
+            pending.push(balance.clone());
         }
     }
 
+    let mut messages: Vec<CosmosMsg> = Vec::new();
+    if pending.is_empty() {
+        messages.extend(get_compound_msg(&config, base_balance)?);
+    } else {
+        // the proceeds are forwarded to the core once the last handler replies
+        PENDING_EXCHANGES.save(deps.storage, &pending)?;
+        BASE_DENOM_BALANCE.save(deps.storage, &base_balance)?;
+    }
+
     Ok(response(
         "exchange_rewards",
         CONTRACT_NAME,
         [attr("total_denoms", balances.len().to_string())],
     )
+    .add_submessages(sub_msgs)
     .add_messages(messages)
     .add_attributes(attrs))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> ContractResult<Response> {
+    match msg.id {
+        EXCHANGE_REPLY_ID => exchange_reply(deps, env),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+fn exchange_reply(deps: DepsMut, env: Env) -> ContractResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let epoch = env.block.time.seconds() / config.epoch_duration;
+
+    let mut pending = PENDING_EXCHANGES.load(deps.storage)?;
+    if pending.is_empty() {
+        return Err(StdError::generic_err("no pending exchanges").into());
+    }
+    let sent = pending.remove(0);
+    // handlers may send back the part they didn't swap
+    let returned = deps
+        .querier
+        .query_balance(&env.contract.address, sent.denom.clone())?
+        .amount;
+    let amount_in = coin(
+        sent.amount
+            .checked_sub(returned)
+            .map_err(StdError::from)?
+            .u128(),
+        sent.denom,
+    );
+
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address, config.base_denom.clone())?
+        .amount;
+    let amount_out = balance
+        .checked_sub(BASE_DENOM_BALANCE.load(deps.storage)?)
+        .map_err(StdError::from)?;
+    record_exchange(
+        deps.storage,
+        epoch,
+        &amount_in.denom,
+        amount_in.amount,
+        amount_out,
+    )?;
+
+    let attrs = vec![
+        attr_coin("amount_in", amount_in.amount, amount_in.denom),
+        attr_coin("amount_out", amount_out, config.base_denom.clone()),
+    ];
+
+    let mut messages: Vec<CosmosMsg> = Vec::new();
+    if pending.is_empty() {
+        PENDING_EXCHANGES.remove(deps.storage);
+        BASE_DENOM_BALANCE.remove(deps.storage);
+        messages.extend(get_compound_msg(&config, balance)?);
+    } else {
+        PENDING_EXCHANGES.save(deps.storage, &pending)?;
+        BASE_DENOM_BALANCE.save(deps.storage, &balance)?;
+    }
+
+    Ok(response("exchange_reply", CONTRACT_NAME, attrs).add_messages(messages))
+}
+
+fn record_exchange(
+    storage: &mut dyn Storage,
+    epoch: u64,
+    denom: &str,
+    amount_in: Uint128,
+    amount_out: Uint128,
+) -> StdResult<()> {
+    REWARDS_HISTORY.update(storage, (epoch, denom.to_string()), |item| {
+        let mut item: RewardsHistoryItem = item.unwrap_or_default();
+        item.amount_in += amount_in;
+        item.amount_out += amount_out;
+        StdResult::Ok(item)
+    })?;
+    Ok(())
+}
+
+fn get_compound_msg(config: &Config, amount: Uint128) -> StdResult<Option<CosmosMsg>> {
+    if amount.is_zero() {
+        return Ok(None);
+    }
+    Ok(Some(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.core_contract.clone(),
+        msg: to_json_binary(&CoreExecuteMsg::CompoundRewards {})?,
+        funds: vec![coin(amount.u128(), config.base_denom.clone())],
+    })))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> ContractResult<Response> {
     deps.api.debug("WASMDEBUG: migrate");
-    Ok(Response::default())
+    let epoch_duration = msg.epoch_duration.unwrap_or(DEFAULT_EPOCH_DURATION);
+    if epoch_duration == 0 {
+        return Err(ContractError::InvalidEpochDuration {});
+    }
+    let core_contract = deps.api.addr_validate(&msg.core_contract)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            core_contract: core_contract.to_string(),
+            base_denom: msg.base_denom.clone(),
+            epoch_duration,
+        },
+    )?;
+
+    let legacy_handlers = LEGACY_REWARDS_HANDLERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (denom, handler) in legacy_handlers {
+        LEGACY_REWARDS_HANDLERS.remove(deps.storage, denom.clone());
+        REWARDS_HANDLERS.save(deps.storage, (denom, handler.address.clone()), &handler)?;
+    }
+    Ok(response(
+        "migrate",
+        CONTRACT_NAME,
+        [
+            attr("core_contract", core_contract),
+            attr("base_denom", msg.base_denom),
+            attr("epoch_duration", epoch_duration.to_string()),
+        ],
+    ))
 }
@@ -1,18 +1,29 @@
-use crate::contract::instantiate;
+use crate::contract::{instantiate, migrate};
 
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 use cosmwasm_std::{
-    attr, coins, to_json_binary, Addr, Attribute, Coin, Empty, Event, Response, StdError, Uint128,
+    attr, coins, to_json_binary, Addr, Attribute, BankMsg, Coin, Empty, Event, Response, StdError,
+    Uint128,
 };
 use cw_multi_test::{custom_app, App, Contract, ContractWrapper, Executor};
 use drop_helpers::answer::{attr_coin, response};
 use drop_helpers::pause::PauseInfoResponse;
-use drop_staking_base::msg::reward_handler::HandlerExecuteMsg;
+use drop_staking_base::msg::core::ExecuteMsg as CoreExecuteMsg;
+use drop_staking_base::msg::reward_handler::{
+    HandlerExecuteMsg, HandlerQueryMsg, SimulateExchangeResponse,
+};
 use drop_staking_base::msg::rewards_manager::QueryMsg;
-use drop_staking_base::msg::rewards_manager::{ExecuteMsg, InstantiateMsg};
-use drop_staking_base::state::rewards_manager::HandlerConfig;
+use drop_staking_base::msg::rewards_manager::{
+    ExecuteMsg, InstantiateMsg, MigrateMsg, RewardsHistoryResponseItem,
+};
+use drop_staking_base::state::rewards_manager::{
+    Config, HandlerConfig, CONFIG, DEFAULT_EPOCH_DURATION, LEGACY_REWARDS_HANDLERS,
+    REWARDS_HANDLERS,
+};
 
 const OWNER_ADDR: &str = "owner_address";
+const CORE_ADDR: &str = "core_contract";
+const BASE_DENOM: &str = "uatom";
 
 const SENDER_ADDR: &str = "sender";
 
@@ -64,6 +75,100 @@ fn handler_contract() -> Box<dyn Contract<Empty>> {
     Box::new(contract)
 }
 
+fn quoting_handler_contract<const QUOTE: u128>() -> Box<dyn Contract<Empty>> {
+    let contract: ContractWrapper<
+        HandlerExecuteMsg,
+        Empty,
+        HandlerQueryMsg,
+        StdError,
+        StdError,
+        StdError,
+    > = ContractWrapper::new(
+        |_, _, info, msg: HandlerExecuteMsg| match msg {
+            HandlerExecuteMsg::Exchange {} => Ok(response(
+                "handler_contract_execute",
+                "handler_mock",
+                [
+                    attr("quote", QUOTE.to_string()),
+                    attr_coin(
+                        "received_funds",
+                        info.funds[0].amount.to_string(),
+                        info.funds[0].denom.clone(),
+                    ),
+                ],
+            )),
+        },
+        |_, _, _, _: Empty| Ok(Response::new()),
+        |_, _, msg: HandlerQueryMsg| match msg {
+            HandlerQueryMsg::SimulateExchange { .. } => to_json_binary(&SimulateExchangeResponse {
+                amount: Uint128::new(QUOTE),
+            }),
+        },
+    );
+    Box::new(contract)
+}
+
+// pays back twice the received amount in the base denom
+fn paying_handler_contract() -> Box<dyn Contract<Empty>> {
+    let contract: ContractWrapper<HandlerExecuteMsg, Empty, Empty, StdError, StdError, StdError> =
+        ContractWrapper::new(
+            |_, _, info, msg: HandlerExecuteMsg| match msg {
+                HandlerExecuteMsg::Exchange {} => Ok(Response::new().add_message(BankMsg::Send {
+                    to_address: info.sender.to_string(),
+                    amount: coins(info.funds[0].amount.u128() * 2, BASE_DENOM),
+                })),
+            },
+            |_, _, _, _: Empty| Ok(Response::new()),
+            |_, _, _: Empty| to_json_binary(&{}),
+        );
+    Box::new(contract)
+}
+
+// swaps 60 of the received amount at 2 and sends back the rest
+fn chunking_handler_contract() -> Box<dyn Contract<Empty>> {
+    let contract: ContractWrapper<HandlerExecuteMsg, Empty, Empty, StdError, StdError, StdError> =
+        ContractWrapper::new(
+            |_, _, info, msg: HandlerExecuteMsg| match msg {
+                HandlerExecuteMsg::Exchange {} => Ok(Response::new()
+                    .add_message(BankMsg::Send {
+                        to_address: info.sender.to_string(),
+                        amount: coins(120, BASE_DENOM),
+                    })
+                    .add_message(BankMsg::Send {
+                        to_address: info.sender.to_string(),
+                        amount: coins(
+                            info.funds[0].amount.u128() - 60,
+                            info.funds[0].denom.clone(),
+                        ),
+                    })),
+            },
+            |_, _, _, _: Empty| Ok(Response::new()),
+            |_, _, _: Empty| to_json_binary(&{}),
+        );
+    Box::new(contract)
+}
+
+fn core_contract() -> Box<dyn Contract<Empty>> {
+    let contract: ContractWrapper<CoreExecuteMsg, Empty, Empty, StdError, StdError, StdError> =
+        ContractWrapper::new(
+            |_, _, info, msg: CoreExecuteMsg| match msg {
+                CoreExecuteMsg::CompoundRewards {} => Ok(response(
+                    "compound_rewards",
+                    "core_mock",
+                    [attr_coin(
+                        "amount",
+                        info.funds[0].amount,
+                        info.funds[0].denom.clone(),
+                    )],
+                )),
+                _ => Err(StdError::generic_err("Wrong execution call")),
+            },
+            |_, _, _, _: Empty| Ok(Response::new()),
+            |_, _, _: Empty| to_json_binary(&{}),
+        );
+    Box::new(contract)
+}
+
 fn instantiate_handler_contract(app: &mut App) -> Addr {
     instantiate_contract(app, handler_contract, "drop handler contract".to_string())
 }
@@ -73,7 +178,8 @@ fn rewards_manager_contract() -> Box<dyn Contract<Empty>> {
         crate::contract::execute,
         crate::contract::instantiate,
         crate::contract::query,
-    );
+    )
+    .with_reply(crate::contract::reply);
     Box::new(contract)
 }
 
@@ -115,6 +221,9 @@ fn test_initialization() {
     let mut deps = mock_dependencies();
     let msg = InstantiateMsg {
         owner: OWNER_ADDR.to_string(),
+        core_contract: CORE_ADDR.to_string(),
+        base_denom: BASE_DENOM.to_string(),
+        epoch_duration: None,
     };
 
     let info = mock_info(OWNER_ADDR, &[]);
@@ -124,10 +233,12 @@ fn test_initialization() {
         res.events,
         vec![
             Event::new("crates.io:drop-staking__drop-rewards-manager-instantiate".to_string())
-                .add_attributes(vec![Attribute::new(
-                    "owner".to_string(),
-                    OWNER_ADDR.to_string()
-                ),])
+                .add_attributes(vec![
+                    Attribute::new("owner".to_string(), OWNER_ADDR.to_string()),
+                    Attribute::new("core_contract".to_string(), CORE_ADDR.to_string()),
+                    Attribute::new("base_denom".to_string(), BASE_DENOM.to_string()),
+                    Attribute::new("epoch_duration".to_string(), "86400".to_string()),
+                ])
         ]
     );
 }
@@ -143,6 +254,9 @@ fn test_config_query() {
         rewards_manager_code_id,
         InstantiateMsg {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
         },
     );
 
@@ -155,6 +269,9 @@ fn test_config_query() {
         config,
         drop_staking_base::msg::rewards_manager::ConfigResponse {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: 86400,
         }
     );
 }
@@ -170,6 +287,9 @@ fn test_handlers_query() {
         rewards_manager_code_id,
         InstantiateMsg {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
         },
     );
 
@@ -192,6 +312,9 @@ fn test_pause_query() {
         rewards_manager_code_id,
         InstantiateMsg {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
         },
     );
 
@@ -214,6 +337,9 @@ fn test_pause_handler_not_owner_error() {
         rewards_manager_code_id,
         InstantiateMsg {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
         },
     );
 
@@ -245,6 +371,9 @@ fn test_pause_handler() {
         rewards_manager_code_id,
         InstantiateMsg {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
         },
     );
 
@@ -299,6 +428,9 @@ fn test_paused_error() {
         rewards_manager_code_id,
         InstantiateMsg {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
         },
     );
 
@@ -344,6 +476,9 @@ fn test_add_remove_handler() {
         rewards_manager_code_id,
         InstantiateMsg {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
         },
     );
 
@@ -405,6 +540,7 @@ fn test_add_remove_handler() {
             rewards_manager_contract.clone(),
             &ExecuteMsg::RemoveHandler {
                 denom: handler_config.denom.clone(),
+                address: None,
             },
             &[],
         )
@@ -450,6 +586,9 @@ fn test_handler_call() {
         rewards_manager_code_id,
         InstantiateMsg {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
         },
     );
 
@@ -515,6 +654,9 @@ fn test_two_handlers_call() {
         rewards_manager_code_id,
         InstantiateMsg {
             owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
         },
     );
 
@@ -578,6 +720,7 @@ fn test_two_handlers_call() {
     let ty = res.events[4].ty.clone();
 
     assert_eq!(ty, "wasm-handler_mock-handler_contract_execute".to_string());
+    assert_eq!(res.events[5].ty, "reply".to_string());
 
     let ueth_attrs = res.events[4].attributes[1..].to_vec();
 
@@ -589,7 +732,8 @@ fn test_two_handlers_call() {
         ]
     );
 
-    let untrn_attrs = res.events[6].attributes[1..].to_vec();
+    // the reply to the first handler call comes in between
+    let untrn_attrs = res.events[8].attributes[1..].to_vec();
 
     assert_eq!(
         untrn_attrs,
@@ -599,3 +743,307 @@ fn test_two_handlers_call() {
         ]
     );
 }
+
+#[test]
+fn test_best_quoting_handler_call() {
+    let mut app = mock_app();
+
+    let sender_address = Addr::unchecked(SENDER_ADDR);
+
+    let low_quote_handler = instantiate_contract(
+        &mut app,
+        quoting_handler_contract::<90>,
+        "low quote handler".to_string(),
+    );
+    let high_quote_handler = instantiate_contract(
+        &mut app,
+        quoting_handler_contract::<110>,
+        "high quote handler".to_string(),
+    );
+
+    let rewards_manager_code_id = app.store_code(rewards_manager_contract());
+
+    let rewards_manager_contract = instantiate_rewards_manager_contract(
+        &mut app,
+        rewards_manager_code_id,
+        InstantiateMsg {
+            owner: OWNER_ADDR.to_string(),
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
+        },
+    );
+
+    let amount = coins(100, "ueth");
+    let _ = app
+        .send_tokens(sender_address, rewards_manager_contract.clone(), &amount)
+        .unwrap();
+
+    for handler in [&low_quote_handler, &high_quote_handler] {
+        app.execute_contract(
+            Addr::unchecked(OWNER_ADDR),
+            rewards_manager_contract.clone(),
+            &ExecuteMsg::AddHandler {
+                config: HandlerConfig {
+                    address: handler.to_string(),
+                    denom: "ueth".to_string(),
+                    min_rewards: Uint128::zero(),
+                },
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    let handlers: Vec<HandlerConfig> = app
+        .wrap()
+        .query_wasm_smart(rewards_manager_contract.clone(), &QueryMsg::Handlers {})
+        .unwrap();
+    assert_eq!(handlers.len(), 2);
+
+    let res = app
+        .execute_contract(
+            Addr::unchecked(OWNER_ADDR),
+            rewards_manager_contract.clone(),
+            &ExecuteMsg::ExchangeRewards {},
+            &[],
+        )
+        .unwrap();
+
+    let handler_events: Vec<_> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm-handler_mock-handler_contract_execute")
+        .collect();
+    assert_eq!(handler_events.len(), 1);
+    assert_eq!(
+        handler_events[0].attributes[0],
+        Attribute::new("_contract_address", high_quote_handler.to_string())
+    );
+    assert_eq!(
+        handler_events[0].attributes[1..].to_vec(),
+        vec![
+            Attribute::new("quote".to_string(), "110".to_string()),
+            Attribute::new("received_funds".to_string(), "100ueth".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_rewards_history() {
+    let mut app = mock_app();
+
+    let sender_address = Addr::unchecked(SENDER_ADDR);
+
+    let handler_contract = instantiate_contract(
+        &mut app,
+        paying_handler_contract,
+        "paying handler contract".to_string(),
+    );
+    let core_contract = instantiate_contract(&mut app, core_contract, "core".to_string());
+
+    let rewards_manager_code_id = app.store_code(rewards_manager_contract());
+
+    let rewards_manager_contract = instantiate_rewards_manager_contract(
+        &mut app,
+        rewards_manager_code_id,
+        InstantiateMsg {
+            owner: OWNER_ADDR.to_string(),
+            core_contract: core_contract.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
+        },
+    );
+
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &handler_contract, coins(1000, BASE_DENOM))
+    })
+    .unwrap();
+    app.send_tokens(
+        sender_address,
+        rewards_manager_contract.clone(),
+        &coins(100, "ueth"),
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(OWNER_ADDR),
+        rewards_manager_contract.clone(),
+        &ExecuteMsg::AddHandler {
+            config: HandlerConfig {
+                address: handler_contract.to_string(),
+                denom: "ueth".to_string(),
+                min_rewards: Uint128::zero(),
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(OWNER_ADDR),
+        rewards_manager_contract.clone(),
+        &ExecuteMsg::ExchangeRewards {},
+        &[],
+    )
+    .unwrap();
+
+    let history: Vec<RewardsHistoryResponseItem> = app
+        .wrap()
+        .query_wasm_smart(
+            rewards_manager_contract.clone(),
+            &QueryMsg::RewardsHistory {
+                start_after_epoch: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        history,
+        vec![RewardsHistoryResponseItem {
+            epoch: app.block_info().time.seconds() / 86400,
+            denom: "ueth".to_string(),
+            amount_in: Uint128::new(100),
+            amount_out: Uint128::new(200),
+        }]
+    );
+
+    let core_balance = app.wrap().query_balance(core_contract, BASE_DENOM).unwrap();
+    assert_eq!(core_balance.amount, Uint128::new(200));
+}
+
+#[test]
+fn test_rewards_history_counts_swapped_amount_only() {
+    let mut app = mock_app();
+
+    let sender_address = Addr::unchecked(SENDER_ADDR);
+
+    let handler_contract = instantiate_contract(
+        &mut app,
+        chunking_handler_contract,
+        "chunking handler contract".to_string(),
+    );
+    let core_contract = instantiate_contract(&mut app, core_contract, "core".to_string());
+
+    let rewards_manager_code_id = app.store_code(rewards_manager_contract());
+
+    let rewards_manager_contract = instantiate_rewards_manager_contract(
+        &mut app,
+        rewards_manager_code_id,
+        InstantiateMsg {
+            owner: OWNER_ADDR.to_string(),
+            core_contract: core_contract.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
+        },
+    );
+
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &handler_contract, coins(1000, BASE_DENOM))
+    })
+    .unwrap();
+    app.send_tokens(
+        sender_address,
+        rewards_manager_contract.clone(),
+        &coins(100, "ueth"),
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(OWNER_ADDR),
+        rewards_manager_contract.clone(),
+        &ExecuteMsg::AddHandler {
+            config: HandlerConfig {
+                address: handler_contract.to_string(),
+                denom: "ueth".to_string(),
+                min_rewards: Uint128::zero(),
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(OWNER_ADDR),
+        rewards_manager_contract.clone(),
+        &ExecuteMsg::ExchangeRewards {},
+        &[],
+    )
+    .unwrap();
+
+    let history: Vec<RewardsHistoryResponseItem> = app
+        .wrap()
+        .query_wasm_smart(
+            rewards_manager_contract.clone(),
+            &QueryMsg::RewardsHistory {
+                start_after_epoch: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        history,
+        vec![RewardsHistoryResponseItem {
+            epoch: app.block_info().time.seconds() / 86400,
+            denom: "ueth".to_string(),
+            amount_in: Uint128::new(60),
+            amount_out: Uint128::new(120),
+        }]
+    );
+
+    let core_balance = app.wrap().query_balance(core_contract, BASE_DENOM).unwrap();
+    assert_eq!(core_balance.amount, Uint128::new(120));
+    // the unswapped part waits for the next exchange
+    let balance = app
+        .wrap()
+        .query_balance(rewards_manager_contract, "ueth")
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(40));
+}
+
+#[test]
+fn migrate_saves_config_and_moves_legacy_handlers() {
+    let mut deps = mock_dependencies();
+    let handler = HandlerConfig {
+        address: "handler".to_string(),
+        denom: "ueth".to_string(),
+        min_rewards: Uint128::one(),
+    };
+    LEGACY_REWARDS_HANDLERS
+        .save(deps.as_mut().storage, "ueth".to_string(), &handler)
+        .unwrap();
+
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        CONFIG.load(deps.as_ref().storage).unwrap(),
+        Config {
+            core_contract: CORE_ADDR.to_string(),
+            base_denom: BASE_DENOM.to_string(),
+            epoch_duration: DEFAULT_EPOCH_DURATION,
+        }
+    );
+    assert!(LEGACY_REWARDS_HANDLERS.is_empty(deps.as_ref().storage));
+    assert_eq!(
+        REWARDS_HANDLERS
+            .load(
+                deps.as_ref().storage,
+                ("ueth".to_string(), "handler".to_string())
+            )
+            .unwrap(),
+        handler
+    );
+}
@@ -0,0 +1,393 @@
+use std::collections::HashMap;
+
+use cosmwasm_std::{
+    attr, ensure, ensure_eq, entry_point, from_json, to_json_binary, Attribute, BankMsg, Binary,
+    Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Uint128,
+    WasmMsg,
+};
+use cw2::set_contract_version;
+use cw721::{Cw721ReceiveMsg, NftInfoResponse};
+use cw_storage_plus::Bound;
+use drop_helpers::answer::{attr_coin, response};
+use drop_staking_base::{
+    msg::{
+        core::QueryMsg as CoreQueryMsg,
+        voucher_marketplace::{
+            ExecuteMsg, InstantiateMsg, ListingResponse, ListingsResponse, QueryMsg, ReceiveNftMsg,
+        },
+        withdrawal_voucher::{
+            ExecuteMsg as VoucherExecuteMsg, Extension, QueryMsg as VoucherQueryMsg,
+        },
+    },
+    state::{
+        core::UnbondBatch,
+        voucher_marketplace::{listings_map, Config, Listing, CONFIG},
+    },
+};
+
+use crate::error::{ContractError, ContractResult};
+
+const CONTRACT_NAME: &str = concat!("crates.io:drop-staking__", env!("CARGO_PKG_NAME"));
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub const PAGINATION_DEFAULT_LIMIT: u32 = 100;
+// every scanned listing may cost a core query
+pub const MAX_SCANNED_LISTINGS: usize = 100;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> ContractResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    cw_ownable::initialize_owner(deps.storage, deps.api, Some(msg.owner.as_ref()))?;
+
+    let attrs: Vec<Attribute> = vec![
+        attr("owner", &msg.owner),
+        attr("core_contract", &msg.core_contract),
+        attr("voucher_contract", &msg.voucher_contract),
+        attr("base_denom", &msg.base_denom),
+        attr("ld_denom", &msg.ld_denom),
+    ];
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            core_contract: deps.api.addr_validate(&msg.core_contract)?.to_string(),
+            voucher_contract: deps.api.addr_validate(&msg.voucher_contract)?.to_string(),
+            base_denom: msg.base_denom,
+            ld_denom: msg.ld_denom,
+        },
+    )?;
+    Ok(response("instantiate", CONTRACT_NAME, attrs))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> ContractResult<Binary> {
+    Ok(match msg {
+        QueryMsg::Ownership {} => to_json_binary(&cw_ownable::get_ownership(deps.storage)?)?,
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?)?,
+        QueryMsg::Listing { token_id } => {
+            let listing = listings_map()
+                .may_load(deps.storage, token_id.to_string())?
+                .ok_or(ContractError::ListingNotFound { token_id })?;
+            let mut valuation = Valuation::new(CONFIG.load(deps.storage)?);
+            to_json_binary(&valuation.listing_response(deps, listing)?)?
+        }
+        QueryMsg::Listings {
+            batch_id,
+            min_discount,
+            page_key,
+            limit,
+        } => query_listings(deps, batch_id, min_discount, page_key, limit)?,
+    })
+}
+
+// returns up to `limit` listings matching the filters along with the next page key,
+// the page ends early once MAX_SCANNED_LISTINGS rows are scanned
+fn query_listings(
+    deps: Deps,
+    batch_id: Option<u128>,
+    min_discount: Option<Decimal>,
+    page_key: Option<String>,
+    limit: Option<u32>,
+) -> ContractResult<Binary> {
+    let listings = listings_map();
+    let start = page_key.map(Bound::inclusive);
+    let iter = match batch_id {
+        Some(batch_id) => listings.idx.batch_id.prefix(batch_id).range(
+            deps.storage,
+            start,
+            None,
+            Order::Ascending,
+        ),
+        None => listings.range(deps.storage, start, None, Order::Ascending),
+    };
+    let limit = limit.unwrap_or(PAGINATION_DEFAULT_LIMIT) as usize;
+    let mut valuation = Valuation::new(CONFIG.load(deps.storage)?);
+    let mut result = vec![];
+    let mut next_page_key = None;
+    for (scanned, item) in iter.enumerate() {
+        let (token_id, listing) = item?;
+        if result.len() == limit || scanned == MAX_SCANNED_LISTINGS {
+            next_page_key = Some(token_id);
+            break;
+        }
+        let listing_response = valuation.listing_response(deps, listing)?;
+        if min_discount.map_or(true, |min_discount| {
+            listing_response.discount >= min_discount
+        }) {
+            result.push(listing_response);
+        }
+    }
+    Ok(to_json_binary(&ListingsResponse {
+        listings: result,
+        next_page_key,
+    })?)
+}
+
+// caches core queries while the order book is being evaluated
+struct Valuation {
+    config: Config,
+    exchange_rate: Option<Decimal>,
+    batches: HashMap<u128, UnbondBatch>,
+}
+
+impl Valuation {
+    fn new(config: Config) -> Self {
+        Self {
+            config,
+            exchange_rate: None,
+            batches: HashMap::new(),
+        }
+    }
+
+    fn listing_response(&mut self, deps: Deps, listing: Listing) -> StdResult<ListingResponse> {
+        if !self.batches.contains_key(&listing.batch_id) {
+            let batch: UnbondBatch = deps.querier.query_wasm_smart(
+                &self.config.core_contract,
+                &CoreQueryMsg::UnbondBatch {
+                    batch_id: listing.batch_id.into(),
+                },
+            )?;
+            self.batches.insert(listing.batch_id, batch);
+        }
+        let expected_payout = match self.batches[&listing.batch_id].slashing_effect {
+            Some(slashing_effect) => Uint128::min(
+                slashing_effect * listing.expected_amount,
+                listing.expected_amount,
+            ),
+            None => listing.expected_amount,
+        };
+        let price_in_base_denom = if listing.price.denom == self.config.ld_denom {
+            let exchange_rate = match self.exchange_rate {
+                Some(exchange_rate) => exchange_rate,
+                None => {
+                    let exchange_rate: Decimal = deps.querier.query_wasm_smart(
+                        &self.config.core_contract,
+                        &CoreQueryMsg::ExchangeRate {},
+                    )?;
+                    self.exchange_rate = Some(exchange_rate);
+                    exchange_rate
+                }
+            };
+            exchange_rate * listing.price.amount
+        } else {
+            listing.price.amount
+        };
+        let discount = if expected_payout.is_zero() || price_in_base_denom >= expected_payout {
+            Decimal::zero()
+        } else {
+            Decimal::one() - Decimal::from_ratio(price_in_base_denom, expected_payout)
+        };
+        Ok(ListingResponse {
+            listing,
+            expected_payout,
+            price_in_base_denom,
+            discount,
+        })
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> ContractResult<Response> {
+    match msg {
+        ExecuteMsg::UpdateOwnership(action) => {
+            cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
+            Ok(response::<(&str, &str), _>(
+                "execute-update-ownership",
+                CONTRACT_NAME,
+                [],
+            ))
+        }
+        ExecuteMsg::UpdateConfig {
+            core_contract,
+            voucher_contract,
+        } => execute_update_config(deps, info, core_contract, voucher_contract),
+        ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender,
+            token_id,
+            msg: raw_msg,
+        }) => {
+            let msg: ReceiveNftMsg = from_json(raw_msg)?;
+            match msg {
+                ReceiveNftMsg::List { price } => {
+                    execute_list(deps, env, info, sender, token_id, price)
+                }
+            }
+        }
+        ExecuteMsg::Buy { token_id, receiver } => execute_buy(deps, info, token_id, receiver),
+        ExecuteMsg::Cancel { token_id } => execute_cancel(deps, info, token_id),
+    }
+}
+
+fn execute_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    core_contract: Option<String>,
+    voucher_contract: Option<String>,
+) -> ContractResult<Response> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut attrs: Vec<Attribute> = Vec::new();
+    if let Some(core_contract) = core_contract {
+        config.core_contract = deps.api.addr_validate(&core_contract)?.to_string();
+        attrs.push(attr("core_contract", core_contract));
+    }
+    if let Some(voucher_contract) = voucher_contract {
+        config.voucher_contract = deps.api.addr_validate(&voucher_contract)?.to_string();
+        attrs.push(attr("voucher_contract", voucher_contract));
+    }
+    CONFIG.save(deps.storage, &config)?;
+    Ok(response("execute-update_config", CONTRACT_NAME, attrs))
+}
+
+fn execute_list(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    seller: String,
+    token_id: String,
+    price: Coin,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_eq!(
+        info.sender,
+        config.voucher_contract,
+        ContractError::Unauthorized {}
+    );
+    ensure!(
+        price.denom == config.base_denom || price.denom == config.ld_denom,
+        ContractError::InvalidPriceDenom { denom: price.denom }
+    );
+    ensure!(!price.amount.is_zero(), ContractError::ZeroPrice {});
+
+    let voucher: NftInfoResponse<Extension> = deps.querier.query_wasm_smart(
+        &config.voucher_contract,
+        &VoucherQueryMsg::NftInfo {
+            token_id: token_id.to_string(),
+        },
+    )?;
+    let metadata = voucher
+        .extension
+        .ok_or_else(|| ContractError::InvalidVoucher {
+            reason: "extension is not set".to_string(),
+        })?;
+    let batch_id =
+        metadata
+            .batch_id
+            .parse::<u128>()
+            .map_err(|_| ContractError::InvalidVoucher {
+                reason: "invalid batch_id".to_string(),
+            })?;
+    ensure!(
+        !metadata.expected_amount.is_zero(),
+        ContractError::InvalidVoucher {
+            reason: "expected_amount is zero".to_string(),
+        }
+    );
+
+    let attrs = vec![
+        attr("token_id", &token_id),
+        attr("seller", &seller),
+        attr("batch_id", batch_id.to_string()),
+        attr("expected_amount", metadata.expected_amount),
+        attr_coin("price", price.amount, &price.denom),
+    ];
+    listings_map().save(
+        deps.storage,
+        token_id.to_string(),
+        &Listing {
+            token_id,
+            seller,
+            batch_id,
+            expected_amount: metadata.expected_amount,
+            price,
+            listed_at: env.block.time.seconds(),
+        },
+    )?;
+    Ok(response("execute-list", CONTRACT_NAME, attrs))
+}
+
+fn execute_buy(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+    receiver: Option<String>,
+) -> ContractResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let listing = listings_map()
+        .may_load(deps.storage, token_id.to_string())?
+        .ok_or_else(|| ContractError::ListingNotFound {
+            token_id: token_id.to_string(),
+        })?;
+    let received = cw_utils::must_pay(&info, &listing.price.denom)?;
+    ensure_eq!(
+        received,
+        listing.price.amount,
+        ContractError::InvalidPayment {
+            expected: listing.price,
+            received,
+        }
+    );
+    let recipient = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?.to_string(),
+        None => info.sender.to_string(),
+    };
+    listings_map().remove(deps.storage, token_id.to_string())?;
+
+    let attrs = vec![
+        attr("token_id", &token_id),
+        attr("seller", &listing.seller),
+        attr("buyer", info.sender),
+        attr("recipient", &recipient),
+        attr_coin("price", listing.price.amount, &listing.price.denom),
+    ];
+    Ok(
+        response("execute-buy", CONTRACT_NAME, attrs).add_messages(vec![
+            get_transfer_voucher_msg(&config, recipient, token_id)?,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: listing.seller,
+                amount: vec![listing.price],
+            }),
+        ]),
+    )
+}
+
+fn execute_cancel(deps: DepsMut, info: MessageInfo, token_id: String) -> ContractResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let listing = listings_map()
+        .may_load(deps.storage, token_id.to_string())?
+        .ok_or_else(|| ContractError::ListingNotFound {
+            token_id: token_id.to_string(),
+        })?;
+    ensure_eq!(info.sender, listing.seller, ContractError::Unauthorized {});
+    listings_map().remove(deps.storage, token_id.to_string())?;
+    Ok(response(
+        "execute-cancel",
+        CONTRACT_NAME,
+        [attr("token_id", &token_id), attr("seller", &listing.seller)],
+    )
+    .add_message(get_transfer_voucher_msg(&config, listing.seller, token_id)?))
+}
+
+fn get_transfer_voucher_msg(
+    config: &Config,
+    recipient: String,
+    token_id: String,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.voucher_contract.to_string(),
+        msg: to_json_binary(&VoucherExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        })?,
+        funds: vec![],
+    }))
+}
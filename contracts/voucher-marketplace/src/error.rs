@@ -0,0 +1,35 @@
+use cosmwasm_std::{Coin, StdError, Uint128};
+use cw_ownable::OwnershipError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    OwnershipError(#[from] OwnershipError),
+
+    #[error("{0}")]
+    PaymentError(#[from] cw_utils::PaymentError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Invalid voucher: {reason}")]
+    InvalidVoucher { reason: String },
+
+    #[error("Price denom {denom} is not accepted")]
+    InvalidPriceDenom { denom: String },
+
+    #[error("Price can't be zero")]
+    ZeroPrice {},
+
+    #[error("Listing for voucher {token_id} is not found")]
+    ListingNotFound { token_id: String },
+
+    #[error("Listing price is {expected}, got {received}")]
+    InvalidPayment { expected: Coin, received: Uint128 },
+}
+
+pub type ContractResult<T> = Result<T, ContractError>;
@@ -0,0 +1,305 @@
+use cosmwasm_std::{
+    from_json,
+    testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier},
+    to_json_binary, BankMsg, Coin, ContractResult, CosmosMsg, Decimal, MemoryStorage, OwnedDeps,
+    Response, SystemResult, Uint128, WasmMsg, WasmQuery,
+};
+use cw721::{Cw721ReceiveMsg, NftInfoResponse};
+use drop_staking_base::{
+    msg::{
+        core::QueryMsg as CoreQueryMsg,
+        voucher_marketplace::{
+            ExecuteMsg, InstantiateMsg, ListingsResponse, QueryMsg, ReceiveNftMsg,
+        },
+        withdrawal_voucher::{
+            ExecuteMsg as VoucherExecuteMsg, Extension, QueryMsg as VoucherQueryMsg,
+        },
+    },
+    state::{
+        core::{UnbondBatch, UnbondBatchStatus},
+        withdrawal_voucher::Metadata,
+    },
+};
+
+use crate::{
+    contract::{execute, instantiate, query},
+    error::ContractError,
+};
+
+fn setup() -> OwnedDeps<MemoryStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    deps.querier.update_wasm(|request| {
+        let WasmQuery::Smart { contract_addr, msg } = request else {
+            unimplemented!()
+        };
+        let reply = match contract_addr.as_str() {
+            "voucher_contract" => match from_json::<VoucherQueryMsg>(msg).unwrap() {
+                VoucherQueryMsg::NftInfo { token_id } => {
+                    to_json_binary(&NftInfoResponse::<Extension> {
+                        token_uri: None,
+                        extension: Some(Metadata {
+                            name: token_id,
+                            batch_id: "0".to_string(),
+                            amount: Uint128::new(100),
+                            expected_amount: Uint128::new(1000),
+                            ..Default::default()
+                        }),
+                    })
+                }
+                _ => unimplemented!(),
+            },
+            "core_contract" => match from_json::<CoreQueryMsg>(msg).unwrap() {
+                CoreQueryMsg::UnbondBatch { .. } => to_json_binary(&UnbondBatch {
+                    total_amount: Uint128::new(100),
+                    expected_amount: Uint128::new(1000),
+                    expected_release: 0,
+                    unbond_items: vec![],
+                    status: UnbondBatchStatus::Withdrawn,
+                    slashing_effect: Some(Decimal::percent(90)),
+                    unbonded_amount: Some(Uint128::new(900)),
+                    withdrawed_amount: None,
+                    created: 0,
+                }),
+                CoreQueryMsg::ExchangeRate {} => to_json_binary(&Decimal::percent(150)),
+                _ => unimplemented!(),
+            },
+            _ => unimplemented!(),
+        };
+        SystemResult::Ok(ContractResult::from(reply))
+    });
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("admin", &[]),
+        InstantiateMsg {
+            owner: "owner".to_string(),
+            core_contract: "core_contract".to_string(),
+            voucher_contract: "voucher_contract".to_string(),
+            base_denom: "base_denom".to_string(),
+            ld_denom: "ld_denom".to_string(),
+        },
+    )
+    .unwrap();
+    deps
+}
+
+fn list(
+    deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+    token_id: &str,
+    price: Coin,
+) -> Result<Response, ContractError> {
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("voucher_contract", &[]),
+        ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: "seller".to_string(),
+            token_id: token_id.to_string(),
+            msg: to_json_binary(&ReceiveNftMsg::List { price }).unwrap(),
+        }),
+    )
+}
+
+#[test]
+fn list_invalid_price() {
+    let mut deps = setup();
+    let err = list(&mut deps, "1", Coin::new(100, "other_denom")).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidPriceDenom {
+            denom: "other_denom".to_string()
+        }
+    );
+    let err = list(&mut deps, "1", Coin::new(0, "base_denom")).unwrap_err();
+    assert_eq!(err, ContractError::ZeroPrice {});
+}
+
+#[test]
+fn listings_discount() {
+    let mut deps = setup();
+    list(&mut deps, "1", Coin::new(810, "base_denom")).unwrap();
+    list(&mut deps, "2", Coin::new(500, "ld_denom")).unwrap();
+
+    let listings: ListingsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Listings {
+                batch_id: Some(0),
+                min_discount: None,
+                page_key: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(listings.listings.len(), 2);
+    assert_eq!(listings.listings[0].expected_payout, Uint128::new(900));
+    assert_eq!(listings.listings[0].discount, Decimal::percent(10));
+    assert_eq!(listings.listings[1].price_in_base_denom, Uint128::new(750));
+    assert_eq!(listings.next_page_key, None);
+
+    let listings: ListingsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Listings {
+                batch_id: None,
+                min_discount: Some(Decimal::percent(15)),
+                page_key: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(listings.listings.len(), 1);
+    assert_eq!(listings.listings[0].listing.token_id, "2");
+
+    let listings: ListingsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Listings {
+                batch_id: Some(1),
+                min_discount: None,
+                page_key: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(listings.listings.is_empty());
+}
+
+#[test]
+fn listings_pagination() {
+    let mut deps = setup();
+    for token_id in ["1", "2", "3"] {
+        list(&mut deps, token_id, Coin::new(810, "base_denom")).unwrap();
+    }
+
+    let listings: ListingsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Listings {
+                batch_id: None,
+                min_discount: None,
+                page_key: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(listings.listings.len(), 2);
+    assert_eq!(listings.next_page_key, Some("3".to_string()));
+
+    let listings: ListingsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Listings {
+                batch_id: None,
+                min_discount: None,
+                page_key: listings.next_page_key,
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(listings.listings.len(), 1);
+    assert_eq!(listings.listings[0].listing.token_id, "3");
+    assert_eq!(listings.next_page_key, None);
+}
+
+#[test]
+fn buy() {
+    let mut deps = setup();
+    list(&mut deps, "1", Coin::new(810, "base_denom")).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("buyer", &[Coin::new(800, "base_denom")]),
+        ExecuteMsg::Buy {
+            token_id: "1".to_string(),
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidPayment {
+            expected: Coin::new(810, "base_denom"),
+            received: Uint128::new(800),
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("buyer", &[Coin::new(810, "base_denom")]),
+        ExecuteMsg::Buy {
+            token_id: "1".to_string(),
+            receiver: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|m| m.msg)
+            .collect::<Vec<CosmosMsg>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "voucher_contract".to_string(),
+                msg: to_json_binary(&VoucherExecuteMsg::TransferNft {
+                    recipient: "buyer".to_string(),
+                    token_id: "1".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "seller".to_string(),
+                amount: vec![Coin::new(810, "base_denom")],
+            }),
+        ]
+    );
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Listing {
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ListingNotFound {
+            token_id: "1".to_string()
+        }
+    );
+}
+
+#[test]
+fn cancel_unauthorized() {
+    let mut deps = setup();
+    list(&mut deps, "1", Coin::new(810, "base_denom")).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        ExecuteMsg::Cancel {
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
@@ -1,21 +1,32 @@
 use cosmwasm_std::{
-    attr, ensure_eq, entry_point, from_json, to_json_binary, Attribute, BankMsg, Binary, Coin,
-    CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+    attr, ensure, ensure_eq, entry_point, from_json, to_json_binary, Attribute, BankMsg, Binary,
+    Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Order, Reply, Response, StdResult,
+    Storage, SubMsg, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
-use cw721::NftInfoResponse;
+use cw721::{NftInfoResponse, OwnerOfResponse};
+use cw_storage_plus::Bound;
 use drop_helpers::{
     answer::response,
     pause::{assert_paused, is_paused, set_pause, unpause, PauseInfoResponse},
 };
 use drop_staking_base::{
     msg::{
-        withdrawal_manager::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveNftMsg},
+        core::LifecycleHookMsg,
+        withdrawal_manager::{
+            BatchFundingResponse, BatchReconciliationResponse, ExecuteMsg, InstantiateMsg,
+            MigrateMsg, QueryMsg, ReceiveNftMsg,
+        },
         withdrawal_voucher::Extension,
     },
     state::{
-        core::{UnbondBatch, UnbondBatchStatus},
-        withdrawal_manager::{Config, Cw721ReceiveMsg, CONFIG},
+        core::{Subscriber, UnbondBatch, UnbondBatchStatus},
+        withdrawal_manager::{
+            ClaimedVoucher, Config, Cw721ReceiveMsg, EmergencySettlement, ACCOUNTED_BALANCE,
+            BATCH_PAID_AMOUNTS, BATCH_RECEIVED_AMOUNTS, CLAIMED_VOUCHERS, CONFIG,
+            EMERGENCY_SETTLEMENTS, MIGRATION_CURSOR, NEXT_FUNDED_BATCH_ID, SKIPPED_BATCHES,
+            UNALLOCATED_FUNDS,
+        },
     },
 };
 use neutron_sdk::bindings::{msg::NeutronMsg, query::NeutronQuery};
@@ -24,6 +35,10 @@ use crate::error::{ContractError, ContractResult};
 const CONTRACT_NAME: &str = concat!("crates.io:drop-staking__", env!("CARGO_PKG_NAME"));
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+pub const PAGINATION_DEFAULT_LIMIT: u32 = 100;
+pub const LIFECYCLE_HOOK_REPLY_ID: u64 = 1;
+pub const MIGRATION_DEFAULT_LIMIT: u32 = 100;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -55,13 +70,93 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<NeutronQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<NeutronQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::ClaimedVouchers { start_after, limit } => {
+            query_claimed_vouchers(deps, start_after, limit)
+        }
+        QueryMsg::BatchReconciliation { batch_id } => query_batch_reconciliation(deps, batch_id),
+        QueryMsg::EmergencySettlement { batch_id } => {
+            to_json_binary(&EMERGENCY_SETTLEMENTS.may_load(deps.storage, batch_id)?)
+        }
+        QueryMsg::BatchFunding { batch_id } => query_batch_funding(deps, env, batch_id),
         QueryMsg::PauseInfo {} => query_pause_info(deps),
     }
 }
 
+fn query_batch_funding(deps: Deps<NeutronQuery>, env: Env, batch_id: u128) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let unbond_batch = query_unbond_batch(deps, &config, batch_id)?;
+    // funds delivered since the last sync are reported as if they were synced
+    let funding = allocate_funding(deps, &env, &config)?;
+    let received_amount = match funding
+        .received_amounts
+        .iter()
+        .find(|(funded_batch_id, _)| *funded_batch_id == batch_id)
+    {
+        Some((_, received_amount)) => *received_amount,
+        None => BATCH_RECEIVED_AMOUNTS
+            .may_load(deps.storage, batch_id)?
+            .unwrap_or_default(),
+    };
+    to_json_binary(&BatchFundingResponse {
+        batch_id,
+        expected_amount: get_batch_expected_amount(&unbond_batch),
+        received_amount,
+        paid_amount: BATCH_PAID_AMOUNTS
+            .may_load(deps.storage, batch_id)?
+            .unwrap_or_default(),
+        skipped: SKIPPED_BATCHES.has(deps.storage, batch_id),
+    })
+}
+
+fn query_claimed_vouchers(
+    deps: Deps<NeutronQuery>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let claimed_vouchers = CLAIMED_VOUCHERS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit.unwrap_or(PAGINATION_DEFAULT_LIMIT) as usize)
+        .map(|item| item.map(|(_, claimed_voucher)| claimed_voucher))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&claimed_vouchers)
+}
+
+fn query_batch_reconciliation(deps: Deps<NeutronQuery>, batch_id: u128) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let unbond_batch: UnbondBatch = deps.querier.query_wasm_smart(
+        &config.core_contract,
+        &drop_staking_base::msg::core::QueryMsg::UnbondBatch {
+            batch_id: batch_id.into(),
+        },
+    )?;
+    let paid_amount = BATCH_PAID_AMOUNTS
+        .may_load(deps.storage, batch_id)?
+        .unwrap_or_default();
+    // emergency batches are reconciled against the recovered amount
+    let unbonded_amount = match unbond_batch.unbonded_amount {
+        Some(unbonded_amount) => Some(unbonded_amount),
+        None => EMERGENCY_SETTLEMENTS
+            .may_load(deps.storage, batch_id)?
+            .map(|settlement| settlement.recovered_amount),
+    };
+    to_json_binary(&BatchReconciliationResponse {
+        batch_id,
+        unbonded_amount,
+        paid_amount,
+        is_consistent: unbonded_amount.map_or(paid_amount.is_zero(), |unbonded_amount| {
+            paid_amount <= unbonded_amount
+        }),
+    })
+}
+
 fn query_pause_info(deps: Deps<NeutronQuery>) -> StdResult<Binary> {
     if is_paused(deps.storage) {
         to_json_binary(&PauseInfoResponse::Paused {})
@@ -73,7 +168,7 @@ fn query_pause_info(deps: Deps<NeutronQuery>) -> StdResult<Binary> {
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut<NeutronQuery>,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> ContractResult<Response<NeutronMsg>> {
@@ -91,10 +186,22 @@ pub fn execute(
             let msg: ReceiveNftMsg = from_json(raw_msg)?;
             match msg {
                 ReceiveNftMsg::Withdraw { receiver } => {
-                    execute_receive_nft_withdraw(deps, info, sender, token_id, receiver)
+                    execute_receive_nft_withdraw(deps, env, info, sender, token_id, receiver)
                 }
             }
         }
+        ExecuteMsg::WithdrawMany {
+            token_ids,
+            receiver,
+        } => execute_withdraw_many(deps, env, info, token_ids, receiver),
+        ExecuteMsg::SettleEmergencyBatch {
+            batch_id,
+            emergency_transfer,
+        } => execute_settle_emergency_batch(deps, env, info, batch_id, emergency_transfer),
+        ExecuteMsg::SkipBatchFunding { batch_id } => {
+            execute_skip_batch_funding(deps, env, info, batch_id)
+        }
+        ExecuteMsg::SyncFunding {} => execute_sync_funding(deps, env),
         ExecuteMsg::Pause {} => exec_pause(deps, info),
         ExecuteMsg::Unpause {} => exec_unpause(deps, info),
     }
@@ -160,7 +267,8 @@ fn execute_update_config(
 }
 
 fn execute_receive_nft_withdraw(
-    deps: DepsMut<NeutronQuery>,
+    mut deps: DepsMut<NeutronQuery>,
+    env: Env,
     info: MessageInfo,
     sender: String,
     token_id: String,
@@ -175,8 +283,536 @@ fn execute_receive_nft_withdraw(
         info.sender,
         ContractError::Unauthorized {}
     );
+    attrs.extend(sync_funding(deps.branch(), &env, &config)?);
+    let (batch_id, payout_amount) =
+        get_voucher_payout(deps.as_ref(), &config, token_id.to_string())?;
+
+    let to_address = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?.to_string(),
+        None => sender,
+    };
+    record_claim(
+        deps.storage,
+        &env,
+        &token_id,
+        batch_id,
+        payout_amount,
+        &to_address,
+    )?;
+    attrs.push(attr("batch_id", batch_id.to_string()));
+    attrs.push(attr("payout_amount", payout_amount.to_string()));
+    attrs.push(attr("to_address", &to_address));
+    let hook_msgs = get_lifecycle_hook_msgs(
+        deps.as_ref(),
+        &config,
+        vec![LifecycleHookMsg::OnWithdraw {
+            token_id: token_id.to_string(),
+            batch_id,
+            receiver: to_address.to_string(),
+            amount: payout_amount,
+        }],
+    )?;
+
+    let msgs = vec![
+        get_burn_voucher_msg(&config, token_id)?,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address,
+            amount: vec![Coin {
+                denom: config.base_denom,
+                amount: payout_amount,
+            }],
+        }),
+    ];
+    Ok(response("execute-receive_nft", CONTRACT_NAME, attrs)
+        .add_messages(msgs)
+        .add_submessages(hook_msgs))
+}
+
+fn execute_withdraw_many(
+    mut deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    token_ids: Vec<String>,
+    receiver: Option<String>,
+) -> ContractResult<Response<NeutronMsg>> {
+    assert_paused(deps.storage)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    ensure!(!token_ids.is_empty(), ContractError::EmptyTokenIds {});
+    let mut attrs = vec![attr("action", "withdraw_many")];
+    attrs.extend(sync_funding(deps.branch(), &env, &config)?);
+    let mut messages = vec![];
+    let mut total_payout = Uint128::zero();
+    let mut seen = std::collections::HashSet::new();
+    let mut claims = vec![];
+    for token_id in token_ids {
+        ensure!(
+            seen.insert(token_id.clone()),
+            ContractError::DuplicateTokenId { token_id }
+        );
+        let owner: OwnerOfResponse = deps.querier.query_wasm_smart(
+            &config.withdrawal_voucher_contract,
+            &drop_staking_base::msg::withdrawal_voucher::QueryMsg::OwnerOf {
+                token_id: token_id.to_string(),
+                include_expired: None,
+            },
+        )?;
+        ensure_eq!(owner.owner, info.sender, ContractError::Unauthorized {});
+        let (batch_id, payout_amount) =
+            get_voucher_payout(deps.as_ref(), &config, token_id.to_string())?;
+        total_payout += payout_amount;
+        attrs.push(attr(format!("{token_id}_batch_id"), batch_id.to_string()));
+        attrs.push(attr(format!("{token_id}_payout_amount"), payout_amount));
+        // the manager has to be approved to burn the vouchers
+        messages.push(get_burn_voucher_msg(&config, token_id.to_string())?);
+        claims.push((token_id, batch_id, payout_amount));
+    }
+
+    let to_address = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?.to_string(),
+        None => info.sender.to_string(),
+    };
+    let mut hook_msgs = vec![];
+    for (token_id, batch_id, payout_amount) in claims {
+        record_claim(
+            deps.storage,
+            &env,
+            &token_id,
+            batch_id,
+            payout_amount,
+            &to_address,
+        )?;
+        hook_msgs.push(LifecycleHookMsg::OnWithdraw {
+            token_id,
+            batch_id,
+            receiver: to_address.to_string(),
+            amount: payout_amount,
+        });
+    }
+    let hook_msgs = get_lifecycle_hook_msgs(deps.as_ref(), &config, hook_msgs)?;
+    attrs.push(attr("payout_amount", total_payout));
+    attrs.push(attr("to_address", &to_address));
+    messages.push(CosmosMsg::Bank(BankMsg::Send {
+        to_address,
+        amount: vec![Coin {
+            denom: config.base_denom,
+            amount: total_payout,
+        }],
+    }));
+    Ok(response("execute-withdraw_many", CONTRACT_NAME, attrs)
+        .add_messages(messages)
+        .add_submessages(hook_msgs))
+}
+
+fn execute_settle_emergency_batch(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    batch_id: u128,
+    emergency_transfer: String,
+) -> ContractResult<Response<NeutronMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let config = CONFIG.load(deps.storage)?;
+    let unbond_batch: UnbondBatch = deps.querier.query_wasm_smart(
+        &config.core_contract,
+        &drop_staking_base::msg::core::QueryMsg::UnbondBatch {
+            batch_id: batch_id.into(),
+        },
+    )?;
+    ensure_eq!(
+        unbond_batch.status,
+        UnbondBatchStatus::WithdrawnEmergency,
+        ContractError::BatchIsNotWithdrawnEmergency {}
+    );
+    // vouchers are paid out by the settlement, so it can't be changed afterwards
+    ensure!(
+        !EMERGENCY_SETTLEMENTS.has(deps.storage, batch_id),
+        ContractError::EmergencyBatchIsAlreadySettled { batch_id }
+    );
+    let recovered_amount: Uint128 = info
+        .funds
+        .iter()
+        .filter(|fund| fund.denom == config.base_denom)
+        .map(|fund| fund.amount)
+        .sum();
+    // the attached funds belong to the settlement, so they are not allocated to the batches
+    ACCOUNTED_BALANCE.save(
+        deps.storage,
+        &(ACCOUNTED_BALANCE
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            + recovered_amount),
+    )?;
+    let slashing_effect = Decimal::min(
+        Decimal::from_ratio(recovered_amount, unbond_batch.expected_amount),
+        Decimal::one(),
+    );
+    EMERGENCY_SETTLEMENTS.save(
+        deps.storage,
+        batch_id,
+        &EmergencySettlement {
+            batch_id,
+            recovered_amount,
+            slashing_effect,
+            emergency_transfer: emergency_transfer.clone(),
+            settled_at: env.block.time.seconds(),
+        },
+    )?;
+    let attrs = vec![
+        attr("action", "settle_emergency_batch"),
+        attr("batch_id", batch_id.to_string()),
+        attr("expected_amount", unbond_batch.expected_amount),
+        attr("recovered_amount", recovered_amount),
+        attr("slashing_effect", slashing_effect.to_string()),
+        attr("emergency_transfer", emergency_transfer),
+    ];
+    Ok(response(
+        "execute-settle_emergency_batch",
+        CONTRACT_NAME,
+        attrs,
+    ))
+}
+
+fn execute_skip_batch_funding(
+    mut deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    batch_id: u128,
+) -> ContractResult<Response<NeutronMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let config = CONFIG.load(deps.storage)?;
+    let mut attrs = vec![attr("action", "skip_batch_funding")];
+    attrs.extend(sync_funding(deps.branch(), &env, &config)?);
+    // only the batch the funding is stuck on can be skipped
+    let next_funded_batch_id = NEXT_FUNDED_BATCH_ID.load(deps.storage)?;
+    ensure_eq!(
+        batch_id,
+        next_funded_batch_id,
+        ContractError::BatchIsNotNextFunded {
+            batch_id,
+            next_funded_batch_id
+        }
+    );
+    let unbond_batch = query_unbond_batch(deps.as_ref(), &config, batch_id)?;
+    ensure!(
+        matches!(
+            unbond_batch.status,
+            UnbondBatchStatus::Withdrawing | UnbondBatchStatus::Withdrawn
+        ),
+        ContractError::BatchIsNotWithdrawn {}
+    );
+    let received_amount = BATCH_RECEIVED_AMOUNTS
+        .may_load(deps.storage, batch_id)?
+        .unwrap_or_default();
+    SKIPPED_BATCHES.save(deps.storage, batch_id, &received_amount)?;
+    // the batch took all the funds delivered so far, the next deliveries go to the next batches
+    NEXT_FUNDED_BATCH_ID.save(deps.storage, &(batch_id + 1))?;
+    attrs.extend([
+        attr("batch_id", batch_id.to_string()),
+        attr("expected_amount", get_batch_expected_amount(&unbond_batch)),
+        attr("received_amount", received_amount),
+    ]);
+    Ok(response("execute-skip_batch_funding", CONTRACT_NAME, attrs))
+}
+
+fn execute_sync_funding(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+) -> ContractResult<Response<NeutronMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut attrs = vec![attr("action", "sync_funding")];
+    attrs.extend(sync_funding(deps, &env, &config)?);
+    Ok(response("execute-sync_funding", CONTRACT_NAME, attrs))
+}
+
+struct Funding {
+    balance: Uint128,
+    unallocated: Uint128,
+    next_funded_batch_id: u128,
+    received_amounts: Vec<(u128, Uint128)>,
+}
+
+// funding is derived from the balance, so funds are only allocated once they have landed
+fn allocate_funding(deps: Deps<NeutronQuery>, env: &Env, config: &Config) -> StdResult<Funding> {
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &config.base_denom)?
+        .amount;
+    let accounted_balance = ACCOUNTED_BALANCE
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let mut unallocated = UNALLOCATED_FUNDS
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + balance.saturating_sub(accounted_balance);
+    let mut batch_id = NEXT_FUNDED_BATCH_ID
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let mut received_amounts = vec![];
+    // funds are delivered in the order the batches are withdrawn
+    while !unallocated.is_zero() {
+        let unbond_batch = query_unbond_batch(deps, config, batch_id)?;
+        match unbond_batch.status {
+            UnbondBatchStatus::Withdrawing | UnbondBatchStatus::Withdrawn => {}
+            // emergency batches are funded by the settlement
+            UnbondBatchStatus::WithdrawingEmergency | UnbondBatchStatus::WithdrawnEmergency => {
+                batch_id += 1;
+                continue;
+            }
+            _ => break,
+        }
+        let expected_amount = get_batch_expected_amount(&unbond_batch);
+        let received_amount = BATCH_RECEIVED_AMOUNTS
+            .may_load(deps.storage, batch_id)?
+            .unwrap_or_default();
+        let allocated_amount =
+            Uint128::min(unallocated, expected_amount.saturating_sub(received_amount));
+        unallocated -= allocated_amount;
+        let received_amount = received_amount + allocated_amount;
+        received_amounts.push((batch_id, received_amount));
+        if received_amount < expected_amount {
+            break;
+        }
+        batch_id += 1;
+    }
+    Ok(Funding {
+        balance,
+        unallocated,
+        next_funded_batch_id: batch_id,
+        received_amounts,
+    })
+}
+
+fn sync_funding(
+    deps: DepsMut<NeutronQuery>,
+    env: &Env,
+    config: &Config,
+) -> ContractResult<Vec<Attribute>> {
+    if let Some(start_batch_id) = MIGRATION_CURSOR.may_load(deps.storage)? {
+        return Err(ContractError::MigrationInProgress { start_batch_id });
+    }
+    let funding = allocate_funding(deps.as_ref(), env, config)?;
+    let mut attrs = vec![];
+    for (batch_id, received_amount) in funding.received_amounts {
+        BATCH_RECEIVED_AMOUNTS.save(deps.storage, batch_id, &received_amount)?;
+        attrs.push(attr(format!("batch_{batch_id}_received"), received_amount));
+    }
+    UNALLOCATED_FUNDS.save(deps.storage, &funding.unallocated)?;
+    NEXT_FUNDED_BATCH_ID.save(deps.storage, &funding.next_funded_batch_id)?;
+    ACCOUNTED_BALANCE.save(deps.storage, &funding.balance)?;
+    attrs.push(attr("unallocated", funding.unallocated));
+    Ok(attrs)
+}
+
+fn query_unbond_batch(
+    deps: Deps<NeutronQuery>,
+    config: &Config,
+    batch_id: u128,
+) -> StdResult<UnbondBatch> {
+    deps.querier.query_wasm_smart(
+        &config.core_contract,
+        &drop_staking_base::msg::core::QueryMsg::UnbondBatch {
+            batch_id: batch_id.into(),
+        },
+    )
+}
+
+fn get_batch_expected_amount(unbond_batch: &UnbondBatch) -> Uint128 {
+    unbond_batch
+        .unbonded_amount
+        .unwrap_or(unbond_batch.expected_amount)
+}
+
+// hooks are registered in core, their failures must not block withdrawals
+fn get_lifecycle_hook_msgs(
+    deps: Deps<NeutronQuery>,
+    config: &Config,
+    hook_msgs: Vec<LifecycleHookMsg>,
+) -> ContractResult<Vec<SubMsg<NeutronMsg>>> {
+    let hooks: Vec<(String, Subscriber)> = deps.querier.query_wasm_smart(
+        &config.core_contract,
+        &drop_staking_base::msg::core::QueryMsg::LifecycleHooks {},
+    )?;
+    let mut messages = vec![];
+    for hook_msg in hook_msgs {
+        let msg = to_json_binary(&hook_msg)?;
+        for (address, hook) in &hooks {
+            messages.push(
+                SubMsg::reply_on_error(
+                    WasmMsg::Execute {
+                        contract_addr: address.to_string(),
+                        msg: msg.clone(),
+                        funds: vec![],
+                    },
+                    LIFECYCLE_HOOK_REPLY_ID,
+                )
+                .with_gas_limit(hook.gas_limit),
+            );
+        }
+    }
+    Ok(messages)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    msg: MigrateMsg,
+) -> ContractResult<Response<NeutronMsg>> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let config = CONFIG.load(deps.storage)?;
+    let cursor = MIGRATION_CURSOR.may_load(deps.storage)?;
+    let start_batch_id = msg.start_batch_id.unwrap_or_default();
+    match cursor {
+        Some(cursor) => ensure_eq!(
+            start_batch_id,
+            cursor,
+            ContractError::MigrationInProgress {
+                start_batch_id: cursor
+            }
+        ),
+        // the funding is already tracked
+        None if ACCOUNTED_BALANCE.exists(deps.storage) => {
+            return Ok(response("migrate", CONTRACT_NAME, Vec::<Attribute>::new()))
+        }
+        None => {
+            ensure_eq!(
+                start_batch_id,
+                0,
+                ContractError::MigrationInProgress { start_batch_id: 0 }
+            );
+            // the balance is what is left of the funds of the batches withdrawn so far
+            let balance = deps
+                .querier
+                .query_balance(&env.contract.address, &config.base_denom)?
+                .amount;
+            ACCOUNTED_BALANCE.save(deps.storage, &balance)?;
+            UNALLOCATED_FUNDS.save(deps.storage, &balance)?;
+            NEXT_FUNDED_BATCH_ID.save(deps.storage, &0)?;
+        }
+    }
+    let mut unallocated = UNALLOCATED_FUNDS.load(deps.storage)?;
+    let mut next_funded_batch_id = NEXT_FUNDED_BATCH_ID.load(deps.storage)?;
+    let mut batch_id = start_batch_id;
+    let mut finished = false;
+    for _ in 0..msg.limit.unwrap_or(MIGRATION_DEFAULT_LIMIT) {
+        let unbond_batch = query_unbond_batch(deps.as_ref(), &config, batch_id)?;
+        let paid_amount = BATCH_PAID_AMOUNTS
+            .may_load(deps.storage, batch_id)?
+            .unwrap_or_default();
+        let is_funded = match unbond_batch.status {
+            // the balance funds the unpaid part of the batches in the order they are withdrawn
+            UnbondBatchStatus::Withdrawing | UnbondBatchStatus::Withdrawn => {
+                let expected_amount = get_batch_expected_amount(&unbond_batch);
+                let allocated_amount =
+                    Uint128::min(unallocated, expected_amount.saturating_sub(paid_amount));
+                unallocated -= allocated_amount;
+                let received_amount = paid_amount + allocated_amount;
+                BATCH_RECEIVED_AMOUNTS.save(deps.storage, batch_id, &received_amount)?;
+                received_amount >= expected_amount
+            }
+            UnbondBatchStatus::WithdrawnEmergency => {
+                // the unpaid part of a settlement is already on the balance
+                if let Some(settlement) = EMERGENCY_SETTLEMENTS.may_load(deps.storage, batch_id)? {
+                    unallocated = unallocated
+                        .saturating_sub(settlement.recovered_amount.saturating_sub(paid_amount));
+                }
+                true
+            }
+            UnbondBatchStatus::WithdrawingEmergency => true,
+            UnbondBatchStatus::New => {
+                finished = true;
+                break;
+            }
+            _ => false,
+        };
+        if is_funded && next_funded_batch_id == batch_id {
+            next_funded_batch_id += 1;
+        }
+        batch_id += 1;
+    }
+    UNALLOCATED_FUNDS.save(deps.storage, &unallocated)?;
+    NEXT_FUNDED_BATCH_ID.save(deps.storage, &next_funded_batch_id)?;
+    let mut attrs = vec![
+        attr("next_funded_batch_id", next_funded_batch_id.to_string()),
+        attr("unallocated", unallocated),
+    ];
+    if finished {
+        MIGRATION_CURSOR.remove(deps.storage);
+    } else {
+        MIGRATION_CURSOR.save(deps.storage, &batch_id)?;
+        attrs.push(attr("next_start_batch_id", batch_id.to_string()));
+    }
+    Ok(response("migrate", CONTRACT_NAME, attrs))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    _deps: DepsMut<NeutronQuery>,
+    _env: Env,
+    msg: Reply,
+) -> ContractResult<Response<NeutronMsg>> {
+    match msg.id {
+        // not reported back to core, the subscriber failure counter there skips these hooks
+        LIFECYCLE_HOOK_REPLY_ID => Ok(response(
+            "reply-lifecycle_hook_failure",
+            CONTRACT_NAME,
+            [attr("error", msg.result.unwrap_err())],
+        )),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+fn get_burn_voucher_msg(config: &Config, token_id: String) -> StdResult<CosmosMsg<NeutronMsg>> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.withdrawal_voucher_contract.to_string(),
+        msg: to_json_binary(
+            &drop_staking_base::msg::withdrawal_voucher::ExecuteMsg::Burn { token_id },
+        )?,
+        funds: vec![],
+    }))
+}
+
+fn record_claim(
+    storage: &mut dyn Storage,
+    env: &Env,
+    token_id: &str,
+    batch_id: u128,
+    amount: Uint128,
+    receiver: &str,
+) -> ContractResult<()> {
+    CLAIMED_VOUCHERS.save(
+        storage,
+        token_id.to_string(),
+        &ClaimedVoucher {
+            token_id: token_id.to_string(),
+            batch_id,
+            amount,
+            receiver: receiver.to_string(),
+            timestamp: env.block.time.seconds(),
+        },
+    )?;
+    BATCH_PAID_AMOUNTS.update(storage, batch_id, |paid| {
+        StdResult::Ok(paid.unwrap_or_default() + amount)
+    })?;
+    // the payout leaves the balance within the same transaction
+    ACCOUNTED_BALANCE.update(storage, |accounted_balance| {
+        StdResult::Ok(accounted_balance.checked_sub(amount)?)
+    })?;
+    Ok(())
+}
+
+fn get_voucher_payout(
+    deps: Deps<NeutronQuery>,
+    config: &Config,
+    token_id: String,
+) -> ContractResult<(u128, Uint128)> {
+    ensure!(
+        !CLAIMED_VOUCHERS.has(deps.storage, token_id.to_string()),
+        ContractError::VoucherIsAlreadyClaimed { token_id }
+    );
     let voucher: NftInfoResponse<Extension> = deps.querier.query_wasm_smart(
-        config.withdrawal_voucher_contract,
+        &config.withdrawal_voucher_contract,
         &drop_staking_base::msg::withdrawal_voucher::QueryMsg::NftInfo { token_id },
     )?;
     let voucher_extention = voucher.extension.ok_or_else(|| ContractError::InvalidNFT {
@@ -191,37 +827,45 @@ fn execute_receive_nft_withdraw(
                 reason: "invalid batch_id".to_string(),
             })?;
 
-    let unbond_batch: UnbondBatch = deps.querier.query_wasm_smart(
-        &config.core_contract,
-        &drop_staking_base::msg::core::QueryMsg::UnbondBatch {
-            batch_id: batch_id.into(),
-        },
-    )?;
-    ensure_eq!(
-        unbond_batch.status,
-        UnbondBatchStatus::Withdrawn,
-        ContractError::BatchIsNotWithdrawn {}
-    );
-    let slashing_effect = unbond_batch
-        .slashing_effect
-        .ok_or(ContractError::BatchSlashingEffectIsEmpty {})?;
+    let unbond_batch = query_unbond_batch(deps, config, batch_id)?;
+    let slashing_effect = match unbond_batch.status {
+        UnbondBatchStatus::Withdrawn => {
+            let slashing_effect = unbond_batch
+                .slashing_effect
+                .ok_or(ContractError::BatchSlashingEffectIsEmpty {})?;
+            let expected_amount = get_batch_expected_amount(&unbond_batch);
+            match SKIPPED_BATCHES.may_load(deps.storage, batch_id)? {
+                // a skipped batch pays out only what it has received
+                Some(received_amount) => {
+                    slashing_effect
+                        * Decimal::checked_from_ratio(received_amount, expected_amount)
+                            .unwrap_or(Decimal::one())
+                }
+                None => {
+                    // the batch can be withdrawn before the pump delivers its funds
+                    ensure!(
+                        BATCH_RECEIVED_AMOUNTS
+                            .may_load(deps.storage, batch_id)?
+                            .unwrap_or_default()
+                            >= expected_amount,
+                        ContractError::BatchIsNotFunded { batch_id }
+                    );
+                    slashing_effect
+                }
+            }
+        }
+        UnbondBatchStatus::WithdrawnEmergency => {
+            EMERGENCY_SETTLEMENTS
+                .may_load(deps.storage, batch_id)?
+                .ok_or(ContractError::EmergencyBatchIsNotSettled { batch_id })?
+                .slashing_effect
+        }
+        _ => return Err(ContractError::BatchIsNotWithdrawn {}),
+    };
 
     let payout_amount = Uint128::min(
         slashing_effect * voucher_extention.expected_amount,
         voucher_extention.expected_amount,
     ); //just in case
-
-    let to_address = receiver.unwrap_or(sender);
-    attrs.push(attr("batch_id", batch_id.to_string()));
-    attrs.push(attr("payout_amount", payout_amount.to_string()));
-    attrs.push(attr("to_address", &to_address));
-
-    let msg = CosmosMsg::Bank(BankMsg::Send {
-        to_address,
-        amount: vec![Coin {
-            denom: config.base_denom,
-            amount: payout_amount,
-        }],
-    });
-    Ok(response("execute-receive_nft", CONTRACT_NAME, attrs).add_message(msg))
+    Ok((batch_id, payout_amount))
 }
@@ -24,12 +24,45 @@ pub enum ContractError {
     #[error("Batch is not withdrawn yet")]
     BatchIsNotWithdrawn {},
 
+    #[error("Batch is not withdrawn in emergency mode")]
+    BatchIsNotWithdrawnEmergency {},
+
+    #[error("Funds of batch {batch_id} are not delivered yet")]
+    BatchIsNotFunded { batch_id: u128 },
+
+    #[error("Emergency batch {batch_id} is not settled yet")]
+    EmergencyBatchIsNotSettled { batch_id: u128 },
+
+    #[error("Emergency batch {batch_id} is already settled")]
+    EmergencyBatchIsAlreadySettled { batch_id: u128 },
+
+    #[error("Batch {batch_id} is not the next batch to fund, it's {next_funded_batch_id}")]
+    BatchIsNotNextFunded {
+        batch_id: u128,
+        next_funded_batch_id: u128,
+    },
+
+    #[error("Migration is in progress, it continues from batch {start_batch_id}")]
+    MigrationInProgress { start_batch_id: u128 },
+
+    #[error("unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
     #[error("Missing unbonded amount in batch")]
     BatchAmountIsEmpty {},
 
     #[error("Slashing effect is not set")]
     BatchSlashingEffectIsEmpty {},
 
+    #[error("Voucher {token_id} is already claimed")]
+    VoucherIsAlreadyClaimed { token_id: String },
+
+    #[error("No vouchers provided")]
+    EmptyTokenIds {},
+
+    #[error("Voucher {token_id} is provided more than once")]
+    DuplicateTokenId { token_id: String },
+
     #[error("{0}")]
     OwnershipError(#[from] OwnershipError),
 
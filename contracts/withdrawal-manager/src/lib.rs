@@ -1,2 +1,4 @@
 pub mod contract;
 mod error;
+#[cfg(test)]
+mod tests;
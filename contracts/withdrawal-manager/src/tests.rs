@@ -0,0 +1,544 @@
+use cosmwasm_std::{
+    attr, coins, from_json,
+    testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage},
+    to_json_binary, BankMsg, ContractResult, CosmosMsg, Decimal, OwnedDeps, Querier, SystemResult,
+    Uint128, WasmQuery,
+};
+use cw721::{NftInfoResponse, OwnerOfResponse};
+use drop_staking_base::{
+    msg::{
+        core::QueryMsg as CoreQueryMsg,
+        withdrawal_manager::{
+            BatchFundingResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+        },
+        withdrawal_voucher::QueryMsg as VoucherQueryMsg,
+    },
+    state::{
+        core::{Subscriber, UnbondBatch, UnbondBatchStatus},
+        withdrawal_manager::{
+            EmergencySettlement, ACCOUNTED_BALANCE, BATCH_PAID_AMOUNTS, BATCH_RECEIVED_AMOUNTS,
+            MIGRATION_CURSOR, NEXT_FUNDED_BATCH_ID, UNALLOCATED_FUNDS,
+        },
+        withdrawal_voucher::Metadata,
+    },
+};
+use neutron_sdk::bindings::query::NeutronQuery;
+use std::marker::PhantomData;
+
+use crate::{
+    contract::{execute, instantiate, migrate, query},
+    error::ContractError,
+};
+
+fn mock_dependencies<Q: Querier + Default>() -> OwnedDeps<MockStorage, MockApi, Q, NeutronQuery> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: Q::default(),
+        custom_query_type: PhantomData,
+    }
+}
+
+fn unbond_batch(status: UnbondBatchStatus) -> UnbondBatch {
+    UnbondBatch {
+        total_amount: Uint128::new(1000),
+        expected_amount: Uint128::new(1000),
+        expected_release: 0,
+        unbond_items: vec![],
+        status,
+        slashing_effect: Some(Decimal::one()),
+        unbonded_amount: Some(Uint128::new(1000)),
+        withdrawed_amount: None,
+        created: 0,
+    }
+}
+
+// batch 0 is withdrawn, batch 1 is withdrawn in emergency mode, vouchers are named after their batch
+fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier, NeutronQuery> {
+    let mut deps = mock_dependencies::<MockQuerier>();
+    deps.querier.update_wasm(|request| {
+        let WasmQuery::Smart { contract_addr, msg } = request else {
+            unimplemented!()
+        };
+        let reply = match contract_addr.as_str() {
+            "core" => match from_json::<CoreQueryMsg>(msg).unwrap() {
+                CoreQueryMsg::UnbondBatch { batch_id } => {
+                    to_json_binary(&unbond_batch(match batch_id.u128() {
+                        0 => UnbondBatchStatus::Withdrawn,
+                        1 => UnbondBatchStatus::WithdrawnEmergency,
+                        _ => UnbondBatchStatus::New,
+                    }))
+                }
+                CoreQueryMsg::LifecycleHooks {} => {
+                    to_json_binary(&Vec::<(String, Subscriber)>::new())
+                }
+                _ => unimplemented!(),
+            },
+            "voucher" => match from_json::<VoucherQueryMsg>(msg).unwrap() {
+                VoucherQueryMsg::OwnerOf { .. } => to_json_binary(&OwnerOfResponse {
+                    owner: "holder".to_string(),
+                    approvals: vec![],
+                }),
+                VoucherQueryMsg::NftInfo { token_id } => to_json_binary(&NftInfoResponse {
+                    token_uri: None,
+                    extension: Some(Metadata {
+                        batch_id: token_id.split('_').next().unwrap().to_string(),
+                        expected_amount: Uint128::new(100),
+                        ..Default::default()
+                    }),
+                }),
+                _ => unimplemented!(),
+            },
+            _ => unimplemented!(),
+        };
+        SystemResult::Ok(ContractResult::from(reply))
+    });
+    instantiate(
+        deps.as_mut().into_empty(),
+        mock_env(),
+        mock_info("admin", &[]),
+        InstantiateMsg {
+            core_contract: "core".to_string(),
+            voucher_contract: "voucher".to_string(),
+            base_denom: "base_denom".to_string(),
+            owner: "owner".to_string(),
+        },
+    )
+    .unwrap();
+    deps
+}
+
+#[test]
+fn withdraw_many_validates_receiver() {
+    let mut deps = setup();
+    deps.querier
+        .update_balance("cosmos2contract", coins(1000, "base_denom"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::WithdrawMany {
+            token_ids: vec!["0_1".to_string()],
+            receiver: Some("".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::WithdrawMany {
+            token_ids: vec!["0_1".to_string(), "0_2".to_string()],
+            receiver: Some("receiver".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.last().unwrap().msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "receiver".to_string(),
+            amount: coins(200, "base_denom"),
+        })
+    );
+}
+
+#[test]
+fn withdraw_many_requires_funded_batch() {
+    let mut deps = setup();
+    // emergency batches are skipped, the rest stays unallocated
+    deps.querier
+        .update_balance("cosmos2contract", coins(999, "base_denom"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::WithdrawMany {
+            token_ids: vec!["0_1".to_string()],
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BatchIsNotFunded { batch_id: 0 });
+}
+
+#[test]
+fn settle_emergency_batch() {
+    let mut deps = setup();
+    // batch 0 takes 1000 of the balance, the rest is attached to the settlement
+    deps.querier
+        .update_balance("cosmos2contract", coins(1800, "base_denom"));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &coins(800, "base_denom")),
+        ExecuteMsg::SettleEmergencyBatch {
+            batch_id: 1,
+            emergency_transfer: "tx_hash".to_string(),
+        },
+    )
+    .unwrap();
+    let settlement: Option<EmergencySettlement> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::EmergencySettlement { batch_id: 1 },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let settlement = settlement.unwrap();
+    assert_eq!(settlement.recovered_amount, Uint128::new(800));
+    assert_eq!(settlement.slashing_effect, Decimal::percent(80));
+
+    // the recovered funds are not allocated to the regular batches
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        ExecuteMsg::SyncFunding {},
+    )
+    .unwrap();
+    assert_eq!(
+        BATCH_RECEIVED_AMOUNTS
+            .load(deps.as_ref().storage, 0)
+            .unwrap(),
+        Uint128::new(1000)
+    );
+    assert_eq!(
+        UNALLOCATED_FUNDS.load(deps.as_ref().storage).unwrap(),
+        Uint128::zero()
+    );
+
+    // the settlement is frozen once set, even before the first claim
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &coins(100, "base_denom")),
+        ExecuteMsg::SettleEmergencyBatch {
+            batch_id: 1,
+            emergency_transfer: "tx_hash".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::EmergencyBatchIsAlreadySettled { batch_id: 1 }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::WithdrawMany {
+            token_ids: vec!["1_1".to_string()],
+            receiver: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.last().unwrap().msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "holder".to_string(),
+            amount: coins(80, "base_denom"),
+        })
+    );
+}
+
+#[test]
+fn settle_emergency_batch_only_for_emergency_batches() {
+    let mut deps = setup();
+    deps.querier
+        .update_balance("cosmos2contract", coins(1000, "base_denom"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SettleEmergencyBatch {
+            batch_id: 0,
+            emergency_transfer: "tx_hash".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BatchIsNotWithdrawnEmergency {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        ExecuteMsg::SettleEmergencyBatch {
+            batch_id: 1,
+            emergency_transfer: "tx_hash".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::OwnershipError(_)));
+}
+
+#[test]
+fn skip_batch_funding() {
+    let mut deps = setup();
+    // the rest of batch 0 is never delivered
+    deps.querier
+        .update_balance("cosmos2contract", coins(600, "base_denom"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        ExecuteMsg::SkipBatchFunding { batch_id: 0 },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::OwnershipError(_)));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SkipBatchFunding { batch_id: 1 },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BatchIsNotNextFunded {
+            batch_id: 1,
+            next_funded_batch_id: 0
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SkipBatchFunding { batch_id: 0 },
+    )
+    .unwrap();
+    assert_eq!(NEXT_FUNDED_BATCH_ID.load(deps.as_ref().storage).unwrap(), 1);
+    let funding: BatchFundingResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BatchFunding { batch_id: 0 },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(funding.skipped);
+    assert_eq!(funding.received_amount, Uint128::new(600));
+
+    // the vouchers are paid pro rata of the received amount
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::WithdrawMany {
+            token_ids: vec!["0_1".to_string()],
+            receiver: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.last().unwrap().msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "holder".to_string(),
+            amount: coins(60, "base_denom"),
+        })
+    );
+}
+
+#[test]
+fn funding_follows_delivered_balance() {
+    let mut deps = setup();
+    let query_received_amount =
+        |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier, NeutronQuery>| {
+            from_json::<BatchFundingResponse>(
+                query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::BatchFunding { batch_id: 0 },
+                )
+                .unwrap(),
+            )
+            .unwrap()
+            .received_amount
+        };
+    assert_eq!(query_received_amount(&deps), Uint128::zero());
+
+    // delivered funds are reported before they are synced
+    deps.querier
+        .update_balance("cosmos2contract", coins(600, "base_denom"));
+    assert_eq!(query_received_amount(&deps), Uint128::new(600));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        ExecuteMsg::SyncFunding {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.events[0].attributes[1..],
+        [attr("batch_0_received", "600"), attr("unallocated", "0")]
+    );
+
+    // the synced balance is not allocated twice
+    deps.querier
+        .update_balance("cosmos2contract", coins(1100, "base_denom"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        ExecuteMsg::SyncFunding {},
+    )
+    .unwrap();
+    assert_eq!(query_received_amount(&deps), Uint128::new(1000));
+    assert_eq!(
+        UNALLOCATED_FUNDS.load(deps.as_ref().storage).unwrap(),
+        Uint128::new(100)
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::WithdrawMany {
+            token_ids: vec!["0_1".to_string()],
+            receiver: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        ACCOUNTED_BALANCE.load(deps.as_ref().storage).unwrap(),
+        Uint128::new(1000)
+    );
+}
+
+#[test]
+fn migrate_seeds_funding_page_by_page() {
+    let mut deps = setup();
+    // 600 of batch 0 is paid out already, the balance covers the rest
+    BATCH_PAID_AMOUNTS
+        .save(deps.as_mut().storage, 0, &Uint128::new(600))
+        .unwrap();
+    deps.querier
+        .update_balance("cosmos2contract", coins(500, "base_denom"));
+
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            start_batch_id: None,
+            limit: Some(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        BATCH_RECEIVED_AMOUNTS
+            .load(deps.as_ref().storage, 0)
+            .unwrap(),
+        Uint128::new(1000)
+    );
+    assert_eq!(MIGRATION_CURSOR.load(deps.as_ref().storage).unwrap(), 1);
+
+    // the funding is frozen until the last page
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("stranger", &[]),
+        ExecuteMsg::SyncFunding {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MigrationInProgress { start_batch_id: 1 }
+    );
+    let err = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            start_batch_id: None,
+            limit: Some(1),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MigrationInProgress { start_batch_id: 1 }
+    );
+
+    for start_batch_id in [1, 2] {
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                start_batch_id: Some(start_batch_id),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    }
+    assert!(!MIGRATION_CURSOR.exists(deps.as_ref().storage));
+    assert!(!BATCH_RECEIVED_AMOUNTS.has(deps.as_ref().storage, 1));
+    assert_eq!(NEXT_FUNDED_BATCH_ID.load(deps.as_ref().storage).unwrap(), 2);
+    assert_eq!(
+        UNALLOCATED_FUNDS.load(deps.as_ref().storage).unwrap(),
+        Uint128::new(100)
+    );
+    assert_eq!(
+        ACCOUNTED_BALANCE.load(deps.as_ref().storage).unwrap(),
+        Uint128::new(500)
+    );
+
+    // the vouchers of the historical batches stay claimable
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::WithdrawMany {
+            token_ids: vec!["0_1".to_string()],
+            receiver: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn migrate_leaves_uncovered_batches_unfunded() {
+    let mut deps = setup();
+    deps.querier
+        .update_balance("cosmos2contract", coins(500, "base_denom"));
+
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            start_batch_id: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        BATCH_RECEIVED_AMOUNTS
+            .load(deps.as_ref().storage, 0)
+            .unwrap(),
+        Uint128::new(500)
+    );
+    assert_eq!(NEXT_FUNDED_BATCH_ID.load(deps.as_ref().storage).unwrap(), 0);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("holder", &[]),
+        ExecuteMsg::WithdrawMany {
+            token_ids: vec!["0_1".to_string()],
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BatchIsNotFunded { batch_id: 0 });
+}
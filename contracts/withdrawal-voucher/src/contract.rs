@@ -1,18 +1,23 @@
-use cosmwasm_std::Empty;
+use cosmwasm_std::{to_json_binary, Binary, Decimal, Deps, Empty, StdError, StdResult, Uint128};
+use cw721::Cw721Query;
 pub use cw721_base::{ContractError, MinterResponse};
-use drop_staking_base::msg::withdrawal_voucher::Extension;
+use drop_staking_base::msg::withdrawal_manager::BatchFundingResponse;
+use drop_staking_base::msg::withdrawal_voucher::{Extension, QueryExtMsg, VoucherInfoResponse};
+use drop_staking_base::state::core::{Config as CoreConfig, UnbondBatch, UnbondBatchStatus};
+use drop_staking_base::state::withdrawal_manager::EmergencySettlement;
 
 const CONTRACT_NAME: &str = concat!("crates.io:drop-staking__", env!("CARGO_PKG_NAME"));
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub type Cw721VoucherContract<'a> = cw721_base::Cw721Contract<'a, Extension, Empty, Empty, Empty>;
+pub type Cw721VoucherContract<'a> =
+    cw721_base::Cw721Contract<'a, Extension, Empty, Empty, QueryExtMsg>;
 
 #[cfg(not(feature = "library"))]
 pub mod entry {
     use super::*;
 
     use cosmwasm_std::entry_point;
-    use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
     use drop_staking_base::msg::withdrawal_voucher::{ExecuteMsg, InstantiateMsg, QueryMsg};
 
     // This makes a conscious choice on the various generics used by the contract
@@ -39,6 +44,128 @@ pub mod entry {
 
     #[entry_point]
     pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-        Cw721VoucherContract::default().query(deps, env, msg)
+        match msg {
+            QueryMsg::Extension { msg } => query_ext(deps, msg),
+            _ => Cw721VoucherContract::default().query(deps, env, msg),
+        }
     }
 }
+
+pub fn query_ext(deps: Deps, msg: QueryExtMsg) -> StdResult<Binary> {
+    match msg {
+        QueryExtMsg::VoucherInfo { token_id } => {
+            let core_contract = query_core_contract(deps)?;
+            to_json_binary(&query_voucher_info(deps, &core_contract, token_id)?)
+        }
+        QueryExtMsg::VouchersByOwner {
+            owner,
+            start_after,
+            limit,
+        } => {
+            let core_contract = query_core_contract(deps)?;
+            let token_ids = Cw721VoucherContract::default()
+                .tokens(deps, owner, start_after, limit)?
+                .tokens;
+            let vouchers = token_ids
+                .into_iter()
+                .map(|token_id| query_voucher_info(deps, &core_contract, token_id))
+                .collect::<StdResult<Vec<_>>>()?;
+            to_json_binary(&vouchers)
+        }
+    }
+}
+
+// core is the only minter of the vouchers
+fn query_core_contract(deps: Deps) -> StdResult<String> {
+    Cw721VoucherContract::default()
+        .minter(deps)?
+        .minter
+        .ok_or_else(|| StdError::generic_err("minter is not set"))
+}
+
+fn query_voucher_info(
+    deps: Deps,
+    core_contract: &str,
+    token_id: String,
+) -> StdResult<VoucherInfoResponse> {
+    let token = Cw721VoucherContract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    let metadata = token
+        .extension
+        .ok_or_else(|| StdError::generic_err("voucher metadata is not set"))?;
+    let batch_id = metadata
+        .batch_id
+        .parse::<u128>()
+        .map_err(|_| StdError::generic_err("invalid batch_id"))?;
+    let unbond_batch: UnbondBatch = deps.querier.query_wasm_smart(
+        core_contract,
+        &drop_staking_base::msg::core::QueryMsg::UnbondBatch {
+            batch_id: batch_id.into(),
+        },
+    )?;
+    // the withdrawal manager pays out only funded batches and settled emergency batches
+    let (slashing_effect, claimable) = match unbond_batch.status {
+        UnbondBatchStatus::Withdrawn => {
+            let funding: BatchFundingResponse = deps.querier.query_wasm_smart(
+                get_withdrawal_manager(deps, core_contract)?,
+                &drop_staking_base::msg::withdrawal_manager::QueryMsg::BatchFunding { batch_id },
+            )?;
+            if funding.skipped {
+                // a skipped batch pays out only what it has received
+                let funded_share =
+                    Decimal::checked_from_ratio(funding.received_amount, funding.expected_amount)
+                        .unwrap_or(Decimal::one());
+                (
+                    unbond_batch
+                        .slashing_effect
+                        .map(|slashing_effect| slashing_effect * funded_share),
+                    true,
+                )
+            } else {
+                (
+                    unbond_batch.slashing_effect,
+                    funding.received_amount >= funding.expected_amount,
+                )
+            }
+        }
+        UnbondBatchStatus::WithdrawnEmergency => {
+            let settlement: Option<EmergencySettlement> = deps.querier.query_wasm_smart(
+                get_withdrawal_manager(deps, core_contract)?,
+                &drop_staking_base::msg::withdrawal_manager::QueryMsg::EmergencySettlement {
+                    batch_id,
+                },
+            )?;
+            match settlement {
+                Some(settlement) => (Some(settlement.slashing_effect), true),
+                None => (unbond_batch.slashing_effect, false),
+            }
+        }
+        _ => (unbond_batch.slashing_effect, false),
+    };
+    // same as the withdrawal manager pays out
+    let payout = slashing_effect.map(|slashing_effect| {
+        Uint128::min(
+            slashing_effect * metadata.expected_amount,
+            metadata.expected_amount,
+        )
+    });
+    Ok(VoucherInfoResponse {
+        token_id,
+        owner: token.owner.to_string(),
+        batch_status: unbond_batch.status,
+        expected_release: unbond_batch.expected_release,
+        slashing_effect,
+        payout,
+        claimable,
+        metadata,
+    })
+}
+
+fn get_withdrawal_manager(deps: Deps, core_contract: &str) -> StdResult<String> {
+    let core_config: CoreConfig = deps.querier.query_wasm_smart(
+        core_contract,
+        &drop_staking_base::msg::core::QueryMsg::Config {},
+    )?;
+    Ok(core_config.withdrawal_manager_contract)
+}
@@ -665,6 +665,10 @@ describe('Auto withdrawer', () => {
             },
           ],
           token_id: `0_${autoWithdrawerContractClient.contractAddress}_2`,
+          batch_id: 0,
+          batch_status: expect.any(String),
+          expected_payout: expect.any(String),
+          claimable: false,
         },
       ],
       next_page_key: null,
@@ -749,6 +753,10 @@ describe('Auto withdrawer', () => {
             },
           ],
           token_id: `0_${autoWithdrawerContractClient.contractAddress}_2`,
+          batch_id: 0,
+          batch_status: expect.any(String),
+          expected_payout: expect.any(String),
+          claimable: false,
         },
       ],
       next_page_key: null,
@@ -1,4 +1,4 @@
-use cosmwasm_std::{StdError, Uint128};
+use cosmwasm_std::{Decimal, StdError, Uint128};
 use cw_ownable::OwnershipError;
 use neutron_sdk::NeutronError;
 use thiserror::Error;
@@ -23,6 +23,30 @@ pub enum ContractError {
         amount: Uint128,
         denom: String,
     },
+
+    #[error("Invalid max spread: {max_spread}")]
+    InvalidMaxSpread { max_spread: Decimal },
+
+    #[error("Invalid max price deviation: {max_deviation}")]
+    InvalidMaxDeviation { max_deviation: Decimal },
+
+    #[error("Simulated swap returns nothing for {amount}{denom}")]
+    ZeroSimulatedReturn { amount: Uint128, denom: String },
+
+    #[error("Price oracle returns nothing for {denom}")]
+    EmptyOracleConsult { denom: String },
+
+    #[error("Price oracle quotes {oracle_asset}, the swap route ends in {route_asset}")]
+    OracleAssetMismatch {
+        oracle_asset: String,
+        route_asset: String,
+    },
+
+    #[error("Price deviates from oracle TWAP: expected at least {min_return}, simulated {simulated_return}")]
+    PriceDeviation {
+        min_return: Uint128,
+        simulated_return: Uint128,
+    },
 }
 
 pub type ContractResult<T> = Result<T, ContractError>;
@@ -1,4 +1,5 @@
 pub mod astroport_exchange_handler;
+pub mod dex_exchange_handler;
 pub mod distribution;
 pub mod rewards_manager;
 pub mod validatorset;
@@ -20,6 +20,12 @@ pub enum ContractError {
 
     #[error(transparent)]
     PauseError(#[from] PauseError),
+
+    #[error("Epoch duration must be positive")]
+    InvalidEpochDuration {},
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
 }
 
 pub type ContractResult<T> = Result<T, ContractError>;
@@ -1,6 +1,8 @@
 use astroport::router::SwapOperation;
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Coin, Decimal, Uint128};
+
+use crate::state::astroport_exchange_handler::PriceOracle;
 
 #[cw_serde]
 pub enum ExecuteMsg {
@@ -12,10 +14,16 @@ pub enum ExecuteMsg {
         pair_contract: Option<String>,
         from_denom: Option<String>,
         min_rewards: Option<Uint128>,
+        max_spread: Option<Decimal>,
+        /// Zero disables chunking.
+        max_chunk_size: Option<Uint128>,
     },
     UpdateSwapOperations {
         operations: Option<Vec<SwapOperation>>,
     },
+    UpdatePriceOracle {
+        oracle: Option<PriceOracle>,
+    },
     Exchange {},
 }
 
@@ -24,6 +32,8 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     #[returns(ConfigResponse)]
     Config {},
+    #[returns(crate::msg::reward_handler::SimulateExchangeResponse)]
+    SimulateExchange { amount: Coin },
 }
 
 #[cw_serde]
@@ -35,7 +45,10 @@ pub struct ConfigResponse {
     pub pair_contract: String,
     pub from_denom: String,
     pub min_rewards: Uint128,
+    pub max_spread: Decimal,
+    pub max_chunk_size: Option<Uint128>,
     pub swap_operations: Option<Vec<SwapOperation>>,
+    pub price_oracle: Option<PriceOracle>,
 }
 
 #[cw_serde]
@@ -47,7 +60,12 @@ pub struct InstantiateMsg {
     pub pair_contract: String,
     pub from_denom: String,
     pub min_rewards: Uint128,
+    pub max_spread: Decimal,
+    pub max_chunk_size: Option<Uint128>,
+    pub price_oracle: Option<PriceOracle>,
 }
 
 #[cw_serde]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {
+    pub max_spread: Decimal,
+}
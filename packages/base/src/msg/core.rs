@@ -1,6 +1,10 @@
-use crate::state::core::{Config, ConfigOptional, NonNativeRewardsItem};
+use crate::state::core::{
+    Config, ConfigOptional, FeeHistoryItem, FeeItem, FeeRecipient, NonNativeRewardsDestination,
+    NonNativeRewardsItem, Subscriber,
+};
+use astroport::router::SwapOperation;
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Coin, Decimal, Uint128};
 use cw_ownable::cw_ownable_execute;
 #[allow(unused_imports)]
 use drop_helpers::pause::PauseInfoResponse;
@@ -33,6 +37,14 @@ pub struct InstantiateMsg {
     pub fee_address: Option<String>,
     pub emergency_address: Option<String>,
     pub min_stake_amount: Uint128,
+    pub router_contract: Option<String>,
+    pub referral_fee_share: Option<Decimal>,
+    pub insurance_contract: Option<String>,
+    pub insurance_fee_share: Option<Decimal>,
+    pub insurance_fee_channel: Option<String>,
+    pub exchange_rate_notify_delta: Option<Decimal>,
+    pub pump_contract: Option<String>,
+    pub cw20_wrapper_contract: Option<String>,
 }
 
 #[pausable_query]
@@ -53,30 +65,204 @@ pub enum QueryMsg {
     LastPuppeteerResponse {},
     #[returns(Vec<NonNativeRewardsItem>)]
     NonNativeRewardsReceivers {},
+    #[returns(Vec<NonNativeRewardsRoutingResponseItem>)]
+    NonNativeRewardsRouting {},
+    #[returns(NonNativeRewardsConsistencyResponse)]
+    NonNativeRewardsConsistency {},
     #[returns(Vec<(String,(String, Uint128))>)]
     PendingLSMShares {},
     #[returns(Vec<(String,(String, Uint128))>)]
     LSMSharesToRedeem {},
     #[returns(Uint128)]
     TotalBonded {},
+    #[returns(Vec<(String, String)>)]
+    LSMSharePaths {},
+    #[returns(SupportedBondDenomsResponse)]
+    SupportedBondDenoms {},
+    #[returns(Vec<FeeRecipient>)]
+    FeeRecipients {},
+    #[returns(CollectedFeesResponse)]
+    CollectedFees {},
+    #[returns(Vec<FeeHistoryItem>)]
+    FeeHistory {},
+    #[returns(Option<ReferrerResponse>)]
+    Referrer { address: String },
+    #[returns(Vec<ReferrerResponse>)]
+    TopReferrers { limit: Option<usize> },
+    #[returns(Vec<(String, Subscriber)>)]
+    ExchangeRateSubscribers {},
+    #[returns(Vec<(String, Subscriber)>)]
+    LifecycleHooks {},
+    #[returns(Uint128)]
+    CompoundedRewards {},
+    #[returns(Uint128)]
+    FailedPumpTransfers {},
+    #[returns(SimulateBondWithSwapResponse)]
+    SimulateBondWithSwap {
+        offer: Coin,
+        swap_operations: Vec<SwapOperation>,
+    },
+}
+
+#[cw_serde]
+pub struct SupportedBondDenomsResponse {
+    pub base_denom: String,
+    // (port, channel) LSM shares are accepted through, including the default one
+    pub lsm_share_paths: Vec<(String, String)>,
+    // validators whose LSM shares are accepted
+    pub lsm_share_validators: Vec<String>,
+}
+
+#[cw_serde]
+pub struct CollectedFeesResponse {
+    // accrued fees, not claimed yet
+    pub collected: Vec<FeeItem>,
+    // claimed fees waiting to be transferred
+    pub to_claim: Vec<FeeItem>,
+}
+
+#[cw_serde]
+pub struct NonNativeRewardsConsistencyResponse {
+    // denoms with receivers set in core, but not queried by the puppeteer
+    pub missing_in_puppeteer: Vec<String>,
+    // denoms queried by the puppeteer, but without receivers in core
+    pub missing_in_core: Vec<String>,
+}
+
+#[cw_serde]
+pub struct NonNativeRewardsRoutingResponseItem {
+    pub denom: String,
+    // current ICA balance of the denom
+    pub balance: Uint128,
+    pub fee: Uint128,
+    pub routes: Vec<NonNativeRewardsRouteAmount>,
+}
+
+#[cw_serde]
+pub struct NonNativeRewardsRouteAmount {
+    pub destination: NonNativeRewardsDestination,
+    // sent on the next rewards transfer
+    pub transfer: Uint128,
+    // kept on the ICA until the route threshold is reached
+    pub held: Uint128,
+}
+
+#[cw_serde]
+pub struct ReferrerResponse {
+    pub address: String,
+    pub bonded_amount: Uint128,
+    pub bonds_count: u64,
+    // protocol fee (remote denom) credited to the referrer
+    pub fee_credit: Uint128,
+}
+
+#[cw_serde]
+pub struct SimulateBondWithSwapResponse {
+    // base denom amount the router is expected to return
+    pub base_denom_amount: Uint128,
+    pub ld_amount: Uint128,
+    pub exchange_rate: Decimal,
 }
 
 #[pausable]
 #[cw_ownable_execute]
 #[cw_serde]
 pub enum ExecuteMsg {
-    Bond { receiver: Option<String> },
+    Bond {
+        receiver: Option<String>,
+        r#ref: Option<String>,
+        // the ld token is minted as CW20 through the wrapper
+        #[serde(default)]
+        mint_cw20: bool,
+    },
+    BondWithSwap {
+        swap_operations: Vec<SwapOperation>,
+        min_ld_out: Uint128,
+        receiver: Option<String>,
+        r#ref: Option<String>,
+    },
     Unbond {},
+    // base denom proceeds of exchanged rewards, staked on the next idle tick
+    CompoundRewards {},
     //permissioned
-    UpdateConfig { new_config: Box<ConfigOptional> },
-    UpdateNonNativeRewardsReceivers { items: Vec<NonNativeRewardsItem> },
+    UpdateConfig {
+        new_config: Box<ConfigOptional>,
+    },
+    UpdateNonNativeRewardsReceivers {
+        items: Vec<NonNativeRewardsItem>,
+    },
+    UpdateLSMSharePaths {
+        paths: Vec<(String, String)>,
+    },
+    UpdateFeeRecipients {
+        recipients: Vec<FeeRecipient>,
+    },
+    ClaimFees {},
+    // moves the referral fee credit of the sender to the fees claimable by the remote receiver
+    ClaimReferralFee {
+        receiver: String,
+    },
+    AddExchangeRateSubscriber {
+        address: String,
+        gas_limit: u64,
+    },
+    RemoveExchangeRateSubscriber {
+        address: String,
+    },
+    AddLifecycleHook {
+        address: String,
+        gas_limit: u64,
+    },
+    RemoveLifecycleHook {
+        address: String,
+    },
     Tick {},
     PuppeteerHook(Box<ResponseHookMsg>),
+    PumpHook(crate::msg::pump::PumpTransferHookMsg),
     ResetBondedAmount {},
+    // marks withdrawing batches as withdrawn once their transfer is confirmed
+    ReconcileWithdrawingBatches {
+        batch_ids: Vec<u128>,
+    },
+    // acknowledges failed pump transfers once their funds are recovered
+    ClearFailedPumpTransfers {
+        amount: Uint128,
+    },
+}
+
+// sent to the exchange rate subscribers
+#[cw_serde]
+pub enum ExchangeRateSubscriberMsg {
+    ExchangeRateUpdated { rate: Decimal, height: u64 },
+}
+
+// sent to the lifecycle hooks
+#[cw_serde]
+pub enum LifecycleHookMsg {
+    OnBond {
+        sender: String,
+        receiver: String,
+        amount: Coin,
+        ld_minted: Uint128,
+    },
+    OnUnbond {
+        sender: String,
+        batch_id: u128,
+        token_id: String,
+        ld_amount: Uint128,
+        expected_amount: Uint128,
+    },
+    // sent by the withdrawal manager
+    OnWithdraw {
+        token_id: String,
+        batch_id: u128,
+        receiver: String,
+        amount: Uint128,
+    },
 }
 
 #[cw_serde]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {}
 
 impl From<InstantiateMsg> for Config {
     fn from(val: InstantiateMsg) -> Self {
@@ -109,6 +295,14 @@ impl From<InstantiateMsg> for Config {
             fee_address: val.fee_address,
             emergency_address: val.emergency_address,
             min_stake_amount: val.min_stake_amount,
+            router_contract: val.router_contract,
+            referral_fee_share: val.referral_fee_share,
+            insurance_contract: val.insurance_contract,
+            insurance_fee_share: val.insurance_fee_share,
+            insurance_fee_channel: val.insurance_fee_channel,
+            exchange_rate_notify_delta: val.exchange_rate_notify_delta,
+            pump_contract: val.pump_contract,
+            cw20_wrapper_contract: val.cw20_wrapper_contract,
         }
     }
 }
@@ -0,0 +1,107 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Binary, Uint128};
+use cw20::{Expiration, Logo};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub ld_denom: String,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub marketing: Option<cw20::InstantiateMarketingInfo>,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    // mints the CW20 one to one for the attached ld denom
+    Wrap {
+        recipient: Option<String>,
+    },
+    // burns the CW20 and releases the same amount of the ld denom
+    Unwrap {
+        amount: Uint128,
+        recipient: Option<String>,
+    },
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    // same as unwrap to the sender
+    Burn {
+        amount: Uint128,
+    },
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    // the ld denom is released to the spender
+    BurnFrom {
+        owner: String,
+        amount: Uint128,
+    },
+    UpdateMarketing {
+        project: Option<String>,
+        description: Option<String>,
+        marketing: Option<String>,
+    },
+    UploadLogo(Logo),
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(crate::state::cw20_wrapper::Config)]
+    Config {},
+    #[returns(cw20::BalanceResponse)]
+    Balance { address: String },
+    #[returns(cw20::TokenInfoResponse)]
+    TokenInfo {},
+    #[returns(cw20::AllowanceResponse)]
+    Allowance { owner: String, spender: String },
+    #[returns(cw20::AllAllowancesResponse)]
+    AllAllowances {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(cw20::AllSpenderAllowancesResponse)]
+    AllSpenderAllowances {
+        spender: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(cw20::AllAccountsResponse)]
+    AllAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(cw20::MarketingInfoResponse)]
+    MarketingInfo {},
+    #[returns(cw20::DownloadLogoResponse)]
+    DownloadLogo {},
+}
+
+#[cw_serde]
+pub enum MigrateMsg {}
@@ -0,0 +1,52 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Coin, Decimal, Uint128};
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Changing `from_denom` or `to_denom` clears the stored routes.
+    UpdateConfig {
+        owner: Option<String>,
+        core_contract: Option<String>,
+        from_denom: Option<String>,
+        to_denom: Option<String>,
+        min_rewards: Option<Uint128>,
+        max_spread: Option<Decimal>,
+    },
+    UpdateRoutes {
+        routes: Vec<Vec<String>>,
+    },
+    Exchange {},
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(crate::msg::reward_handler::SimulateExchangeResponse)]
+    SimulateExchange { amount: Coin },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub owner: String,
+    pub core_contract: String,
+    pub from_denom: String,
+    pub to_denom: String,
+    pub min_rewards: Uint128,
+    pub max_spread: Decimal,
+    pub routes: Vec<Vec<String>>,
+}
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub owner: String,
+    pub core_contract: String,
+    pub from_denom: String,
+    pub to_denom: String,
+    pub min_rewards: Uint128,
+    pub max_spread: Decimal,
+}
+
+#[cw_serde]
+pub enum MigrateMsg {}
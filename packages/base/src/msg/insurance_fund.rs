@@ -0,0 +1,62 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Decimal, Uint128};
+use cw_ownable::{cw_ownable_execute, cw_ownable_query};
+
+use crate::state::insurance_fund::{Config, Payout};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub owner: String,
+    pub core_contract: String,
+    pub withdrawal_manager_contract: String,
+    pub base_denom: String,
+    pub max_payout_per_batch: Uint128,
+}
+
+#[cw_ownable_execute]
+#[cw_serde]
+pub enum ExecuteMsg {
+    UpdateConfig {
+        core_contract: Option<String>,
+        withdrawal_manager_contract: Option<String>,
+        max_payout_per_batch: Option<Uint128>,
+    },
+    Deposit {},
+    // core only: pays `amount` to the withdrawal manager on behalf of the batch
+    CoverShortfall {
+        batch_id: u128,
+        amount: Uint128,
+    },
+}
+
+#[cw_ownable_query]
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(Config)]
+    Config {},
+    #[returns(Uint128)]
+    Balance {},
+    // amount the fund is able to pay for the given shortfall
+    #[returns(Uint128)]
+    AvailableCoverage { shortfall: Uint128 },
+    #[returns(Vec<Payout>)]
+    Payouts {
+        start_after: Option<u128>,
+        limit: Option<u32>,
+    },
+    #[returns(CoverageRatioResponse)]
+    CoverageRatio {},
+}
+
+#[cw_serde]
+pub struct CoverageRatioResponse {
+    pub balance: Uint128,
+    pub total_bonded: Uint128,
+    pub total_paid_out: Uint128,
+    // balance / total_bonded
+    pub ratio: Decimal,
+}
+
+#[cw_serde]
+pub enum MigrateMsg {}
@@ -1,7 +1,10 @@
 pub mod astroport_exchange_handler;
 pub mod core;
+pub mod cw20_wrapper;
+pub mod dex_exchange_handler;
 pub mod distribution;
 pub mod hook_tester;
+pub mod insurance_fund;
 pub mod proposal_votes;
 pub mod provider_proposals;
 pub mod pump;
@@ -14,5 +17,6 @@ mod tests;
 pub mod token;
 pub mod validatorset;
 pub mod validatorsstats;
+pub mod voucher_marketplace;
 pub mod withdrawal_manager;
 pub mod withdrawal_voucher;
@@ -1,4 +1,4 @@
-use crate::state::pump::{IBCFees, PumpTimeout};
+use crate::state::pump::{IBCFees, PumpRoute, PumpSweepConfig, PumpTimeout};
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::Coin;
 
@@ -9,6 +9,12 @@ pub enum QueryMsg {
     Config {},
     #[returns(drop_helpers::ica::IcaState)]
     Ica {},
+    #[returns(Vec<(String, crate::state::pump::PumpRoute)>)]
+    Routes {},
+    #[returns(Vec<((String, u64), crate::state::pump::PumpTransfer)>)]
+    PendingTransfers {},
+    #[returns(Option<(crate::msg::puppeteer::MultiBalances, u64, cosmwasm_std::Timestamp)>)]
+    IcaBalances {},
 }
 
 #[cw_serde]
@@ -32,6 +38,9 @@ pub struct UpdateConfigMsg {
     pub ibc_fees: Option<IBCFees>,
     pub timeout: Option<PumpTimeout>,
     pub local_denom: Option<String>,
+    pub max_retries: Option<u32>,
+    pub core_contract: Option<String>,
+    pub sweep: Option<PumpSweepConfig>,
 }
 
 #[cw_serde]
@@ -40,6 +49,10 @@ pub enum ExecuteMsg {
     Push { coins: Vec<Coin> },
     Refund {},
     UpdateConfig { new_config: Box<UpdateConfigMsg> },
+    SetRoute { denom: String, route: PumpRoute },
+    RemoveRoute { denom: String },
+    RegisterBalancesQuery {},
+    Sweep {},
 }
 
 #[cw_serde]
@@ -53,6 +66,23 @@ pub struct InstantiateMsg {
     pub timeout: PumpTimeout,
     pub local_denom: String,
     pub owner: Option<String>,
+    pub max_retries: u32,
+    pub core_contract: Option<String>,
+    pub sweep: Option<PumpSweepConfig>,
+}
+
+// sent to the core contract once a transfer is settled
+#[cw_serde]
+pub enum ReceiverExecuteMsg {
+    PumpHook(PumpTransferHookMsg),
+}
+
+#[cw_serde]
+pub struct PumpTransferHookMsg {
+    pub amount: Coin,
+    pub receiver: String,
+    // false once the retries are exhausted
+    pub succeeded: bool,
 }
 
 #[cw_serde]
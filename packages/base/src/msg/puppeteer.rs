@@ -52,6 +52,9 @@ pub enum ExecuteMsg {
     RegisterNonNativeRewardsBalancesQuery {
         denoms: Vec<String>,
     },
+    RegisterLiquidStakingCapsQuery {
+        validators: Vec<String>,
+    },
     SetFees {
         recv_fee: Uint128,
         ack_fee: Uint128,
@@ -79,6 +82,17 @@ pub enum ExecuteMsg {
     TokenizeShare {
         validator: String,
         amount: Uint128,
+        tokenized_share_owner: Option<String>,
+        timeout: Option<u64>,
+        reply_to: String,
+    },
+    TransferTokenizeShareRecord {
+        record_id: u64,
+        new_owner: String,
+        timeout: Option<u64>,
+        reply_to: String,
+    },
+    DisableTokenizeShares {
         timeout: Option<u64>,
         reply_to: String,
     },
@@ -91,9 +105,14 @@ pub enum ExecuteMsg {
         timeout: u64,
         reason: IBCTransferReason,
         reply_to: String,
+        // (port, channel) to transfer over instead of the configured one
+        #[serde(default)]
+        source_path: Option<(String, String)>,
     },
     Transfer {
         items: Vec<(String, cosmwasm_std::Coin)>,
+        #[serde(default)]
+        ibc_items: Vec<drop_puppeteer_base::msg::IBCTransferItem>,
         timeout: Option<u64>,
         reply_to: String,
     },
@@ -136,6 +155,7 @@ pub type Height = u64;
 
 pub type DelegationsResponse = (Delegations, Height, Timestamp);
 pub type BalancesResponse = (Balances, Height, Timestamp);
+pub type LiquidStakingCapsResponse = (LiquidStakingCaps, Height, Timestamp);
 
 #[cw_serde]
 pub struct FeesResponse {
@@ -154,10 +174,14 @@ pub enum QueryExtMsg {
     Balances {},
     #[returns(BalancesResponse)]
     NonNativeRewardsBalances {},
+    #[returns(Vec<String>)]
+    NonNativeRewardsDenoms {},
     #[returns(FeesResponse)]
     Fees {},
     #[returns(Vec<drop_puppeteer_base::state::UnbondingDelegation>)]
     UnbondingDelegations {},
+    #[returns(Option<LiquidStakingCapsResponse>)]
+    LiquidStakingCaps {},
 }
 
 #[cw_serde]
@@ -299,3 +323,148 @@ impl PuppeteerReconstruct for MultiBalances {
         Ok(MultiBalances { coins })
     }
 }
+
+// Only the fields of the LSM staking params and validator we need to track the caps,
+// prost skips all the other ones on decoding.
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct LiquidStakingParams {
+    #[prost(string, tag = "7")]
+    pub validator_bond_factor: String,
+    #[prost(string, tag = "8")]
+    pub global_liquid_staking_cap: String,
+    #[prost(string, tag = "9")]
+    pub validator_liquid_staking_cap: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct LiquidStakingValidator {
+    #[prost(string, tag = "1")]
+    pub operator_address: String,
+    #[prost(string, tag = "5")]
+    pub tokens: String,
+    #[prost(string, tag = "6")]
+    pub delegator_shares: String,
+    #[prost(string, tag = "11")]
+    pub total_validator_bond_shares: String,
+    #[prost(string, tag = "12")]
+    pub total_liquid_shares: String,
+}
+
+#[cw_serde]
+pub struct ValidatorLiquidStaking {
+    pub valoper_address: String,
+    pub tokens: Uint128,
+    pub delegator_shares: Decimal,
+    pub total_validator_bond_shares: Decimal,
+    pub total_liquid_shares: Decimal,
+}
+
+#[cw_serde]
+pub struct LiquidStakingCaps {
+    // None means the validator bond cap is disabled on the remote chain
+    pub validator_bond_factor: Option<Decimal>,
+    pub global_liquid_staking_cap: Decimal,
+    pub validator_liquid_staking_cap: Decimal,
+    pub total_liquid_staked_tokens: Uint128,
+    pub total_bonded_tokens: Uint128,
+    pub validators: Vec<ValidatorLiquidStaking>,
+}
+
+// sdk.Dec is encoded in protobuf as a string of atomics and a negative value
+// is used by the LSM to disable a cap, so we map it to None
+fn parse_proto_dec(value: &str) -> NeutronResult<Option<Decimal>> {
+    if value.is_empty() || value.starts_with('-') {
+        return Ok(None);
+    }
+    Ok(Some(Decimal::from_atomics(
+        Uint128::from_str(value)?,
+        DECIMAL_PLACES,
+    )?))
+}
+
+// params subspace (sdk < 0.47) keeps sdk.Dec as an amino json string
+fn parse_json_dec(value: &[u8]) -> NeutronResult<Option<Decimal>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let value: String = from_json(value)?;
+    if value.starts_with('-') {
+        return Ok(None);
+    }
+    Ok(Some(Decimal::from_str(&value)?))
+}
+
+impl PuppeteerReconstruct for LiquidStakingCaps {
+    fn reconstruct(storage_values: &[StorageValue], version: &str) -> NeutronResult<Self> {
+        let version = version_to_u32(version)?;
+        // staking params are stored as one value since 0.47.0 and as
+        // three separate params subspace values before
+        let params_len = if version >= version_to_u32("0.47.0")? {
+            1
+        } else {
+            3
+        };
+        if storage_values.len() < params_len + 2 {
+            return Err(NeutronError::InvalidQueryResultFormat(format!(
+                "storage_values length is {}",
+                storage_values.len()
+            )));
+        }
+        let (validator_bond_factor, global_liquid_staking_cap, validator_liquid_staking_cap) =
+            if params_len == 1 {
+                let params = LiquidStakingParams::decode(storage_values[0].value.as_slice())?;
+                (
+                    parse_proto_dec(&params.validator_bond_factor)?,
+                    parse_proto_dec(&params.global_liquid_staking_cap)?,
+                    parse_proto_dec(&params.validator_liquid_staking_cap)?,
+                )
+            } else {
+                (
+                    parse_json_dec(&storage_values[0].value)?,
+                    parse_json_dec(&storage_values[1].value)?,
+                    parse_json_dec(&storage_values[2].value)?,
+                )
+            };
+        let total_liquid_staked_tokens = match &storage_values[params_len].value {
+            value if value.is_empty() => Uint128::zero(),
+            value => Uint128::from_str(&String::from_utf8(value.to_vec()).map_err(|_| {
+                NeutronError::InvalidQueryResultFormat("Invalid utf8".to_string())
+            })?)?,
+        };
+        let total_bonded_tokens = match &storage_values[params_len + 1].value {
+            value if value.is_empty() => Uint128::zero(),
+            value if version >= version_to_u32("0.47.0")? => {
+                Uint128::from_str(&String::from_utf8(value.to_vec()).map_err(|_| {
+                    NeutronError::InvalidQueryResultFormat("Invalid utf8".to_string())
+                })?)?
+            }
+            value => Uint128::from_str(CosmosCoin::decode(value.as_slice())?.amount.as_str())?,
+        };
+        let mut validators = Vec::with_capacity(storage_values.len() - params_len - 2);
+        for kv in &storage_values[params_len + 2..] {
+            if kv.value.is_empty() {
+                continue;
+            }
+            let validator = LiquidStakingValidator::decode(kv.value.as_slice())?;
+            validators.push(ValidatorLiquidStaking {
+                valoper_address: validator.operator_address,
+                tokens: Uint128::from_str(&validator.tokens)?,
+                delegator_shares: parse_proto_dec(&validator.delegator_shares)?.unwrap_or_default(),
+                total_validator_bond_shares: parse_proto_dec(
+                    &validator.total_validator_bond_shares,
+                )?
+                .unwrap_or_default(),
+                total_liquid_shares: parse_proto_dec(&validator.total_liquid_shares)?
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(LiquidStakingCaps {
+            validator_bond_factor,
+            global_liquid_staking_cap: global_liquid_staking_cap.unwrap_or(Decimal::one()),
+            validator_liquid_staking_cap: validator_liquid_staking_cap.unwrap_or(Decimal::one()),
+            total_liquid_staked_tokens,
+            total_bonded_tokens,
+            validators,
+        })
+    }
+}
@@ -1,6 +1,26 @@
-use cosmwasm_schema::cw_serde;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Coin, Uint128};
 
+/// Execute interface shared by all rewards exchange handlers. On `Exchange`
+/// the handler swaps only the reward denom funds attached to the call into the
+/// base denom and sends the proceeds to the caller (`info.sender`), along with
+/// any part of the funds it didn't swap.
 #[cw_serde]
 pub enum HandlerExecuteMsg {
     Exchange {},
 }
+
+/// Query interface shared by all rewards exchange handlers. Besides these
+/// queries every handler answers `Config {}` with its own config shape.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum HandlerQueryMsg {
+    #[returns(SimulateExchangeResponse)]
+    SimulateExchange { amount: Coin },
+}
+
+/// Amount of the base denom the handler would send to the caller.
+#[cw_serde]
+pub struct SimulateExchangeResponse {
+    pub amount: Uint128,
+}
@@ -1,4 +1,5 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Uint128;
 #[allow(unused_imports)]
 use drop_helpers::pause::PauseInfoResponse;
 use drop_macros::{pausable, pausable_query};
@@ -8,9 +9,17 @@ use crate::state::rewards_manager::HandlerConfig;
 #[pausable]
 #[cw_serde]
 pub enum ExecuteMsg {
-    UpdateConfig { owner: Option<String> },
-    AddHandler { config: HandlerConfig },
-    RemoveHandler { denom: String },
+    UpdateConfig {
+        owner: Option<String>,
+    },
+    AddHandler {
+        config: HandlerConfig,
+    },
+    /// Removes the handler at `address`, or every handler of the denom.
+    RemoveHandler {
+        denom: String,
+        address: Option<String>,
+    },
     ExchangeRewards {},
 }
 
@@ -22,17 +31,40 @@ pub enum QueryMsg {
     Config {},
     #[returns(Vec<HandlerConfig>)]
     Handlers {},
+    #[returns(Vec<RewardsHistoryResponseItem>)]
+    RewardsHistory {
+        start_after_epoch: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
 pub struct ConfigResponse {
     pub owner: String,
+    pub core_contract: String,
+    pub base_denom: String,
+    pub epoch_duration: u64,
+}
+
+#[cw_serde]
+pub struct RewardsHistoryResponseItem {
+    pub epoch: u64,
+    pub denom: String,
+    pub amount_in: Uint128,
+    pub amount_out: Uint128,
 }
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub owner: String,
+    pub core_contract: String,
+    pub base_denom: String,
+    pub epoch_duration: Option<u64>,
 }
 
 #[cw_serde]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {
+    pub core_contract: String,
+    pub base_denom: String,
+    pub epoch_duration: Option<u64>,
+}
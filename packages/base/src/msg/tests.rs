@@ -1,11 +1,14 @@
-use cosmwasm_std::{to_json_binary, Addr, Binary, Coin, Delegation, Uint128};
+use cosmwasm_std::{to_json_binary, Addr, Binary, Coin, Decimal, Delegation, Uint128};
 use drop_puppeteer_base::r#trait::PuppeteerReconstruct;
 use neutron_sdk::interchain_queries::v047::helpers::create_account_denom_balance_key;
 use neutron_sdk::NeutronResult;
 use neutron_sdk::{bindings::types::StorageValue, interchain_queries::helpers::decode_and_convert};
 use prost::Message;
 
-use super::puppeteer::{BalancesAndDelegations, MultiBalances};
+use super::puppeteer::{
+    BalancesAndDelegations, LiquidStakingCaps, LiquidStakingParams, LiquidStakingValidator,
+    MultiBalances, ValidatorLiquidStaking,
+};
 
 #[test]
 fn test_reconstruct_multi_balances() {
@@ -192,3 +195,59 @@ fn test_reconstruct_balance_and_delegations_with_delegations() {
         }
     }
 }
+
+#[test]
+fn test_reconstruct_liquid_staking_caps() {
+    let params = LiquidStakingParams {
+        validator_bond_factor: "-1000000000000000000".to_string(),
+        global_liquid_staking_cap: "250000000000000000".to_string(),
+        validator_liquid_staking_cap: "500000000000000000".to_string(),
+    };
+    let validator = LiquidStakingValidator {
+        operator_address: "cosmosvaloper1".to_string(),
+        tokens: "1000".to_string(),
+        delegator_shares: "1000000000000000000000".to_string(),
+        total_validator_bond_shares: "10000000000000000000".to_string(),
+        total_liquid_shares: "100000000000000000000".to_string(),
+    };
+    let storage_values: Vec<StorageValue> = vec![
+        StorageValue {
+            storage_prefix: "staking".to_string(),
+            key: Binary::default(),
+            value: params.encode_to_vec().into(),
+        },
+        StorageValue {
+            storage_prefix: "staking".to_string(),
+            key: Binary::default(),
+            value: Binary::from("200".as_bytes()),
+        },
+        StorageValue {
+            storage_prefix: "bank".to_string(),
+            key: Binary::default(),
+            value: Binary::from("5000".as_bytes()),
+        },
+        StorageValue {
+            storage_prefix: "staking".to_string(),
+            key: Binary::default(),
+            value: validator.encode_to_vec().into(),
+        },
+    ];
+    let result = LiquidStakingCaps::reconstruct(&storage_values, "0.47.10").unwrap();
+    assert_eq!(
+        result,
+        LiquidStakingCaps {
+            validator_bond_factor: None,
+            global_liquid_staking_cap: Decimal::percent(25),
+            validator_liquid_staking_cap: Decimal::percent(50),
+            total_liquid_staked_tokens: Uint128::new(200),
+            total_bonded_tokens: Uint128::new(5000),
+            validators: vec![ValidatorLiquidStaking {
+                valoper_address: "cosmosvaloper1".to_string(),
+                tokens: Uint128::new(1000),
+                delegator_shares: Decimal::from_ratio(1000u128, 1u128),
+                total_validator_bond_shares: Decimal::from_ratio(10u128, 1u128),
+                total_liquid_shares: Decimal::from_ratio(100u128, 1u128),
+            }],
+        }
+    );
+}
@@ -0,0 +1,76 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Coin, Decimal, Uint128};
+use cw721::Cw721ReceiveMsg;
+use cw_ownable::{cw_ownable_execute, cw_ownable_query};
+
+use crate::state::voucher_marketplace::{Config, Listing};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub owner: String,
+    pub core_contract: String,
+    pub voucher_contract: String,
+    pub base_denom: String,
+    pub ld_denom: String,
+}
+
+#[cw_ownable_execute]
+#[cw_serde]
+pub enum ExecuteMsg {
+    UpdateConfig {
+        core_contract: Option<String>,
+        voucher_contract: Option<String>,
+    },
+    // vouchers are listed by sending them to the marketplace
+    ReceiveNft(Cw721ReceiveMsg),
+    Buy {
+        token_id: String,
+        receiver: Option<String>,
+    },
+    Cancel {
+        token_id: String,
+    },
+}
+
+#[cw_serde]
+pub enum ReceiveNftMsg {
+    List { price: Coin },
+}
+
+#[cw_ownable_query]
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(Config)]
+    Config {},
+    #[returns(ListingResponse)]
+    Listing { token_id: String },
+    #[returns(ListingsResponse)]
+    Listings {
+        batch_id: Option<u128>,
+        min_discount: Option<Decimal>,
+        page_key: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct ListingsResponse {
+    pub listings: Vec<ListingResponse>,
+    // set when not all the listings are scanned, pages may hold less than `limit` of them
+    pub next_page_key: Option<String>,
+}
+
+#[cw_serde]
+pub struct ListingResponse {
+    pub listing: Listing,
+    // expected_amount adjusted by the batch slashing effect when it's known
+    pub expected_payout: Uint128,
+    // price converted to base_denom
+    pub price_in_base_denom: Uint128,
+    // 1 - price_in_base_denom / expected_payout, zero for listings above the payout
+    pub discount: Decimal,
+}
+
+#[cw_serde]
+pub enum MigrateMsg {}
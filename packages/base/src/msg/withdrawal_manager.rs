@@ -1,4 +1,5 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Uint128;
 use cw721::Cw721ReceiveMsg;
 #[allow(unused_imports)]
 use drop_helpers::pause::PauseInfoResponse;
@@ -18,6 +19,17 @@ pub struct InstantiateMsg {
 pub enum QueryMsg {
     #[returns(crate::state::withdrawal_manager::Config)]
     Config {},
+    #[returns(Vec<crate::state::withdrawal_manager::ClaimedVoucher>)]
+    ClaimedVouchers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(BatchReconciliationResponse)]
+    BatchReconciliation { batch_id: u128 },
+    #[returns(Option<crate::state::withdrawal_manager::EmergencySettlement>)]
+    EmergencySettlement { batch_id: u128 },
+    #[returns(BatchFundingResponse)]
+    BatchFunding { batch_id: u128 },
 }
 
 #[pausable]
@@ -29,6 +41,44 @@ pub enum ExecuteMsg {
         voucher_contract: Option<String>,
     },
     ReceiveNft(Cw721ReceiveMsg),
+    // vouchers have to be approved to the manager beforehand
+    WithdrawMany {
+        token_ids: Vec<String>,
+        receiver: Option<String>,
+    },
+    // owner settles a batch withdrawn in emergency mode with the recovered funds attached,
+    // funds sent to the manager in any other way are allocated to the regular batches
+    SettleEmergencyBatch {
+        batch_id: u128,
+        emergency_transfer: String,
+    },
+    // owner gives up on the rest of the funds of the first batch which is not fully funded,
+    // e.g. after its pump transfer failed, so the funding moves on to the next batches
+    SkipBatchFunding {
+        batch_id: u128,
+    },
+    // allocates the base_denom delivered to the manager since the last sync to the batches,
+    // withdrawals sync on their own
+    SyncFunding {},
+}
+
+#[cw_serde]
+pub struct BatchReconciliationResponse {
+    pub batch_id: u128,
+    pub unbonded_amount: Option<Uint128>,
+    pub paid_amount: Uint128,
+    // paid amount doesn't exceed the unbonded one
+    pub is_consistent: bool,
+}
+
+#[cw_serde]
+pub struct BatchFundingResponse {
+    pub batch_id: u128,
+    pub expected_amount: Uint128,
+    pub received_amount: Uint128,
+    pub paid_amount: Uint128,
+    // the vouchers of a skipped batch are paid pro rata of the received amount
+    pub skipped: bool,
 }
 
 #[cw_serde]
@@ -36,5 +86,10 @@ pub enum ReceiveNftMsg {
     Withdraw { receiver: Option<String> },
 }
 
+// the funding of the batches withdrawn before it was tracked is seeded page by page,
+// every next page starts where the previous one stopped
 #[cw_serde]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {
+    pub start_batch_id: Option<u128>,
+    pub limit: Option<u32>,
+}
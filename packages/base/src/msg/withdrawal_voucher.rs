@@ -1,13 +1,42 @@
-use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Empty;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{CustomMsg, Decimal, Empty, Uint128};
 pub use cw721_base::{ContractError, InstantiateMsg as CW721InstantiateMsg, MinterResponse};
 
-use crate::state::withdrawal_voucher::Metadata;
+use crate::state::{core::UnbondBatchStatus, withdrawal_voucher::Metadata};
 
 pub type Extension = Option<Metadata>;
 pub type InstantiateMsg = CW721InstantiateMsg;
 pub type ExecuteMsg = cw721_base::ExecuteMsg<Extension, Empty>;
-pub type QueryMsg = cw721_base::QueryMsg<Empty>;
+pub type QueryMsg = cw721_base::QueryMsg<QueryExtMsg>;
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryExtMsg {
+    // voucher metadata enriched with the live batch state from core
+    #[returns(VoucherInfoResponse)]
+    VoucherInfo { token_id: String },
+    #[returns(Vec<VoucherInfoResponse>)]
+    VouchersByOwner {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+impl CustomMsg for QueryExtMsg {}
+
+#[cw_serde]
+pub struct VoucherInfoResponse {
+    pub token_id: String,
+    pub owner: String,
+    pub metadata: Metadata,
+    pub batch_status: UnbondBatchStatus,
+    pub expected_release: u64,
+    pub slashing_effect: Option<Decimal>,
+    // known once the batch is unbonded
+    pub payout: Option<Uint128>,
+    pub claimable: bool,
+}
 
 #[cw_serde]
 pub enum MigrateMsg {}
@@ -1,6 +1,6 @@
 use astroport::router::SwapOperation;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Decimal, Uint128};
 use cw_storage_plus::Item;
 
 #[cw_serde]
@@ -12,7 +12,35 @@ pub struct Config {
     pub pair_contract: String,
     pub from_denom: String,
     pub min_rewards: Uint128,
+    pub max_spread: Decimal,
+    pub max_chunk_size: Option<Uint128>,
 }
 pub const CONFIG: Item<Config> = Item::new("config");
 
+/// Config stored before `max_spread` was added, only read during migration.
+#[cw_serde]
+pub struct LegacyConfig {
+    pub owner: String,
+    pub core_contract: String,
+    pub cron_address: String,
+    pub router_contract: String,
+    pub pair_contract: String,
+    pub from_denom: String,
+    pub min_rewards: Uint128,
+}
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+
 pub const SWAP_OPERATIONS: Item<Vec<SwapOperation>> = Item::new("swap_operations");
+
+/// Astroport TWAP oracle used to reject swaps when the spot price deviates
+/// from the time-weighted average by more than `max_deviation`. It's the only
+/// sandwich protection: without it `max_spread` is checked against a simulation
+/// of the very same spot price a sandwich moves. With swap operations set, the
+/// oracle must quote the asset the route ends in.
+#[cw_serde]
+pub struct PriceOracle {
+    pub contract: String,
+    pub max_deviation: Decimal,
+}
+
+pub const PRICE_ORACLE: Item<PriceOracle> = Item::new("price_oracle");
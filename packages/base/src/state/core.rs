@@ -32,6 +32,21 @@ pub struct Config {
     pub fee_address: Option<String>,
     pub emergency_address: Option<String>,
     pub min_stake_amount: Uint128,
+    pub router_contract: Option<String>,
+    // portion of the protocol fee credited to referrers
+    pub referral_fee_share: Option<Decimal>,
+    // covers slashing shortfalls of unbond batches
+    pub insurance_contract: Option<String>,
+    // portion of the protocol fee sent to the insurance contract
+    pub insurance_fee_share: Option<Decimal>,
+    // channel on the remote chain the insurance fee share is sent through
+    pub insurance_fee_channel: Option<String>,
+    // minimal relative exchange rate change pushed to the subscribers
+    pub exchange_rate_notify_delta: Option<Decimal>,
+    // reports settled transfers of the pump ICA
+    pub pump_contract: Option<String>,
+    // wraps the ld denom into a CW20 token on bond
+    pub cw20_wrapper_contract: Option<String>,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
@@ -96,6 +111,11 @@ pub const UNBOND_BATCH_ID: Item<u128> = Item::new("batches_ids");
 pub const TOTAL_LSM_SHARES: Item<u128> = Item::new("total_lsm_shares");
 pub const PENDING_LSM_SHARES: Map<String, (String, Uint128)> = Map::new("pending_lsm_shares");
 pub const LSM_SHARES_TO_REDEEM: Map<String, (String, Uint128)> = Map::new("lsm_shares_to_redeem");
+// local denom of a pending LSM share -> (port, channel) it arrived through
+pub const PENDING_LSM_SHARE_PATHS: Map<String, (String, String)> =
+    Map::new("pending_lsm_share_paths");
+// (port, channel) LSM shares of the remote chain are accepted through besides the config channel
+pub const LSM_SHARE_PATHS: Item<Vec<(String, String)>> = Item::new("lsm_share_paths");
 
 #[cw_serde]
 pub enum ContractState {
@@ -156,10 +176,31 @@ const TRANSITIONS: &[Transition<ContractState>] = &[
 #[cw_serde]
 pub struct NonNativeRewardsItem {
     pub denom: String,
+    // receiver on the remote chain, used when no routes are set
     pub address: String,
     pub min_amount: Uint128,
     pub fee_address: String,
     pub fee: Decimal,
+    // split of the rewards left after the fee, weights sum up to 1
+    #[serde(default)]
+    pub routes: Vec<NonNativeRewardsRoute>,
+}
+
+#[cw_serde]
+pub struct NonNativeRewardsRoute {
+    pub destination: NonNativeRewardsDestination,
+    pub weight: Decimal,
+    // the route share is held on the ICA until it reaches this amount
+    #[serde(default)]
+    pub min_amount: Uint128,
+}
+
+#[cw_serde]
+pub enum NonNativeRewardsDestination {
+    // bank transfer on the remote chain
+    Remote { address: String },
+    // IBC transfer from the ICA back to Neutron, e.g. to the rewards manager
+    Neutron { channel: String, address: String },
 }
 
 #[cw_serde]
@@ -169,18 +210,129 @@ pub struct FeeItem {
     pub amount: Uint128,
 }
 
+#[cw_serde]
+pub struct FeeRecipient {
+    pub address: String,
+    pub share: Decimal,
+}
+
+#[cw_serde]
+pub struct FeeHistoryItem {
+    pub address: String,
+    pub denom: String,
+    // lifetime amounts
+    pub collected: Uint128,
+    pub claimed: Uint128,
+}
+
 pub const FSM: Fsm<ContractState> = Fsm::new("machine_state", TRANSITIONS);
 pub const LAST_IDLE_CALL: Item<u64> = Item::new("last_tick");
 pub const LAST_ICA_BALANCE_CHANGE_HEIGHT: Item<u64> = Item::new("last_ica_balance_change_height");
 pub const LAST_PUPPETEER_RESPONSE: Item<drop_puppeteer_base::msg::ResponseHookMsg> =
     Item::new("last_puppeteer_response");
-pub const COLLECTED_FEES: Map<String, FeeItem> = Map::new("collected_fees");
+// (recipient, denom) -> fees collected and not claimed yet
+pub const COLLECTED_FEES: Map<(String, String), FeeItem> = Map::new("collected_fees_by_recipient");
+// denom -> fees collected for the single fee address, only read during migration
+pub const LEGACY_COLLECTED_FEES: Map<String, FeeItem> = Map::new("collected_fees");
+// (recipient, denom) -> claimed fees waiting to be transferred from the ICA
+pub const FEES_TO_CLAIM: Map<(String, String), FeeItem> = Map::new("fees_to_claim");
+// claimed fees included in the pending rewards transfer, settled once it succeeds
+pub const PENDING_FEE_TRANSFER: Item<Vec<FeeItem>> = Item::new("pending_fee_transfer");
+pub const FEE_HISTORY: Map<(String, String), FeeHistoryItem> = Map::new("fee_history");
+// insurance fee share (remote denom) held on the ICA until it is sent to Neutron
+pub const INSURANCE_FEES: Item<Uint128> = Item::new("insurance_fees");
+// insurance fees included in the pending rewards transfer
+pub const PENDING_INSURANCE_FEE: Item<Uint128> = Item::new("pending_insurance_fee");
+pub const FEE_RECIPIENTS: Item<Vec<FeeRecipient>> = Item::new("fee_recipients");
 pub const FAILED_BATCH_ID: Item<u128> = Item::new("failed_batch_id");
 pub const PRE_UNBONDING_BALANCE: Item<Uint128> = Item::new("pre_unbonding_balance");
 pub const PENDING_TRANSFER: Item<Uint128> = Item::new("pending_transfer");
 // Vec<(denom, address for pumping)>
 pub const NON_NATIVE_REWARDS_CONFIG: Item<Vec<NonNativeRewardsItem>> =
     Item::new("non_native_rewards_config");
+// (denom, route index) -> route share held on the ICA
+pub const NON_NATIVE_REWARDS_HELD: Map<(String, u32), Uint128> =
+    Map::new("non_native_rewards_held");
+// held amounts to be saved once the pending rewards transfer succeeds
+pub const PENDING_NON_NATIVE_REWARDS_HELD: Item<Vec<((String, u32), Uint128)>> =
+    Item::new("pending_non_native_rewards_held");
 pub const BONDED_AMOUNT: Item<Uint128> = Item::new("bonded_amount");
 pub const LAST_LSM_REDEEM: Item<u64> = Item::new("last_lsm_redeem");
 pub const EXCHANGE_RATE: Item<(Decimal, u64)> = Item::new("exchange_rate");
+
+// exchange rate subscriber or lifecycle hook
+#[cw_serde]
+pub struct Subscriber {
+    pub gas_limit: u64,
+    // notifications sent by core that failed so far, the OnWithdraw hooks are sent by the
+    // withdrawal manager and their failures are only logged there
+    pub failures: u64,
+}
+
+// address -> subscriber
+pub const EXCHANGE_RATE_SUBSCRIBERS: Map<String, Subscriber> =
+    Map::new("exchange_rate_subscribers");
+// (rate, height) last pushed to the subscribers
+pub const NOTIFIED_EXCHANGE_RATE: Item<(Decimal, u64)> = Item::new("notified_exchange_rate");
+// subscribers in the order of the notification submessages, used to match replies
+pub const PENDING_NOTIFICATIONS: Item<Vec<String>> = Item::new("pending_notifications");
+// address -> contract notified about bonds, unbonds and withdrawals
+pub const LIFECYCLE_HOOKS: Map<String, Subscriber> = Map::new("lifecycle_hooks");
+pub const PENDING_HOOK_CALLS: Item<Vec<String>> = Item::new("pending_hook_calls");
+// lifetime base denom amount received through CompoundRewards
+pub const COMPOUNDED_REWARDS: Item<Uint128> = Item::new("compounded_rewards");
+// amount of the pump transfers that ran out of retries and aren't recovered yet
+pub const FAILED_PUMP_TRANSFERS: Item<Uint128> = Item::new("failed_pump_transfers");
+
+#[cw_serde]
+pub struct BondWithSwapState {
+    pub sender: String,
+    pub receiver: String,
+    pub referrer: Option<String>,
+    pub min_ld_out: Uint128,
+    // contract base denom balance before the swap
+    pub base_denom_balance: Uint128,
+}
+
+pub const BOND_WITH_SWAP_STATE: Item<BondWithSwapState> = Item::new("bond_with_swap_state");
+
+#[cw_serde]
+pub struct ReferrerInfo {
+    pub address: String,
+    // cumulative amount bonded with this referrer
+    pub bonded_amount: Uint128,
+    pub bonds_count: u64,
+    // protocol fee (remote denom) credited to the referrer up to the checkpoint
+    pub fee_credit: Uint128,
+    pub fee_per_volume_checkpoint: Decimal,
+}
+
+pub struct ReferrerIndexes<'a> {
+    pub bonded_amount: MultiIndex<'a, u128, ReferrerInfo, String>,
+}
+
+impl<'a> IndexList<ReferrerInfo> for ReferrerIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<ReferrerInfo>> + '_> {
+        let v: Vec<&dyn Index<ReferrerInfo>> = vec![&self.bonded_amount];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn referrers_map<'a>() -> IndexedMap<'a, String, ReferrerInfo, ReferrerIndexes<'a>> {
+    IndexedMap::new(
+        "referrers",
+        ReferrerIndexes {
+            bonded_amount: MultiIndex::new(
+                |_pk, r| r.bonded_amount.u128(),
+                "referrers",
+                "referrers__bonded_amount",
+            ),
+        },
+    )
+}
+
+pub const TOTAL_REFERRED_AMOUNT: Item<Uint128> = Item::new("total_referred_amount");
+// referral fee share accumulated per unit of referred volume
+pub const REFERRAL_FEE_PER_VOLUME: Item<Decimal> = Item::new("referral_fee_per_volume");
+// referral fees accrued on the ICA and not claimed by the referrers yet
+pub const TOTAL_REFERRAL_FEE_CREDIT: Item<Uint128> = Item::new("total_referral_fee_credit");
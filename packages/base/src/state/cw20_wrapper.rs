@@ -0,0 +1,10 @@
+use cosmwasm_schema::cw_serde;
+use cw_storage_plus::Item;
+
+#[cw_serde]
+pub struct Config {
+    // tokenfactory denom held one to one against the CW20 supply
+    pub ld_denom: String,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
@@ -0,0 +1,19 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, Uint128};
+use cw_storage_plus::Item;
+
+#[cw_serde]
+pub struct Config {
+    pub owner: String,
+    pub core_contract: String,
+    pub from_denom: String,
+    pub to_denom: String,
+    pub min_rewards: Uint128,
+    pub max_spread: Decimal,
+}
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Multi-hop routes through the Neutron dex, each a list of denoms starting
+/// with `from_denom` and ending with `to_denom`. Without routes the handler
+/// swaps directly from `from_denom` to `to_denom`.
+pub const ROUTES: Item<Vec<Vec<String>>> = Item::new("routes");
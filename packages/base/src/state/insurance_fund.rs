@@ -0,0 +1,22 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Uint128;
+use cw_storage_plus::{Item, Map};
+
+#[cw_serde]
+pub struct Config {
+    pub core_contract: String,
+    pub withdrawal_manager_contract: String,
+    pub base_denom: String,
+    pub max_payout_per_batch: Uint128,
+}
+
+#[cw_serde]
+pub struct Payout {
+    pub batch_id: u128,
+    pub amount: Uint128,
+    pub timestamp: u64,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const PAYOUTS: Map<u128, Payout> = Map::new("payouts");
+pub const TOTAL_PAID_OUT: Item<Uint128> = Item::new("total_paid_out");
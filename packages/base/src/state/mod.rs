@@ -1,6 +1,9 @@
 pub mod astroport_exchange_handler;
 pub mod core;
+pub mod cw20_wrapper;
+pub mod dex_exchange_handler;
 pub mod hook_tester;
+pub mod insurance_fund;
 pub mod proposal_votes;
 pub mod provider_proposals;
 pub mod pump;
@@ -10,5 +13,6 @@ pub mod strategy;
 pub mod token;
 pub mod validatorset;
 pub mod validatorsstats;
+pub mod voucher_marketplace;
 pub mod withdrawal_manager;
 pub mod withdrawal_voucher;
@@ -1,8 +1,10 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Coin, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
 use drop_helpers::ica::Ica;
 
+use crate::msg::puppeteer::MultiBalances;
+
 #[cw_serde]
 pub struct IBCFees {
     pub recv_fee: Uint128,
@@ -28,8 +30,63 @@ pub struct Config {
     pub ibc_fees: IBCFees,
     pub timeout: PumpTimeout,
     pub local_denom: String,
+    // failed transfers are resubmitted up to this many times
+    #[serde(default)]
+    pub max_retries: u32,
+    // notified once a transfer is settled
+    pub core_contract: Option<Addr>,
+    pub sweep: Option<PumpSweepConfig>,
+}
+
+// balances of these denoms on the ICA are watched by an ICQ and pushed by anyone on sweep
+#[cw_serde]
+pub struct PumpSweepConfig {
+    pub denoms: Vec<String>,
+    pub update_period: u64,
+    pub sdk_version: String,
+}
+
+// transfer destination of a denom, used instead of the default one in the config
+#[cw_serde]
+pub struct PumpRoute {
+    pub dest_address: String,
+    pub dest_channel: String,
+    pub dest_port: String,
+    pub ibc_fees: IBCFees,
+    pub timeout: PumpTimeout,
+}
+
+#[cw_serde]
+pub enum PumpTransferStatus {
+    // waiting for the ICA tx acknowledgement
+    Pending,
+    // resubmitted once the ICA channel is reopened
+    TimedOut,
+    // retries are exhausted
+    Failed,
+}
+
+#[cw_serde]
+pub struct PumpTransfer {
+    pub amount: Coin,
+    pub route: PumpRoute,
+    pub status: PumpTransferStatus,
+    pub retries: u32,
 }
 
 pub const CONFIG: Item<Config> = Item::new("core");
+pub const ROUTES: Map<&str, PumpRoute> = Map::new("routes");
+// (channel, sequence) -> transfer
+pub const TRANSFERS: Map<(String, u64), PumpTransfer> = Map::new("transfers");
+// transfers waiting for their sequence in the submit tx reply
+pub const SUBMITTED_TRANSFERS: Item<Vec<PumpTransfer>> = Item::new("submitted_transfers");
+pub const BALANCES_QUERY_ID: Item<u64> = Item::new("balances_query_id");
+// (balances, remote height, local time) of the last ICQ result
+pub const ICA_BALANCES: Item<(MultiBalances, u64, Timestamp)> = Item::new("ica_balances");
+// remote height of the ICQ result swept last, the same balances are never pushed twice
+pub const LAST_SWEEP_HEIGHT: Item<u64> = Item::new("last_sweep_height");
+// remote height known to the light client when the last transfer was acknowledged,
+// older ICQ results can still hold the transferred funds
+pub const LAST_SETTLED_TRANSFER_HEIGHT: Item<u64> = Item::new("last_settled_transfer_height");
 pub const ICA: Ica = Ica::new("ica");
 pub const ICA_ID: &str = "drop_PUMP";
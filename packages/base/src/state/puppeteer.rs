@@ -4,7 +4,7 @@ use cw_storage_plus::Item;
 use drop_puppeteer_base::state::BaseConfig;
 use optfield::optfield;
 
-use crate::msg::puppeteer::{BalancesAndDelegations, MultiBalances};
+use crate::msg::puppeteer::{BalancesAndDelegations, LiquidStakingCaps, MultiBalances};
 
 #[optfield(pub ConfigOptional, attrs)]
 #[cw_serde]
@@ -39,10 +39,17 @@ pub enum KVQueryType {
     UnbondingDelegations,
     DelegationsAndBalance,
     NonNativeRewardsBalances,
+    LiquidStakingCaps,
 }
 
 pub const NON_NATIVE_REWARD_BALANCES: Item<(MultiBalances, u64, Timestamp)> =
     Item::new("non_native_reward_balances");
 
+// denoms the non-native rewards balances query is registered for
+pub const NON_NATIVE_REWARDS_DENOMS: Item<Vec<String>> = Item::new("non_native_rewards_denoms");
+
 pub const DELEGATIONS_AND_BALANCE: Item<(BalancesAndDelegations, u64, Timestamp)> =
     Item::new("delegations_and_balance");
+
+pub const LIQUID_STAKING_CAPS: Item<(LiquidStakingCaps, u64, Timestamp)> =
+    Item::new("liquid_staking_caps");
@@ -1,6 +1,20 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Uint128;
-use cw_storage_plus::Map;
+use cosmwasm_std::{Coin, Uint128};
+use cw_storage_plus::{Item, Map};
+
+pub const DEFAULT_EPOCH_DURATION: u64 = 86400;
+
+/// Handlers send the exchange proceeds back to this contract, which forwards
+/// them to the core contract in `base_denom`.
+#[cw_serde]
+pub struct Config {
+    pub core_contract: String,
+    pub base_denom: String,
+    /// Length of a rewards history epoch in seconds.
+    pub epoch_duration: u64,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
 
 #[cw_serde]
 pub struct HandlerConfig {
@@ -9,4 +23,30 @@ pub struct HandlerConfig {
     pub min_rewards: Uint128,
 }
 
-pub const REWARDS_HANDLERS: Map<String, HandlerConfig> = Map::new("rewards_handlers");
+/// Handlers registered per reward denom, keyed by (denom, handler address).
+/// When several handlers serve a denom the best-quoting one is used.
+pub const REWARDS_HANDLERS: Map<(String, String), HandlerConfig> =
+    Map::new("rewards_handlers_by_address");
+
+/// Pre-multi-handler storage with a single handler per denom, only read
+/// during migration.
+pub const LEGACY_REWARDS_HANDLERS: Map<String, HandlerConfig> = Map::new("rewards_handlers");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct RewardsHistoryItem {
+    pub amount_in: Uint128,
+    /// Base denom received for `amount_in`.
+    pub amount_out: Uint128,
+}
+
+/// Exchanged amounts keyed by (epoch, reward denom).
+pub const REWARDS_HISTORY: Map<(u64, String), RewardsHistoryItem> = Map::new("rewards_history");
+
+/// Funds attached to the in-flight handler calls, in submessage order. The
+/// whole balance of the denom is attached, what's left of it on reply is the
+/// part the handler sent back unswapped.
+pub const PENDING_EXCHANGES: Item<Vec<Coin>> = Item::new("pending_exchanges");
+
+/// Base denom balance before the next pending handler call.
+pub const BASE_DENOM_BALANCE: Item<Uint128> = Item::new("base_denom_balance");
@@ -0,0 +1,44 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Coin, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
+
+#[cw_serde]
+pub struct Config {
+    pub core_contract: String,
+    pub voucher_contract: String,
+    pub base_denom: String,
+    pub ld_denom: String,
+}
+
+#[cw_serde]
+pub struct Listing {
+    pub token_id: String,
+    pub seller: String,
+    pub batch_id: u128,
+    pub expected_amount: Uint128,
+    // in base_denom or ld_denom
+    pub price: Coin,
+    pub listed_at: u64,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+pub struct ListingIndexes<'a> {
+    pub batch_id: MultiIndex<'a, u128, Listing, String>,
+}
+
+impl<'a> IndexList<Listing> for ListingIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Listing>> + '_> {
+        let v: Vec<&dyn Index<Listing>> = vec![&self.batch_id];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn listings_map<'a>() -> IndexedMap<'a, String, Listing, ListingIndexes<'a>> {
+    IndexedMap::new(
+        "listings",
+        ListingIndexes {
+            batch_id: MultiIndex::new(|_pk, l| l.batch_id, "listings", "listings__batch_id"),
+        },
+    )
+}
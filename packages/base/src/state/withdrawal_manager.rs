@@ -1,5 +1,6 @@
 use cosmwasm_schema::cw_serde;
-use cw_storage_plus::Item;
+use cosmwasm_std::{Decimal, Uint128};
+use cw_storage_plus::{Item, Map};
 
 #[cw_serde]
 pub struct Config {
@@ -12,3 +13,42 @@ pub struct Config {
 pub type Cw721ReceiveMsg = cw721::Cw721ReceiveMsg;
 
 pub const CONFIG: Item<Config> = Item::new("config");
+
+#[cw_serde]
+pub struct ClaimedVoucher {
+    pub token_id: String,
+    pub batch_id: u128,
+    pub amount: Uint128,
+    pub receiver: String,
+    pub timestamp: u64,
+}
+
+pub const CLAIMED_VOUCHERS: Map<String, ClaimedVoucher> = Map::new("claimed_vouchers");
+#[cw_serde]
+pub struct EmergencySettlement {
+    pub batch_id: u128,
+    // amount recovered from the emergency address and attached to the settlement
+    pub recovered_amount: Uint128,
+    pub slashing_effect: Decimal,
+    // reference to the emergency transfer, e.g. its tx hash
+    pub emergency_transfer: String,
+    pub settled_at: u64,
+}
+
+pub const EMERGENCY_SETTLEMENTS: Map<u128, EmergencySettlement> = Map::new("emergency_settlements");
+// batch_id -> total amount paid out for the batch vouchers
+pub const BATCH_PAID_AMOUNTS: Map<u128, Uint128> = Map::new("batch_paid_amounts");
+// batch_id -> base_denom delivered by the pump for the batch
+pub const BATCH_RECEIVED_AMOUNTS: Map<u128, Uint128> = Map::new("batch_received_amounts");
+// delivered funds waiting for the next batch to be withdrawn
+pub const UNALLOCATED_FUNDS: Item<Uint128> = Item::new("unallocated_funds");
+// the first batch which is not fully funded yet
+pub const NEXT_FUNDED_BATCH_ID: Item<u128> = Item::new("next_funded_batch_id");
+// base_denom balance of the manager allocated to the batches and the unallocated funds,
+// anything above it is delivered since the last sync
+pub const ACCOUNTED_BALANCE: Item<Uint128> = Item::new("accounted_balance");
+// batch_id -> amount received by a batch the owner gave up funding, its vouchers are paid
+// pro rata of it
+pub const SKIPPED_BATCHES: Map<u128, Uint128> = Map::new("skipped_batches");
+// the next batch the paginated migration seeds the funding of, funding is frozen while it's set
+pub const MIGRATION_CURSOR: Item<u128> = Item::new("migration_cursor");
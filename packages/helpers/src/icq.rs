@@ -15,9 +15,17 @@ use neutron_sdk::{
     },
     NeutronResult,
 };
+use sha2::{Digest, Sha256};
 
 use crate::version::version_to_u32;
 
+// LSM staking store key holding the total amount of liquid staked tokens
+const TOTAL_LIQUID_STAKED_TOKENS_KEY: u8 = 0x85;
+const BONDED_POOL_NAME: &str = "bonded_tokens_pool";
+const KEY_VALIDATOR_BOND_FACTOR: &str = "ValidatorBondFactor";
+const KEY_GLOBAL_LIQUID_STAKING_CAP: &str = "GlobalLiquidStakingCap";
+const KEY_VALIDATOR_LIQUID_STAKING_CAP: &str = "ValidatorLiquidStakingCap";
+
 pub fn new_multiple_balances_query_msg(
     connection_id: String,
     address: String,
@@ -129,3 +137,75 @@ pub fn get_balance_and_delegations_keys(
 
     Ok(keys)
 }
+
+/// Query message to get LSM caps: staking params, total liquid staked tokens,
+/// bonded pool balance and liquid shares of the given validators
+pub fn new_liquid_staking_caps_query_msg(
+    connection_id: String,
+    denom: String,
+    validators: Vec<String>,
+    update_period: u64,
+    sdk_version: &str,
+) -> NeutronResult<NeutronMsg> {
+    let keys = get_liquid_staking_caps_keys(denom, validators, sdk_version)?;
+    NeutronMsg::register_interchain_query(QueryPayload::KV(keys), connection_id, update_period)
+}
+
+pub fn update_liquid_staking_caps_query_msg(
+    query_id: u64,
+    denom: String,
+    validators: Vec<String>,
+    sdk_version: &str,
+) -> NeutronResult<NeutronMsg> {
+    let keys = get_liquid_staking_caps_keys(denom, validators, sdk_version)?;
+    NeutronMsg::update_interchain_query(query_id, Some(keys), None, None)
+}
+
+pub fn get_liquid_staking_caps_keys(
+    denom: String,
+    validators: Vec<String>,
+    sdk_version: &str,
+) -> NeutronResult<Vec<KVKey>> {
+    let mut keys: Vec<KVKey> = Vec::with_capacity(validators.len() + 5);
+
+    // create KV keys to get LSM params from staking module params
+    if version_to_u32(sdk_version)? < version_to_u32("0.47.0")? {
+        for param in [
+            KEY_VALIDATOR_BOND_FACTOR,
+            KEY_GLOBAL_LIQUID_STAKING_CAP,
+            KEY_VALIDATOR_LIQUID_STAKING_CAP,
+        ] {
+            keys.push(KVKey {
+                path: PARAMS_STORE_KEY.to_string(),
+                key: Binary(create_params_store_key(STAKING_STORE_KEY, param)),
+            });
+        }
+    } else {
+        keys.push(KVKey {
+            path: STAKING_STORE_KEY.to_string(),
+            key: Binary(vec![STAKING_PARAMS_KEY]),
+        });
+    }
+
+    keys.push(KVKey {
+        path: STAKING_STORE_KEY.to_string(),
+        key: Binary(vec![TOTAL_LIQUID_STAKED_TOKENS_KEY]),
+    });
+
+    // bonded pool is a module account, its address is derived from the module name
+    let bonded_pool_addr = Sha256::digest(BONDED_POOL_NAME.as_bytes())[..20].to_vec();
+    keys.push(KVKey {
+        path: BANK_STORE_KEY.to_string(),
+        key: Binary(create_account_denom_balance_key(bonded_pool_addr, denom)?),
+    });
+
+    for v in validators {
+        let val_addr = decode_and_convert(&v)?;
+        keys.push(KVKey {
+            path: STAKING_STORE_KEY.to_string(),
+            key: Binary(create_validator_key(&val_addr)?),
+        });
+    }
+
+    Ok(keys)
+}
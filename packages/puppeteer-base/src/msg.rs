@@ -1,8 +1,8 @@
 use crate::{
     proto::{
-        MsgBeginRedelegateResponse, MsgDelegateResponse, MsgExecResponse, MsgIBCTransfer,
-        MsgRedeemTokensforSharesResponse, MsgSendResponse, MsgTokenizeSharesResponse,
-        MsgUndelegateResponse,
+        MsgBeginRedelegateResponse, MsgDelegateResponse, MsgDisableTokenizeSharesResponse,
+        MsgExecResponse, MsgIBCTransfer, MsgRedeemTokensforSharesResponse, MsgSendResponse,
+        MsgTokenizeSharesResponse, MsgTransferTokenizeShareRecordResponse, MsgUndelegateResponse,
     },
     state::RedeemShareItem,
 };
@@ -94,6 +94,8 @@ pub enum ResponseAnswer {
     BeginRedelegateResponse(MsgBeginRedelegateResponse),
     TokenizeSharesResponse(MsgTokenizeSharesResponse),
     RedeemTokensforSharesResponse(MsgRedeemTokensforSharesResponse),
+    TransferTokenizeShareRecordResponse(MsgTransferTokenizeShareRecordResponse),
+    DisableTokenizeSharesResponse(MsgDisableTokenizeSharesResponse),
     AuthzExecResponse(MsgExecResponse),
     IBCTransfer(MsgIBCTransfer),
     TransferResponse(MsgSendResponse),
@@ -129,6 +131,17 @@ pub enum Transaction {
         validator: String,
         denom: String,
         amount: u128,
+        // transactions stored before the owner was tracked have none
+        #[serde(default)]
+        tokenized_share_owner: Option<String>,
+    },
+    TransferTokenizeShareRecord {
+        interchain_account_id: String,
+        record_id: u64,
+        new_owner: String,
+    },
+    DisableTokenizeShares {
+        interchain_account_id: String,
     },
     RedeemShares {
         interchain_account_id: String,
@@ -149,9 +162,20 @@ pub enum Transaction {
     Transfer {
         interchain_account_id: String,
         items: Vec<(String, cosmwasm_std::Coin)>,
+        #[serde(default)]
+        ibc_items: Vec<IBCTransferItem>,
     },
 }
 
+// IBC transfer sent from the ICA to another chain
+#[cw_serde]
+pub struct IBCTransferItem {
+    // channel on the remote chain
+    pub channel: String,
+    pub receiver: String,
+    pub amount: cosmwasm_std::Coin,
+}
+
 #[cw_serde]
 pub enum IBCTransferReason {
     LSMShare,
@@ -28,6 +28,12 @@ pub struct MsgTokenizeSharesResponse {
 pub struct MsgRedeemTokensforSharesResponse {
     pub amount: Option<Coin>,
 }
+#[cw_serde]
+pub struct MsgTransferTokenizeShareRecordResponse {}
+
+#[cw_serde]
+pub struct MsgDisableTokenizeSharesResponse {}
+
 #[cw_serde]
 pub struct MsgExecResponse {
     pub results: Vec<Vec<u8>>,
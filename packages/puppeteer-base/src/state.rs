@@ -155,6 +155,7 @@ mod reply_msg {
     const KV_UNBONDING_DELEGATIONS_LOWER_BOUND: u64 = 5 << OFFSET;
     const KV_UNBONDING_DELEGATIONS_UPPER_BOUND: u64 =
         KV_UNBONDING_DELEGATIONS_LOWER_BOUND + u16::MAX as u64;
+    const KV_LIQUID_STAKING_CAPS: u64 = 6 << OFFSET;
 
     #[cosmwasm_schema::cw_serde]
     pub enum ReplyMsg {
@@ -163,6 +164,7 @@ mod reply_msg {
         KvDelegationsAndBalance,
         KvNonNativeRewardsBalances,
         KvUnbondingDelegations { validator_index: u16 },
+        KvLiquidStakingCaps,
     }
 
     impl ReplyMsg {
@@ -175,6 +177,7 @@ mod reply_msg {
                 ReplyMsg::KvUnbondingDelegations { validator_index } => {
                     KV_UNBONDING_DELEGATIONS_LOWER_BOUND | *validator_index as u64
                 }
+                ReplyMsg::KvLiquidStakingCaps => KV_LIQUID_STAKING_CAPS,
             }
         }
 
@@ -188,6 +191,7 @@ mod reply_msg {
                     ..=KV_UNBONDING_DELEGATIONS_UPPER_BOUND => Self::KvUnbondingDelegations {
                     validator_index: validator_index as u16,
                 },
+                KV_LIQUID_STAKING_CAPS => Self::KvLiquidStakingCaps,
                 _ => unreachable!(),
             }
         }
@@ -205,6 +209,10 @@ mod reply_msg {
                 ReplyMsg::from_reply_id(KV_DELEGATIONS_AND_BALANCE),
                 ReplyMsg::KvDelegationsAndBalance
             );
+            assert_eq!(
+                ReplyMsg::from_reply_id(KV_LIQUID_STAKING_CAPS),
+                ReplyMsg::KvLiquidStakingCaps
+            );
         }
 
         #[test]
@@ -215,6 +223,10 @@ mod reply_msg {
                 ReplyMsg::KvDelegationsAndBalance.to_reply_id(),
                 KV_DELEGATIONS_AND_BALANCE
             );
+            assert_eq!(
+                ReplyMsg::KvLiquidStakingCaps.to_reply_id(),
+                KV_LIQUID_STAKING_CAPS
+            );
         }
 
         mod kv_unbonding_delegations_from_reply_id {
@@ -281,7 +293,7 @@ mod reply_msg {
         #[test]
         #[should_panic]
         fn invalid_reply_id() {
-            ReplyMsg::from_reply_id(KV_UNBONDING_DELEGATIONS_UPPER_BOUND + 1);
+            ReplyMsg::from_reply_id(KV_LIQUID_STAKING_CAPS + 1);
         }
     }
 }